@@ -1,6 +1,6 @@
 use crate::{test_type, test_type_asymmetric};
 use postgres::{Client, NoTls};
-use postgres_types::{FromSql, ToSql, WrongType};
+use postgres_types::{FromSql, PostgresComposite, ToSql, WrongType};
 use std::error::Error;
 
 #[test]
@@ -346,3 +346,93 @@ fn generics() {
         },
     );
 }
+
+#[test]
+fn postgres_composite() {
+    #[derive(PostgresComposite, Debug, PartialEq)]
+    #[postgres(type_name = "inventory_item")]
+    struct InventoryItem {
+        item_name: String,
+        supplier: i32,
+        price: Option<f64>,
+    }
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+    conn.batch_execute(
+        "CREATE TYPE pg_temp.inventory_item AS (
+            name TEXT,
+            supplier_id INT,
+            price DOUBLE PRECISION
+        );",
+    )
+    .unwrap();
+
+    let item = InventoryItem {
+        item_name: "foobar".to_owned(),
+        supplier: 100,
+        price: Some(15.50),
+    };
+
+    let item_null = InventoryItem {
+        item_name: "foobar".to_owned(),
+        supplier: 100,
+        price: None,
+    };
+
+    test_type(
+        &mut conn,
+        "inventory_item",
+        &[
+            (item, "ROW('foobar', 100, 15.50)"),
+            (item_null, "ROW('foobar', 100, NULL)"),
+        ],
+    );
+}
+
+#[test]
+fn postgres_composite_tuple() {
+    #[derive(PostgresComposite, Debug, PartialEq)]
+    #[postgres(type_name = "point_3d")]
+    struct Point3d(f64, f64, Option<f64>);
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+    conn.batch_execute("CREATE TYPE pg_temp.point_3d AS (x FLOAT8, y FLOAT8, z FLOAT8);")
+        .unwrap();
+
+    test_type(
+        &mut conn,
+        "point_3d",
+        &[
+            (Point3d(1., 2., Some(3.)), "ROW(1, 2, 3)"),
+            (Point3d(1., 2., None), "ROW(1, 2, NULL)"),
+        ],
+    );
+}
+
+#[test]
+fn postgres_composite_wrong_order() {
+    #[derive(PostgresComposite, Debug, PartialEq)]
+    #[postgres(type_name = "inventory_item")]
+    struct InventoryItem {
+        supplier_id: i32,
+        name: String,
+    }
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+    conn.batch_execute(
+        "CREATE TYPE pg_temp.inventory_item AS (
+            name TEXT,
+            supplier_id INT
+        );",
+    )
+    .unwrap();
+
+    let item = InventoryItem {
+        supplier_id: 100,
+        name: "foobar".to_owned(),
+    };
+    let err = conn
+        .execute("SELECT $1::inventory_item", &[&item])
+        .unwrap_err();
+    assert!(err.source().unwrap().is::<WrongType>());
+}
@@ -1,6 +1,6 @@
 use crate::test_type;
 use postgres::{error::DbError, Client, NoTls};
-use postgres_types::{FromSql, ToSql, WrongType};
+use postgres_types::{FromSql, PostgresEnum, ToSql, WrongType};
 use std::error::Error;
 
 #[test]
@@ -201,3 +201,92 @@ fn wrong_name_and_allow_mismatch() {
     let err = conn.query_one("SELECT $1::foo", &[&Foo::Bar]).unwrap_err();
     assert!(err.source().unwrap().is::<WrongType>());
 }
+
+#[test]
+fn postgres_enum_text() {
+    #[derive(Debug, PostgresEnum, PartialEq)]
+    #[postgres(rename_all = "snake_case")]
+    enum Mood {
+        VerySad,
+        #[postgres(name = "okay")]
+        Ok,
+    }
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+
+    test_type(
+        &mut conn,
+        "text",
+        &[(Mood::VerySad, "'very_sad'"), (Mood::Ok, "'okay'")],
+    );
+    test_type(
+        &mut conn,
+        "varchar",
+        &[(Mood::VerySad, "'very_sad'"), (Mood::Ok, "'okay'")],
+    );
+
+    let err = conn
+        .query_one("SELECT 'happy'::text", &[])
+        .unwrap()
+        .try_get::<_, Mood>(0)
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid variant `happy`"));
+}
+
+#[test]
+fn postgres_enum_labels() {
+    #[derive(Debug, PostgresEnum, PartialEq)]
+    enum Mood {
+        #[postgres(name = "sad")]
+        Sad,
+        #[postgres(name = "happy")]
+        Happy,
+    }
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+    conn.execute("CREATE TYPE pg_temp.mood AS ENUM ('sad', 'happy')", &[])
+        .unwrap();
+    conn.execute(
+        "CREATE TYPE pg_temp.feeling AS ENUM ('sad', 'meh', 'happy')",
+        &[],
+    )
+    .unwrap();
+
+    test_type(
+        &mut conn,
+        "mood",
+        &[(Mood::Sad, "'sad'"), (Mood::Happy, "'happy'")],
+    );
+
+    let err = conn
+        .execute("SELECT $1::feeling", &[&Mood::Sad])
+        .unwrap_err();
+    assert!(err.source().unwrap().is::<WrongType>());
+}
+
+#[test]
+fn postgres_enum_name() {
+    #[derive(Debug, PostgresEnum, PartialEq)]
+    #[postgres(name = "mood")]
+    enum Mood {
+        Sad,
+        Happy,
+    }
+
+    let mut conn = Client::connect("user=postgres host=localhost port=5433", NoTls).unwrap();
+    conn.execute("CREATE TYPE pg_temp.mood AS ENUM ('Sad', 'Happy')", &[])
+        .unwrap();
+    conn.execute("CREATE TYPE pg_temp.feeling AS ENUM ('Sad', 'Happy')", &[])
+        .unwrap();
+
+    test_type(
+        &mut conn,
+        "mood",
+        &[(Mood::Sad, "'Sad'"), (Mood::Happy, "'Happy'")],
+    );
+
+    let err = conn
+        .execute("SELECT $1::feeling", &[&Mood::Sad])
+        .unwrap_err();
+    assert!(err.source().unwrap().is::<WrongType>());
+}
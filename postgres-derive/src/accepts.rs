@@ -99,3 +99,39 @@ pub fn composite_body(name: &str, trait_: &str, fields: &[Field]) -> TokenStream
         }
     }
 }
+
+pub fn text_enum_body(name: Option<&str>, variants: &[Variant]) -> TokenStream {
+    let variant_names = variants.iter().map(|v| &v.name);
+    let name_check = name.map(|name| {
+        quote! {
+            if type_.name() != #name {
+                return false;
+            }
+        }
+    });
+
+    quote! {
+        if *type_ == ::postgres_types::Type::TEXT
+            || *type_ == ::postgres_types::Type::VARCHAR
+            || *type_ == ::postgres_types::Type::UNKNOWN
+        {
+            return true;
+        }
+
+        #name_check
+
+        match *type_.kind() {
+            ::postgres_types::Kind::Enum(ref variants) => {
+                variants.iter().all(|v| {
+                    match &**v {
+                        #(
+                            #variant_names => true,
+                        )*
+                        _ => false,
+                    }
+                })
+            }
+            _ => false,
+        }
+    }
+}
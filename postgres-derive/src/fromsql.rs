@@ -144,7 +144,7 @@ fn transparent_body(ident: &Ident, field: &syn::Field) -> TokenStream {
     }
 }
 
-fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
+pub fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
     let variant_names = variants.iter().map(|v| &v.name);
     let idents = iter::repeat(ident);
     let variant_idents = variants.iter().map(|v| &v.ident);
@@ -233,7 +233,7 @@ fn composite_body(ident: &Ident, fields: &[Field]) -> TokenStream {
     }
 }
 
-fn build_generics(source: &Generics) -> (Generics, Lifetime) {
+pub fn build_generics(source: &Generics) -> (Generics, Lifetime) {
     // don't worry about lifetime name collisions, it doesn't make sense to derive FromSql on a struct with a lifetime
     let lifetime = Lifetime::new("'a", Span::call_site());
 
@@ -12,6 +12,8 @@ mod composites;
 mod enums;
 mod fromsql;
 mod overrides;
+mod postgres_composite;
+mod postgres_enum;
 mod tosql;
 
 #[proc_macro_derive(ToSql, attributes(postgres))]
@@ -31,3 +33,21 @@ pub fn derive_fromsql(input: TokenStream) -> TokenStream {
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
+
+#[proc_macro_derive(PostgresEnum, attributes(postgres))]
+pub fn derive_postgres_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    postgres_enum::expand_derive_postgres_enum(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(PostgresComposite, attributes(postgres))]
+pub fn derive_postgres_composite(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    postgres_composite::expand_derive_postgres_composite(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
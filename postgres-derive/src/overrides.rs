@@ -5,6 +5,7 @@ use crate::case::{RenameRule, RENAME_RULES};
 
 pub struct Overrides {
     pub name: Option<String>,
+    pub type_name: Option<String>,
     pub rename_all: Option<RenameRule>,
     pub transparent: bool,
     pub allow_mismatch: bool,
@@ -14,6 +15,7 @@ impl Overrides {
     pub fn extract(attrs: &[Attribute], container_attr: bool) -> Result<Overrides, Error> {
         let mut overrides = Overrides {
             name: None,
+            type_name: None,
             rename_all: None,
             transparent: false,
             allow_mismatch: false,
@@ -36,13 +38,20 @@ impl Overrides {
                     Meta::NameValue(meta) => {
                         let name_override = meta.path.is_ident("name");
                         let rename_all_override = meta.path.is_ident("rename_all");
+                        let type_name_override = meta.path.is_ident("type_name");
                         if !container_attr && rename_all_override {
                             return Err(Error::new_spanned(
                                 &meta.path,
                                 "rename_all is a container attribute",
                             ));
                         }
-                        if !name_override && !rename_all_override {
+                        if !container_attr && type_name_override {
+                            return Err(Error::new_spanned(
+                                &meta.path,
+                                "type_name is a container attribute",
+                            ));
+                        }
+                        if !name_override && !rename_all_override && !type_name_override {
                             return Err(Error::new_spanned(&meta.path, "unknown override"));
                         }
 
@@ -57,6 +66,8 @@ impl Overrides {
 
                         if name_override {
                             overrides.name = Some(value);
+                        } else if type_name_override {
+                            overrides.type_name = Some(value);
                         } else if rename_all_override {
                             let rename_rule = RenameRule::from_str(&value).ok_or_else(|| {
                                 Error::new_spanned(
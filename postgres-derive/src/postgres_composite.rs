@@ -0,0 +1,179 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DataStruct, DeriveInput, Error, Fields, Index, Member};
+
+use crate::composites::append_generic_bound;
+use crate::overrides::Overrides;
+use crate::{fromsql, tosql};
+
+pub fn expand_derive_postgres_composite(input: DeriveInput) -> Result<TokenStream, Error> {
+    let overrides = Overrides::extract(&input.attrs, true)?;
+
+    if overrides.transparent || overrides.allow_mismatch || overrides.rename_all.is_some() {
+        return Err(Error::new_spanned(
+            &input,
+            "#[derive(PostgresComposite)] only supports #[postgres(type_name = \"...\")]",
+        ));
+    }
+
+    let fields = match input.data {
+        Data::Struct(DataStruct {
+            fields: Fields::Named(ref fields),
+            ..
+        }) => fields.named.iter().collect::<Vec<_>>(),
+        Data::Struct(DataStruct {
+            fields: Fields::Unnamed(ref fields),
+            ..
+        }) => fields.unnamed.iter().collect::<Vec<_>>(),
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "#[derive(PostgresComposite)] may only be applied to structs with fields",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let type_name = overrides
+        .type_name
+        .or(overrides.name)
+        .unwrap_or_else(|| ident.to_string());
+    let num_fields = fields.len();
+    let indices = (0..num_fields).collect::<Vec<_>>();
+    let types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, f)| match f.ident {
+            Some(ref ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: idx as u32,
+                span: Span::call_site(),
+            }),
+        })
+        .collect::<Vec<_>>();
+    let temp_vars = (0..num_fields)
+        .map(|idx| format_ident!("__field{}", idx))
+        .collect::<Vec<_>>();
+
+    let accepts_body = |trait_: TokenStream| {
+        quote! {
+            if type_.name() != #type_name {
+                return false;
+            }
+
+            match *type_.kind() {
+                postgres_types::Kind::Composite(ref fields) => {
+                    if fields.len() != #num_fields {
+                        return false;
+                    }
+
+                    true #(
+                        && <#types as #trait_>::accepts(fields[#indices].type_())
+                    )*
+                }
+                _ => false,
+            }
+        }
+    };
+    let tosql_accepts_body = accepts_body(quote!(postgres_types::ToSql));
+
+    let tosql_generics = append_generic_bound(input.generics.to_owned(), &tosql::new_tosql_bound());
+    let (tosql_impl_generics, ty_generics, where_clause) = tosql_generics.split_for_impl();
+    let (fromsql_generics, lifetime) = fromsql::build_generics(&input.generics);
+    let (fromsql_impl_generics, _, _) = fromsql_generics.split_for_impl();
+    let fromsql_accepts_body = accepts_body(quote!(postgres_types::FromSql<#lifetime>));
+
+    let out = quote! {
+        impl#tosql_impl_generics postgres_types::ToSql for #ident#ty_generics #where_clause {
+            fn to_sql(&self,
+                      _type: &postgres_types::Type,
+                      buf: &mut postgres_types::private::BytesMut)
+                      -> std::result::Result<postgres_types::IsNull,
+                                             std::boxed::Box<dyn std::error::Error +
+                                                             std::marker::Sync +
+                                                             std::marker::Send>> {
+                let fields = match *_type.kind() {
+                    postgres_types::Kind::Composite(ref fields) => fields,
+                    _ => unreachable!(),
+                };
+
+                buf.extend_from_slice(&(#num_fields as i32).to_be_bytes());
+
+                #(
+                    let field = &fields[#indices];
+                    buf.extend_from_slice(&field.type_().oid().to_be_bytes());
+
+                    let base = buf.len();
+                    buf.extend_from_slice(&[0; 4]);
+                    let count = match postgres_types::ToSql::to_sql(&self.#members, field.type_(), buf)? {
+                        postgres_types::IsNull::Yes => -1,
+                        postgres_types::IsNull::No => {
+                            let len = buf.len() - base - 4;
+                            if len > i32::max_value() as usize {
+                                return std::result::Result::Err(
+                                    std::convert::Into::into("value too large to transmit"));
+                            }
+                            len as i32
+                        }
+                    };
+                    buf[base..base + 4].copy_from_slice(&count.to_be_bytes());
+                )*
+
+                std::result::Result::Ok(postgres_types::IsNull::No)
+            }
+
+            fn accepts(type_: &postgres_types::Type) -> bool {
+                #tosql_accepts_body
+            }
+
+            postgres_types::to_sql_checked!();
+        }
+
+        impl #fromsql_impl_generics postgres_types::FromSql<#lifetime> for #ident #ty_generics #where_clause {
+            fn from_sql(_type: &postgres_types::Type, buf: &#lifetime [u8])
+                        -> std::result::Result<#ident #ty_generics,
+                                               std::boxed::Box<dyn std::error::Error +
+                                                               std::marker::Sync +
+                                                               std::marker::Send>> {
+                let fields = match *_type.kind() {
+                    postgres_types::Kind::Composite(ref fields) => fields,
+                    _ => unreachable!(),
+                };
+
+                let mut buf = buf;
+                let num_fields = postgres_types::private::read_be_i32(&mut buf)?;
+                if num_fields as usize != #num_fields {
+                    return std::result::Result::Err(
+                        std::convert::Into::into(format!("invalid field count: {} vs {}", num_fields, #num_fields)));
+                }
+
+                #(
+                    let field = &fields[#indices];
+                    let oid = postgres_types::private::read_be_i32(&mut buf)? as u32;
+                    if oid != field.type_().oid() {
+                        return std::result::Result::Err(std::convert::Into::into(format!(
+                            "unexpected OID {} for field `{}`, expected {}",
+                            oid,
+                            field.name(),
+                            field.type_().oid(),
+                        )));
+                    }
+                    let #temp_vars = postgres_types::private::read_value(field.type_(), &mut buf)?;
+                )*
+
+                std::result::Result::Ok(#ident {
+                    #(
+                        #members: #temp_vars,
+                    )*
+                })
+            }
+
+            fn accepts(type_: &postgres_types::Type) -> bool {
+                #fromsql_accepts_body
+            }
+        }
+    };
+
+    Ok(out)
+}
@@ -0,0 +1,80 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error};
+
+use crate::accepts;
+use crate::composites::append_generic_bound;
+use crate::enums::Variant;
+use crate::overrides::Overrides;
+use crate::{fromsql, tosql};
+
+pub fn expand_derive_postgres_enum(input: DeriveInput) -> Result<TokenStream, Error> {
+    let overrides = Overrides::extract(&input.attrs, true)?;
+
+    if overrides.transparent || overrides.allow_mismatch {
+        return Err(Error::new_spanned(
+            &input,
+            "#[postgres(transparent)] and #[postgres(allow_mismatch)] are not allowed with #[derive(PostgresEnum)]",
+        ));
+    }
+
+    let variants = match input.data {
+        Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .map(|variant| Variant::parse(variant, overrides.rename_all))
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => {
+            return Err(Error::new_spanned(
+                input,
+                "#[derive(PostgresEnum)] may only be applied to enums",
+            ));
+        }
+    };
+
+    let ident = &input.ident;
+    let accepts_body = accepts::text_enum_body(overrides.name.as_deref(), &variants);
+    let to_sql_body = tosql::enum_body(ident, &variants);
+    let from_sql_body = fromsql::enum_body(ident, &variants);
+
+    let tosql_generics = append_generic_bound(input.generics.to_owned(), &tosql::new_tosql_bound());
+    let (tosql_impl_generics, ty_generics, where_clause) = tosql_generics.split_for_impl();
+    let (fromsql_generics, lifetime) = fromsql::build_generics(&input.generics);
+    let (fromsql_impl_generics, _, _) = fromsql_generics.split_for_impl();
+
+    let out = quote! {
+        impl#tosql_impl_generics postgres_types::ToSql for #ident#ty_generics #where_clause {
+            fn to_sql(&self,
+                      _type: &postgres_types::Type,
+                      buf: &mut postgres_types::private::BytesMut)
+                      -> std::result::Result<postgres_types::IsNull,
+                                             std::boxed::Box<dyn std::error::Error +
+                                                             std::marker::Sync +
+                                                             std::marker::Send>> {
+                #to_sql_body
+            }
+
+            fn accepts(type_: &postgres_types::Type) -> bool {
+                #accepts_body
+            }
+
+            postgres_types::to_sql_checked!();
+        }
+
+        impl #fromsql_impl_generics postgres_types::FromSql<#lifetime> for #ident #ty_generics #where_clause {
+            fn from_sql(_type: &postgres_types::Type, buf: &#lifetime [u8])
+                        -> std::result::Result<#ident #ty_generics,
+                                               std::boxed::Box<dyn std::error::Error +
+                                                               std::marker::Sync +
+                                                               std::marker::Send>> {
+                #from_sql_body
+            }
+
+            fn accepts(type_: &postgres_types::Type) -> bool {
+                #accepts_body
+            }
+        }
+    };
+
+    Ok(out)
+}
@@ -140,7 +140,7 @@ fn transparent_body() -> TokenStream {
     }
 }
 
-fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
+pub fn enum_body(ident: &Ident, variants: &[Variant]) -> TokenStream {
     let idents = iter::repeat(ident);
     let variant_idents = variants.iter().map(|v| &v.ident);
     let variant_names = variants.iter().map(|v| &v.name);
@@ -211,7 +211,7 @@ fn composite_body(fields: &[Field]) -> TokenStream {
     }
 }
 
-fn new_tosql_bound() -> TypeParamBound {
+pub fn new_tosql_bound() -> TypeParamBound {
     TypeParamBound::Trait(TraitBound {
         lifetimes: None,
         modifier: TraitBoundModifier::None,
@@ -31,6 +31,7 @@ pub const PORTAL_SUSPENDED_TAG: u8 = b's';
 pub const PARAMETER_STATUS_TAG: u8 = b'S';
 pub const PARAMETER_DESCRIPTION_TAG: u8 = b't';
 pub const ROW_DESCRIPTION_TAG: u8 = b'T';
+pub const NEGOTIATE_PROTOCOL_VERSION_TAG: u8 = b'v';
 pub const READY_FOR_QUERY_TAG: u8 = b'Z';
 
 #[derive(Debug, Copy, Clone)]
@@ -96,6 +97,7 @@ pub enum Message {
     DataRow(DataRowBody),
     EmptyQueryResponse,
     ErrorResponse(ErrorResponseBody),
+    NegotiateProtocolVersion(NegotiateProtocolVersionBody),
     NoData,
     NoticeResponse(NoticeResponseBody),
     NotificationResponse(NotificationResponseBody),
@@ -259,6 +261,16 @@ impl Message {
                 let status = buf.read_u8()?;
                 Message::ReadyForQuery(ReadyForQueryBody { status })
             }
+            NEGOTIATE_PROTOCOL_VERSION_TAG => {
+                let newest_minor_version = buf.read_i32::<BigEndian>()?;
+                let len = buf.read_u32::<BigEndian>()?;
+                let storage = buf.read_all();
+                Message::NegotiateProtocolVersion(NegotiateProtocolVersionBody {
+                    newest_minor_version,
+                    len,
+                    storage,
+                })
+            }
             tag => {
                 return Err(io::Error::new(
                     io::ErrorKind::InvalidInput,
@@ -663,6 +675,63 @@ impl<'a> ErrorField<'a> {
     }
 }
 
+pub struct NegotiateProtocolVersionBody {
+    newest_minor_version: i32,
+    len: u32,
+    storage: Bytes,
+}
+
+impl NegotiateProtocolVersionBody {
+    #[inline]
+    pub fn newest_minor_version(&self) -> i32 {
+        self.newest_minor_version
+    }
+
+    #[inline]
+    pub fn options(&self) -> ProtocolOptions<'_> {
+        ProtocolOptions {
+            buf: &self.storage,
+            remaining: self.len,
+        }
+    }
+}
+
+pub struct ProtocolOptions<'a> {
+    buf: &'a [u8],
+    remaining: u32,
+}
+
+impl<'a> FallibleIterator for ProtocolOptions<'a> {
+    type Item = &'a str;
+    type Error = io::Error;
+
+    #[inline]
+    fn next(&mut self) -> io::Result<Option<&'a str>> {
+        if self.remaining == 0 {
+            if self.buf.is_empty() {
+                return Ok(None);
+            } else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid message length: protocol options is not drained",
+                ));
+            }
+        }
+
+        self.remaining -= 1;
+        let end = find_null(self.buf, 0)?;
+        let option = get_str(&self.buf[..end])?;
+        self.buf = &self.buf[end + 1..];
+        Ok(Some(option))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.remaining as usize;
+        (len, Some(len))
+    }
+}
+
 pub struct NoticeResponseBody {
     storage: Bytes,
 }
@@ -181,6 +181,53 @@
 //!    Meh,
 //! }
 //! ```
+//!
+//! ## Text Enums
+//!
+//! The `PostgresEnum` derive implements both [`ToSql`] & [`FromSql`] for an enum by encoding each variant as its
+//! label. Unlike the enum implementations above, it is also compatible with `TEXT` and `VARCHAR` values, and with
+//! any Postgres enum type whose labels all correspond to a variant. The `#[postgres(name = "...")]` attribute
+//! restricts the accepted enum types to the named one, and variants can be renamed as described above:
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! use postgres_types::PostgresEnum;
+//!
+//! # #[cfg(feature = "derive")]
+//! #[derive(Debug, PostgresEnum)]
+//! #[postgres(rename_all = "snake_case")]
+//! enum Mood {
+//!     #[postgres(name = "sad")]
+//!     Unhappy,
+//!     VeryHappy,      // very_happy
+//! }
+//! ```
+//!
+//! ## Positional Composites
+//!
+//! The `PostgresComposite` derive implements both [`ToSql`] & [`FromSql`] for a struct by mapping its fields to the
+//! attributes of a composite type in declaration order, regardless of their names. Tuple structs are supported as
+//! well. The type name defaults to the struct name and can be set with `#[postgres(type_name = "...")]`. The type
+//! of each attribute must be compatible with the corresponding field, and the attribute OIDs of decoded values are
+//! checked against the type:
+//!
+//! ```sql
+//! CREATE TYPE point_3d AS (
+//!     x DOUBLE PRECISION,
+//!     y DOUBLE PRECISION,
+//!     z DOUBLE PRECISION
+//! );
+//! ```
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")]
+//! use postgres_types::PostgresComposite;
+//!
+//! # #[cfg(feature = "derive")]
+//! #[derive(Debug, PostgresComposite)]
+//! #[postgres(type_name = "point_3d")]
+//! struct Point3d(f64, f64, Option<f64>);
+//! ```
 #![warn(clippy::all, rust_2018_idioms, missing_docs)]
 use fallible_iterator::FallibleIterator;
 use postgres_protocol::types::{self, ArrayDimension};
@@ -195,7 +242,7 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[cfg(feature = "derive")]
-pub use postgres_derive::{FromSql, ToSql};
+pub use postgres_derive::{FromSql, PostgresComposite, PostgresEnum, ToSql};
 
 #[cfg(feature = "with-serde_json-1")]
 pub use crate::serde_json_1::Json;
@@ -12,11 +12,11 @@ use std::time::Duration;
 use tokio::runtime;
 #[doc(inline)]
 pub use tokio_postgres::config::{
-    ChannelBinding, Host, LoadBalanceHosts, SslMode, TargetSessionAttrs,
+    ChannelBinding, Host, LoadBalanceHosts, ReplicationMode, SslMode, TargetSessionAttrs,
 };
 use tokio_postgres::error::DbError;
 use tokio_postgres::tls::{MakeTlsConnect, TlsConnect};
-use tokio_postgres::{Error, Socket};
+use tokio_postgres::{ConnectionLifecycle, Error, PasswordProvider, Socket};
 
 /// Connection configuration.
 ///
@@ -81,6 +81,9 @@ use tokio_postgres::{Error, Socket};
 ///     `disable`, hosts and addresses will be tried in the order provided. If set to `random`, hosts will be tried
 ///     in a random order, and the IP addresses resolved from a hostname will also be tried in a random order. Defaults
 ///     to `disable`.
+/// * `replication` - Makes the connection a replication connection. If set to `database`, the connection can be used
+///     for logical replication from the database specified by `dbname`. If set to `true`, `on`, `yes` or `1`, the
+///     connection is used for physical replication. Defaults to `false`.
 ///
 /// ## Examples
 ///
@@ -179,6 +182,19 @@ impl Config {
         self.config.get_password()
     }
 
+    /// Sets the provider queried for the password to authenticate with.
+    ///
+    /// The provider is only used if no password has been set with the `password` method.
+    pub fn password_provider(&mut self, provider: Arc<dyn PasswordProvider>) -> &mut Config {
+        self.config.password_provider(provider);
+        self
+    }
+
+    /// Gets the password provider, if one has been set with the `password_provider` method.
+    pub fn get_password_provider(&self) -> Option<&Arc<dyn PasswordProvider>> {
+        self.config.get_password_provider()
+    }
+
     /// Sets the name of the database to connect to.
     ///
     /// Defaults to the user.
@@ -414,6 +430,101 @@ impl Config {
         self.config.get_load_balance_hosts()
     }
 
+    /// Sets the `idle_in_transaction_session_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established. Sessions left idle
+    /// within an open transaction for longer than this are terminated by the server.
+    pub fn idle_in_transaction_session_timeout(&mut self, millis: u64) -> &mut Config {
+        self.config.idle_in_transaction_session_timeout(millis);
+        self
+    }
+
+    /// Gets the `idle_in_transaction_session_timeout` of the session, in milliseconds.
+    pub fn get_idle_in_transaction_session_timeout(&self) -> Option<u64> {
+        self.config.get_idle_in_transaction_session_timeout()
+    }
+
+    /// Sets the `statement_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established.
+    pub fn statement_timeout(&mut self, millis: u64) -> &mut Config {
+        self.config.statement_timeout(millis);
+        self
+    }
+
+    /// Gets the `statement_timeout` of the session, in milliseconds.
+    pub fn get_statement_timeout(&self) -> Option<u64> {
+        self.config.get_statement_timeout()
+    }
+
+    /// Sets the `lock_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established.
+    pub fn lock_timeout(&mut self, millis: u64) -> &mut Config {
+        self.config.lock_timeout(millis);
+        self
+    }
+
+    /// Gets the `lock_timeout` of the session, in milliseconds.
+    pub fn get_lock_timeout(&self) -> Option<u64> {
+        self.config.get_lock_timeout()
+    }
+
+    /// Makes the connection a replication connection.
+    ///
+    /// The `replication` startup parameter is sent to the server, and no other query is issued while connecting: the
+    /// session timeouts and `target_session_attrs` are ignored.
+    pub fn replication_mode(&mut self, replication_mode: ReplicationMode) -> &mut Config {
+        self.config.replication_mode(replication_mode);
+        self
+    }
+
+    /// Gets the replication mode, if one has been set with the `replication_mode` method.
+    pub fn get_replication_mode(&self) -> Option<ReplicationMode> {
+        self.config.get_replication_mode()
+    }
+
+    /// Sets the interval of the protocol-level keepalive.
+    ///
+    /// When set, the connection sends a Sync message, without any SQL, whenever it has been idle for this long.
+    /// Defaults to disabled.
+    pub fn keepalive_interval(&mut self, keepalive_interval: Duration) -> &mut Config {
+        self.config.keepalive_interval(keepalive_interval);
+        self
+    }
+
+    /// Gets the interval of the protocol-level keepalive, if one has been set with the `keepalive_interval` method.
+    pub fn get_keepalive_interval(&self) -> Option<Duration> {
+        self.config.get_keepalive_interval()
+    }
+
+    /// Controls the handling of the protocol options rejected by the server during startup.
+    ///
+    /// If this is set to `true`, the connection fails when an option is rejected; otherwise a warning is logged.
+    /// Defaults to `false`.
+    pub fn reject_unsupported_protocol_options(&mut self, reject: bool) -> &mut Config {
+        self.config.reject_unsupported_protocol_options(reject);
+        self
+    }
+
+    /// Gets whether the connection fails when the server rejects protocol options.
+    pub fn get_reject_unsupported_protocol_options(&self) -> bool {
+        self.config.get_reject_unsupported_protocol_options()
+    }
+
+    /// Sets the hooks to invoke during the lifetime of the connection.
+    ///
+    /// The hooks receive the underlying asynchronous client.
+    pub fn lifecycle(&mut self, lifecycle: Arc<dyn ConnectionLifecycle>) -> &mut Config {
+        self.config.lifecycle(lifecycle);
+        self
+    }
+
+    /// Gets the lifecycle hooks, if they have been set with the `lifecycle` method.
+    pub fn get_lifecycle(&self) -> Option<&Arc<dyn ConnectionLifecycle>> {
+        self.config.get_lifecycle()
+    }
+
     /// Sets the notice callback.
     ///
     /// This callback will be invoked with the contents of every
@@ -68,8 +68,8 @@
 
 pub use fallible_iterator;
 pub use tokio_postgres::{
-    error, row, tls, types, Column, IsolationLevel, Notification, Portal, SimpleQueryMessage,
-    Socket, Statement, ToStatement,
+    error, row, tls, types, Column, ConnectionLifecycle, DisconnectReason, IsolationLevel,
+    Notification, PasswordProvider, Portal, SimpleQueryMessage, Socket, Statement, ToStatement,
 };
 
 pub use crate::cancel_token::CancelToken;
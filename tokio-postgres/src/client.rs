@@ -5,6 +5,12 @@ use crate::copy_out::CopyOutStream;
 #[cfg(feature = "runtime")]
 use crate::keepalive::KeepaliveConfig;
 use crate::query::RowStream;
+#[cfg(feature = "raw")]
+use crate::raw::statement_cache::StatementCache;
+#[cfg(feature = "raw")]
+use crate::raw::statistics::Statistics;
+#[cfg(feature = "raw")]
+use crate::raw::Portal;
 use crate::simple_query::SimpleQueryStream;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
@@ -13,8 +19,9 @@ use crate::types::{Oid, ToSql, Type};
 #[cfg(feature = "runtime")]
 use crate::Socket;
 use crate::{
-    copy_in, copy_out, prepare, query, simple_query, slice_iter, CancelToken, CopyInSink, Error,
-    Row, SimpleQueryMessage, Statement, ToStatement, Transaction, TransactionBuilder,
+    copy_in, copy_out, prepare, query, simple_query, slice_iter, CancelToken, ConnectionLifecycle,
+    CopyInSink, DisconnectReason, Error, Row, SimpleQueryMessage, Statement, ToStatement,
+    Transaction, TransactionBuilder,
 };
 use bytes::{Buf, BytesMut};
 use fallible_iterator::FallibleIterator;
@@ -24,20 +31,29 @@ use parking_lot::Mutex;
 use postgres_protocol::message::{backend::Message, frontend};
 use postgres_types::BorrowToSql;
 use std::collections::HashMap;
+#[cfg(feature = "raw")]
+use std::collections::VecDeque;
 use std::fmt;
 #[cfg(feature = "runtime")]
 use std::net::IpAddr;
 #[cfg(feature = "runtime")]
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 #[cfg(feature = "runtime")]
 use std::time::Duration;
+use std::time::Instant;
 use tokio::io::{AsyncRead, AsyncWrite};
 
 pub struct Responses {
     receiver: mpsc::Receiver<BackendMessages>,
     cur: BackendMessages,
+    pending: Option<Arc<AtomicUsize>>,
+    #[cfg(feature = "raw")]
+    search_path_changes: Arc<AtomicUsize>,
+    #[cfg(feature = "raw")]
+    statistics: Arc<Statistics>,
 }
 
 impl Responses {
@@ -45,22 +61,80 @@ impl Responses {
         loop {
             match self.cur.next().map_err(Error::parse)? {
                 Some(Message::ErrorResponse(body)) => return Poll::Ready(Err(Error::db(body))),
-                Some(message) => return Poll::Ready(Ok(message)),
+                Some(message) => {
+                    // The request is complete once the server is ready for the next one, even if the responses are
+                    // not dropped yet.
+                    if let Message::ReadyForQuery(_) = message {
+                        self.finish();
+                    }
+                    #[cfg(feature = "raw")]
+                    {
+                        self.track_search_path(&message);
+                        if let Message::DataRow(_) = message {
+                            self.statistics.record_row();
+                        }
+                    }
+                    return Poll::Ready(Ok(message));
+                }
                 None => {}
             }
 
             match ready!(self.receiver.poll_next_unpin(cx)) {
-                Some(messages) => self.cur = messages,
-                None => return Poll::Ready(Err(Error::closed())),
+                Some(messages) => {
+                    #[cfg(feature = "raw")]
+                    self.statistics.record_responses(messages.as_bytes().len());
+                    self.cur = messages;
+                }
+                None => {
+                    self.finish();
+                    return Poll::Ready(Err(Error::closed()));
+                }
             }
         }
     }
 
+    /// Invalidates the cached schemas when a command that may have changed the effective search path completes.
+    #[cfg(feature = "raw")]
+    fn track_search_path(&self, message: &Message) {
+        if let Message::CommandComplete(body) = message {
+            let changed = match body.tag() {
+                Ok(tag) => matches!(
+                    tag,
+                    "SET"
+                        | "RESET"
+                        | "DISCARD ALL"
+                        | "COMMIT"
+                        | "ROLLBACK"
+                        | "CREATE SCHEMA"
+                        | "ALTER SCHEMA"
+                        | "DROP SCHEMA"
+                ),
+                Err(_) => true,
+            };
+            if changed {
+                // The counter only invalidates the cached schemas, so no ordering with other memory is needed.
+                self.search_path_changes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(pending) = self.pending.take() {
+            pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
     pub async fn next(&mut self) -> Result<Message, Error> {
         future::poll_fn(|cx| self.poll_next(cx)).await
     }
 }
 
+impl Drop for Responses {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
 /// A cache of type info and prepared statements for fetching type info
 /// (corresponding to the queries in the [prepare](prepare) module).
 #[derive(Default)]
@@ -81,28 +155,98 @@ struct CachedTypeInfo {
     types: HashMap<Oid, Type>,
 }
 
+/// A cache of the schemas of the current search path.
+#[cfg(feature = "raw")]
+#[derive(Default)]
+struct CachedSchemas {
+    /// The value of `search_path_changes` when the cached values were fetched.
+    generation: usize,
+    /// The result of `current_schema`, which is `None` when the search path has no existing schema.
+    current_schema: Option<Option<String>>,
+    /// The results of `current_schemas(false)` and `current_schemas(true)`.
+    current_schemas: [Option<Vec<String>>; 2],
+}
+
+/// The identifier to assign to the next client.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
 pub struct InnerClient {
+    /// A process-wide unique identifier of the connection.
+    connection_id: u64,
     sender: mpsc::UnboundedSender<Request>,
     cached_typeinfo: Mutex<CachedTypeInfo>,
 
     /// A buffer to use when writing out postgres commands.
     buffer: Mutex<BytesMut>,
+
+    /// The number of requests whose responses have not been fully consumed yet.
+    pending: Arc<AtomicUsize>,
+
+    /// The number of completed commands which may have changed the search path.
+    #[cfg(feature = "raw")]
+    search_path_changes: Arc<AtomicUsize>,
+    #[cfg(feature = "raw")]
+    cached_schemas: Mutex<CachedSchemas>,
+    /// The statements prepared by the high level raw helpers.
+    #[cfg(feature = "raw")]
+    raw_statements: Mutex<StatementCache>,
+    /// The portals executed by the buffered raw commands, in order.
+    #[cfg(feature = "raw")]
+    raw_executions: Mutex<VecDeque<Portal>>,
+    /// The metrics of the queries made through the connection.
+    #[cfg(feature = "raw")]
+    statistics: Arc<Statistics>,
 }
 
 impl InnerClient {
     pub fn send(&self, messages: RequestMessages) -> Result<Responses, Error> {
+        #[cfg(feature = "raw")]
+        {
+            if let RequestMessages::Single(FrontendMessage::Raw(buf)) = &messages {
+                self.statistics.record_request(buf);
+            }
+        }
+
         let (sender, receiver) = mpsc::channel(1);
         let request = Request { messages, sender };
         self.sender
             .unbounded_send(request)
             .map_err(|_| Error::closed())?;
+        self.pending.fetch_add(1, Ordering::SeqCst);
 
         Ok(Responses {
             receiver,
             cur: BackendMessages::empty(),
+            pending: Some(self.pending.clone()),
+            #[cfg(feature = "raw")]
+            search_path_changes: self.search_path_changes.clone(),
+            #[cfg(feature = "raw")]
+            statistics: self.statistics.clone(),
         })
     }
 
+    /// Sends a request whose responses are handled by the caller.
+    #[cfg(feature = "raw")]
+    pub(crate) fn forward(&self, request: Request) -> Result<(), Error> {
+        self.sender
+            .unbounded_send(request)
+            .map_err(|_| Error::closed())
+    }
+
+    /// Returns the number of requests that have been sent but whose responses have not been fully consumed.
+    pub fn pending_request_count(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    pub fn connection_id(&self) -> u64 {
+        self.connection_id
+    }
+
+    #[cfg(feature = "raw")]
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
     pub fn typeinfo(&self) -> Option<Statement> {
         self.cached_typeinfo.lock().typeinfo.clone()
     }
@@ -139,6 +283,79 @@ impl InnerClient {
         self.cached_typeinfo.lock().types.clear();
     }
 
+    /// Returns a counter which changes whenever the cached schemas are invalidated.
+    #[cfg(feature = "raw")]
+    pub(crate) fn search_path_generation(&self) -> usize {
+        self.search_path_changes.load(Ordering::Relaxed)
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn current_schema(&self) -> Option<Option<String>> {
+        let cache = self.cached_schemas.lock();
+        if cache.generation != self.search_path_generation() {
+            return None;
+        }
+        cache.current_schema.clone()
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn set_current_schema(&self, generation: usize, schema: Option<&str>) {
+        let mut cache = self.cached_schemas.lock();
+        if cache.generation != generation {
+            *cache = CachedSchemas {
+                generation,
+                ..Default::default()
+            };
+        }
+        cache.current_schema = Some(schema.map(str::to_string));
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn current_schemas(&self, include_implicit: bool) -> Option<Vec<String>> {
+        let cache = self.cached_schemas.lock();
+        if cache.generation != self.search_path_generation() {
+            return None;
+        }
+        cache.current_schemas[include_implicit as usize].clone()
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn set_current_schemas(
+        &self,
+        generation: usize,
+        include_implicit: bool,
+        schemas: &[String],
+    ) {
+        let mut cache = self.cached_schemas.lock();
+        if cache.generation != generation {
+            *cache = CachedSchemas {
+                generation,
+                ..Default::default()
+            };
+        }
+        cache.current_schemas[include_implicit as usize] = Some(schemas.to_vec());
+    }
+
+    /// Manipulates the cache of statements prepared by the high level raw helpers.
+    #[cfg(feature = "raw")]
+    pub(crate) fn raw_statements<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut StatementCache) -> R,
+    {
+        let mut cache = self.raw_statements.lock();
+        f(&mut cache)
+    }
+
+    /// Manipulates the list of portals executed by the buffered raw commands.
+    #[cfg(feature = "raw")]
+    pub(crate) fn raw_executions<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut VecDeque<Portal>) -> R,
+    {
+        let mut executions = self.raw_executions.lock();
+        f(&mut executions)
+    }
+
     /// Manipulates the raw buffer
     #[cfg(feature = "raw")]
     pub(crate) fn raw_buf<F, R>(&self, f: F) -> R
@@ -151,6 +368,9 @@ impl InnerClient {
 
     /// Call the given function with a buffer to be used when writing out
     /// postgres commands.
+    ///
+    /// The buffer is shared with the raw commands, so the portals executed by
+    /// the buffered raw commands are forgotten once it has been flushed.
     pub fn with_buf<F, R>(&self, f: F) -> R
     where
         F: FnOnce(&mut BytesMut) -> R,
@@ -158,6 +378,8 @@ impl InnerClient {
         let mut buffer = self.buffer.lock();
         let r = f(&mut buffer);
         buffer.clear();
+        #[cfg(feature = "raw")]
+        self.raw_executions.lock().clear();
         r
     }
 }
@@ -192,6 +414,8 @@ pub struct Client {
     ssl_mode: SslMode,
     process_id: i32,
     secret_key: i32,
+    negotiated_protocol_options: Vec<String>,
+    lifecycle: Option<Arc<dyn ConnectionLifecycle>>,
 }
 
 impl Client {
@@ -203,15 +427,29 @@ impl Client {
     ) -> Client {
         Client {
             inner: Arc::new(InnerClient {
+                connection_id: NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed),
                 sender,
                 cached_typeinfo: Default::default(),
                 buffer: Default::default(),
+                pending: Default::default(),
+                #[cfg(feature = "raw")]
+                search_path_changes: Default::default(),
+                #[cfg(feature = "raw")]
+                cached_schemas: Default::default(),
+                #[cfg(feature = "raw")]
+                raw_statements: Default::default(),
+                #[cfg(feature = "raw")]
+                raw_executions: Default::default(),
+                #[cfg(feature = "raw")]
+                statistics: Default::default(),
             }),
             #[cfg(feature = "runtime")]
             socket_config: None,
             ssl_mode,
             process_id,
             secret_key,
+            negotiated_protocol_options: vec![],
+            lifecycle: None,
         }
     }
 
@@ -219,6 +457,15 @@ impl Client {
         &self.inner
     }
 
+    pub(crate) fn set_negotiated_protocol_options(&mut self, options: Vec<String>) {
+        self.negotiated_protocol_options = options;
+    }
+
+    pub(crate) fn set_lifecycle(&mut self, lifecycle: Arc<dyn ConnectionLifecycle>) {
+        lifecycle.on_connect(self);
+        self.lifecycle = Some(lifecycle);
+    }
+
     #[cfg(feature = "runtime")]
     pub(crate) fn set_socket_config(&mut self, socket_config: SocketConfig) {
         self.socket_config = Some(socket_config);
@@ -454,7 +701,23 @@ impl Client {
     /// functionality to safely embed that data in the request. Do not form statements via string concatenation and pass
     /// them to this method!
     pub async fn simple_query(&self, query: &str) -> Result<Vec<SimpleQueryMessage>, Error> {
-        self.simple_query_raw(query).await?.try_collect().await
+        let lifecycle = match &self.lifecycle {
+            Some(lifecycle) => lifecycle,
+            None => return self.simple_query_raw(query).await?.try_collect().await,
+        };
+
+        lifecycle.on_query_start(self, query);
+        let start = Instant::now();
+        let (result, messages) = match self.simple_query_raw(query).await {
+            Ok(stream) => match stream.try_collect().await {
+                Ok(messages) => (Ok(()), messages),
+                Err(e) => (Err(e), vec![]),
+            },
+            Err(e) => (Err(e), vec![]),
+        };
+        lifecycle.on_query_end(self, start.elapsed(), &result);
+
+        result.map(|()| messages)
     }
 
     pub(crate) async fn simple_query_raw(&self, query: &str) -> Result<SimpleQueryStream, Error> {
@@ -472,7 +735,17 @@ impl Client {
     /// functionality to safely embed that data in the request. Do not form statements via string concatenation and pass
     /// them to this method!
     pub async fn batch_execute(&self, query: &str) -> Result<(), Error> {
-        simple_query::batch_execute(self.inner(), query).await
+        let lifecycle = match &self.lifecycle {
+            Some(lifecycle) => lifecycle,
+            None => return simple_query::batch_execute(self.inner(), query).await,
+        };
+
+        lifecycle.on_query_start(self, query);
+        let start = Instant::now();
+        let result = simple_query::batch_execute(self.inner(), query).await;
+        lifecycle.on_query_end(self, start.elapsed(), &result);
+
+        result
     }
 
     /// Begins a new database transaction.
@@ -573,6 +846,40 @@ impl Client {
         self.inner().clear_type_cache();
     }
 
+    /// Returns the number of requests that have been sent to the server but whose responses have not been fully
+    /// consumed yet.
+    ///
+    /// Applications pipelining many requests can use this to apply backpressure, delaying new requests until some of
+    /// the in-flight ones complete.
+    pub fn pending_request_count(&self) -> usize {
+        self.inner.pending_request_count()
+    }
+
+    /// Returns the process ID of the backend serving this connection.
+    pub fn backend_pid(&self) -> i32 {
+        self.process_id
+    }
+
+    /// Returns an identifier of this connection, unique among all clients created by this process.
+    ///
+    /// Identifiers are assigned sequentially starting at 1 as clients are constructed, and are never reused.
+    pub fn connection_id(&self) -> u64 {
+        self.inner.connection_id()
+    }
+
+    /// Returns the metrics of the queries made through this connection.
+    ///
+    /// Requires the `raw` Cargo feature.
+    #[cfg(feature = "raw")]
+    pub fn statistics(&self) -> &crate::raw::Statistics {
+        self.inner.statistics()
+    }
+
+    /// Returns the protocol options which were not accepted by the server during the startup of the connection.
+    pub fn negotiated_protocol_options(&self) -> &[String] {
+        &self.negotiated_protocol_options
+    }
+
     /// Determines if the connection to the server has already closed.
     ///
     /// In that case, all future queries will fail.
@@ -586,6 +893,19 @@ impl Client {
     }
 }
 
+impl Drop for Client {
+    fn drop(&mut self) {
+        if let Some(lifecycle) = self.lifecycle.take() {
+            let reason = if self.is_closed() {
+                DisconnectReason::ConnectionClosed
+            } else {
+                DisconnectReason::ClientDropped
+            };
+            lifecycle.on_disconnect(self, reason);
+        }
+    }
+}
+
 impl fmt::Debug for Client {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Client").finish()
@@ -24,6 +24,16 @@ impl BackendMessages {
     pub fn empty() -> BackendMessages {
         BackendMessages(BytesMut::new())
     }
+
+    #[cfg(feature = "raw")]
+    pub fn new(buf: BytesMut) -> BackendMessages {
+        BackendMessages(buf)
+    }
+
+    #[cfg(feature = "raw")]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
 }
 
 impl FallibleIterator for BackendMessages {
@@ -5,12 +5,14 @@ use crate::connect::connect;
 use crate::connect_raw::connect_raw;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::keepalive::KeepaliveConfig;
+use crate::lifecycle::Lifecycle;
+use crate::password::Provider;
 #[cfg(feature = "runtime")]
 use crate::tls::MakeTlsConnect;
 use crate::tls::TlsConnect;
 #[cfg(feature = "runtime")]
 use crate::Socket;
-use crate::{Client, Connection, Error};
+use crate::{Client, Connection, ConnectionLifecycle, Error, PasswordProvider};
 use std::borrow::Cow;
 #[cfg(unix)]
 use std::ffi::OsStr;
@@ -22,6 +24,7 @@ use std::os::unix::ffi::OsStrExt;
 use std::path::{Path, PathBuf};
 use std::str;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{error, fmt, iter, mem};
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -72,6 +75,16 @@ pub enum LoadBalanceHosts {
     Random,
 }
 
+/// Replication mode configuration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplicationMode {
+    /// Physical replication, connecting to the cluster rather than to a database.
+    Physical,
+    /// Logical replication, connecting to the database specified by `dbname`.
+    Database,
+}
+
 /// A host specification.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Host {
@@ -147,6 +160,9 @@ pub enum Host {
 ///     `disable`, hosts and addresses will be tried in the order provided. If set to `random`, hosts will be tried
 ///     in a random order, and the IP addresses resolved from a hostname will also be tried in a random order. Defaults
 ///     to `disable`.
+/// * `replication` - Makes the connection a replication connection. If set to `database`, the connection can be used
+///     for logical replication from the database specified by `dbname`. If set to `true`, `on`, `yes` or `1`, the
+///     connection is used for physical replication. Defaults to `false`.
 ///
 /// ## Examples
 ///
@@ -194,6 +210,7 @@ pub enum Host {
 pub struct Config {
     pub(crate) user: Option<String>,
     pub(crate) password: Option<Vec<u8>>,
+    pub(crate) password_provider: Option<Provider>,
     pub(crate) dbname: Option<String>,
     pub(crate) options: Option<String>,
     pub(crate) application_name: Option<String>,
@@ -209,6 +226,13 @@ pub struct Config {
     pub(crate) target_session_attrs: TargetSessionAttrs,
     pub(crate) channel_binding: ChannelBinding,
     pub(crate) load_balance_hosts: LoadBalanceHosts,
+    pub(crate) idle_in_transaction_session_timeout: Option<u64>,
+    pub(crate) statement_timeout: Option<u64>,
+    pub(crate) lock_timeout: Option<u64>,
+    pub(crate) replication_mode: Option<ReplicationMode>,
+    pub(crate) lifecycle: Option<Lifecycle>,
+    pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) reject_unsupported_protocol_options: bool,
 }
 
 impl Default for Config {
@@ -223,6 +247,7 @@ impl Config {
         Config {
             user: None,
             password: None,
+            password_provider: None,
             dbname: None,
             options: None,
             application_name: None,
@@ -242,6 +267,13 @@ impl Config {
             target_session_attrs: TargetSessionAttrs::Any,
             channel_binding: ChannelBinding::Prefer,
             load_balance_hosts: LoadBalanceHosts::Disable,
+            idle_in_transaction_session_timeout: None,
+            statement_timeout: None,
+            lock_timeout: None,
+            replication_mode: None,
+            lifecycle: None,
+            keepalive_interval: None,
+            reject_unsupported_protocol_options: false,
         }
     }
 
@@ -274,6 +306,19 @@ impl Config {
         self.password.as_deref()
     }
 
+    /// Sets the provider queried for the password to authenticate with.
+    ///
+    /// The provider is only used if no password has been set with the `password` method.
+    pub fn password_provider(&mut self, provider: Arc<dyn PasswordProvider>) -> &mut Config {
+        self.password_provider = Some(Provider(provider));
+        self
+    }
+
+    /// Gets the password provider, if one has been set with the `password_provider` method.
+    pub fn get_password_provider(&self) -> Option<&Arc<dyn PasswordProvider>> {
+        self.password_provider.as_ref().map(|p| &p.0)
+    }
+
     /// Sets the name of the database to connect to.
     ///
     /// Defaults to the user.
@@ -524,6 +569,103 @@ impl Config {
         self.load_balance_hosts
     }
 
+    /// Sets the `idle_in_transaction_session_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established. Sessions left idle
+    /// within an open transaction for longer than this are terminated by the server.
+    pub fn idle_in_transaction_session_timeout(&mut self, millis: u64) -> &mut Config {
+        self.idle_in_transaction_session_timeout = Some(millis);
+        self
+    }
+
+    /// Gets the `idle_in_transaction_session_timeout` of the session, in milliseconds.
+    pub fn get_idle_in_transaction_session_timeout(&self) -> Option<u64> {
+        self.idle_in_transaction_session_timeout
+    }
+
+    /// Sets the `statement_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established.
+    pub fn statement_timeout(&mut self, millis: u64) -> &mut Config {
+        self.statement_timeout = Some(millis);
+        self
+    }
+
+    /// Gets the `statement_timeout` of the session, in milliseconds.
+    pub fn get_statement_timeout(&self) -> Option<u64> {
+        self.statement_timeout
+    }
+
+    /// Sets the `lock_timeout` of the session, in milliseconds.
+    ///
+    /// The value is set with a `SET` command right after the connection has been established.
+    pub fn lock_timeout(&mut self, millis: u64) -> &mut Config {
+        self.lock_timeout = Some(millis);
+        self
+    }
+
+    /// Gets the `lock_timeout` of the session, in milliseconds.
+    pub fn get_lock_timeout(&self) -> Option<u64> {
+        self.lock_timeout
+    }
+
+    /// Makes the connection a replication connection.
+    ///
+    /// The `replication` startup parameter is sent to the server, and no other query is issued while connecting: the
+    /// session timeouts and `target_session_attrs` are ignored.
+    pub fn replication_mode(&mut self, replication_mode: ReplicationMode) -> &mut Config {
+        self.replication_mode = Some(replication_mode);
+        self
+    }
+
+    /// Gets the replication mode, if one has been set with the `replication_mode` method.
+    pub fn get_replication_mode(&self) -> Option<ReplicationMode> {
+        self.replication_mode
+    }
+
+    /// Sets the hooks to invoke during the lifetime of the connection.
+    pub fn lifecycle(&mut self, lifecycle: Arc<dyn ConnectionLifecycle>) -> &mut Config {
+        self.lifecycle = Some(Lifecycle(lifecycle));
+        self
+    }
+
+    /// Gets the lifecycle hooks, if they have been set with the `lifecycle` method.
+    pub fn get_lifecycle(&self) -> Option<&Arc<dyn ConnectionLifecycle>> {
+        self.lifecycle.as_ref().map(|l| &l.0)
+    }
+
+    /// Sets the interval of the protocol-level keepalive.
+    ///
+    /// When set, the connection sends a Sync message, without any SQL, whenever it has been idle for this long, and
+    /// discards the resulting `ReadyForQuery`. Unlike TCP keepalives, this produces application-level traffic which
+    /// is seen by proxies and firewalls. Defaults to disabled.
+    ///
+    /// Requires the `runtime` Cargo feature (enabled by default).
+    pub fn keepalive_interval(&mut self, keepalive_interval: Duration) -> &mut Config {
+        self.keepalive_interval = Some(keepalive_interval);
+        self
+    }
+
+    /// Gets the interval of the protocol-level keepalive, if one has been set with the `keepalive_interval` method.
+    pub fn get_keepalive_interval(&self) -> Option<Duration> {
+        self.keepalive_interval
+    }
+
+    /// Controls the handling of the protocol options rejected by the server during startup.
+    ///
+    /// The server lists the protocol options it does not support in a `NegotiateProtocolVersion` message. If this
+    /// is set to `true`, the connection fails when an option is rejected; otherwise a warning is logged and the
+    /// options are reported by `Client::negotiated_protocol_options`. Defaults to `false`.
+    pub fn reject_unsupported_protocol_options(&mut self, reject: bool) -> &mut Config {
+        self.reject_unsupported_protocol_options = reject;
+        self
+    }
+
+    /// Gets whether the connection fails when the server rejects protocol options.
+    pub fn get_reject_unsupported_protocol_options(&self) -> bool {
+        self.reject_unsupported_protocol_options
+    }
+
     fn param(&mut self, key: &str, value: &str) -> Result<(), Error> {
         match key {
             "user" => {
@@ -660,6 +802,18 @@ impl Config {
                 };
                 self.load_balance_hosts(load_balance_hosts);
             }
+            "replication" => match value {
+                "database" => {
+                    self.replication_mode(ReplicationMode::Database);
+                }
+                "true" | "on" | "yes" | "1" => {
+                    self.replication_mode(ReplicationMode::Physical);
+                }
+                "false" | "off" | "no" | "0" => {
+                    self.replication_mode = None;
+                }
+                _ => return Err(Error::config_parse(Box::new(InvalidValue("replication")))),
+            },
             key => {
                 return Err(Error::config_parse(Box::new(UnknownOption(
                     key.to_string(),
@@ -683,7 +837,8 @@ impl Config {
 
     /// Connects to a PostgreSQL database over an arbitrary stream.
     ///
-    /// All of the settings other than `user`, `password`, `dbname`, `options`, and `application_name` name are ignored.
+    /// All of the settings other than `user`, `password`, `dbname`, `options`, `application_name`, `replication` and
+    /// the session timeouts are ignored.
     pub async fn connect_raw<S, T>(
         &self,
         stream: S,
@@ -722,6 +877,7 @@ impl fmt::Debug for Config {
         config_dbg = config_dbg
             .field("user", &self.user)
             .field("password", &self.password.as_ref().map(|_| Redaction {}))
+            .field("password_provider", &self.password_provider.is_some())
             .field("dbname", &self.dbname)
             .field("options", &self.options)
             .field("application_name", &self.application_name)
@@ -744,6 +900,19 @@ impl fmt::Debug for Config {
         config_dbg
             .field("target_session_attrs", &self.target_session_attrs)
             .field("channel_binding", &self.channel_binding)
+            .field(
+                "idle_in_transaction_session_timeout",
+                &self.idle_in_transaction_session_timeout,
+            )
+            .field("statement_timeout", &self.statement_timeout)
+            .field("lock_timeout", &self.lock_timeout)
+            .field("replication_mode", &self.replication_mode)
+            .field("lifecycle", &self.lifecycle.is_some())
+            .field("keepalive_interval", &self.keepalive_interval)
+            .field(
+                "reject_unsupported_protocol_options",
+                &self.reject_unsupported_protocol_options,
+            )
             .finish()
     }
 }
@@ -160,7 +160,7 @@ where
     let has_hostname = hostname.is_some();
     let (mut client, mut connection) = connect_raw(socket, tls, has_hostname, config).await?;
 
-    if config.target_session_attrs != TargetSessionAttrs::Any {
+    if config.target_session_attrs != TargetSessionAttrs::Any && config.replication_mode.is_none() {
         let rows = client.simple_query_raw("SHOW transaction_read_only");
         pin_mut!(rows);
 
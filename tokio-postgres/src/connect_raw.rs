@@ -1,5 +1,5 @@
 use crate::codec::{BackendMessage, BackendMessages, FrontendMessage, PostgresCodec};
-use crate::config::{self, Config};
+use crate::config::{self, Config, ReplicationMode};
 use crate::connect_tls::connect_tls;
 use crate::maybe_tls_stream::MaybeTlsStream;
 use crate::tls::{TlsConnect, TlsStream};
@@ -8,10 +8,13 @@ use bytes::BytesMut;
 use fallible_iterator::FallibleIterator;
 use futures_channel::mpsc;
 use futures_util::{ready, Sink, SinkExt, Stream, TryStreamExt};
+use log::warn;
 use postgres_protocol::authentication;
 use postgres_protocol::authentication::sasl;
 use postgres_protocol::authentication::sasl::ScramSha256;
-use postgres_protocol::message::backend::{AuthenticationSaslBody, Message};
+use postgres_protocol::message::backend::{
+    AuthenticationSaslBody, Message, NegotiateProtocolVersionBody,
+};
 use postgres_protocol::message::frontend;
 use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
@@ -21,6 +24,9 @@ use std::task::{Context, Poll};
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio_util::codec::Framed;
 
+/// The minor version of the protocol requested by the startup message.
+const PROTOCOL_MINOR_VERSION: i32 = 0;
+
 pub struct StartupStream<S, T> {
     inner: Framed<MaybeTlsStream<S, T>, PostgresCodec>,
     buf: BackendMessages,
@@ -103,12 +109,25 @@ where
         .map_or_else(|| Cow::Owned(whoami::username()), Cow::Borrowed);
 
     startup(&mut stream, config, &user).await?;
-    authenticate(&mut stream, config, &user).await?;
-    let (process_id, secret_key, parameters) = read_info(&mut stream).await?;
+    let mut negotiated_options = vec![];
+    authenticate(&mut stream, config, &user, &mut negotiated_options).await?;
+    let (process_id, secret_key, mut parameters) = read_info(&mut stream).await?;
+    if config.replication_mode.is_none() {
+        set_timeouts(&mut stream, config, &mut parameters).await?;
+    }
 
     let (sender, receiver) = mpsc::unbounded();
-    let client = Client::new(sender, config.ssl_mode, process_id, secret_key);
-    let connection = Connection::new(stream.inner, stream.delayed, parameters, receiver);
+    let mut client = Client::new(sender, config.ssl_mode, process_id, secret_key);
+    client.set_negotiated_protocol_options(negotiated_options);
+    if let Some(lifecycle) = &config.lifecycle {
+        client.set_lifecycle(lifecycle.0.clone());
+    }
+    #[allow(unused_mut)]
+    let mut connection = Connection::new(stream.inner, stream.delayed, parameters, receiver);
+    #[cfg(feature = "runtime")]
+    if let Some(interval) = config.keepalive_interval {
+        connection.set_keepalive_interval(interval);
+    }
 
     Ok((client, connection))
 }
@@ -133,6 +152,11 @@ where
     if let Some(application_name) = &config.application_name {
         params.push(("application_name", &**application_name));
     }
+    match config.replication_mode {
+        Some(ReplicationMode::Physical) => params.push(("replication", "true")),
+        Some(ReplicationMode::Database) => params.push(("replication", "database")),
+        None => {}
+    }
 
     let mut buf = BytesMut::new();
     frontend::startup_message(params, &mut buf).map_err(Error::encode)?;
@@ -147,12 +171,19 @@ async fn authenticate<S, T>(
     stream: &mut StartupStream<S, T>,
     config: &Config,
     user: &str,
+    negotiated_options: &mut Vec<String>,
 ) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin,
     T: TlsStream + Unpin,
 {
-    match stream.try_next().await.map_err(Error::io)? {
+    let mut message = stream.try_next().await.map_err(Error::io)?;
+    if let Some(Message::NegotiateProtocolVersion(body)) = &message {
+        *negotiated_options = negotiate_protocol_version(body, config)?;
+        message = stream.try_next().await.map_err(Error::io)?;
+    }
+
+    match message {
         Some(Message::AuthenticationOk) => {
             can_skip_channel_binding(config)?;
             return Ok(());
@@ -160,26 +191,20 @@ where
         Some(Message::AuthenticationCleartextPassword) => {
             can_skip_channel_binding(config)?;
 
-            let pass = config
-                .password
-                .as_ref()
-                .ok_or_else(|| Error::config("password missing".into()))?;
+            let pass = password(config, user).await?;
 
-            authenticate_password(stream, pass).await?;
+            authenticate_password(stream, &pass).await?;
         }
         Some(Message::AuthenticationMd5Password(body)) => {
             can_skip_channel_binding(config)?;
 
-            let pass = config
-                .password
-                .as_ref()
-                .ok_or_else(|| Error::config("password missing".into()))?;
+            let pass = password(config, user).await?;
 
-            let output = authentication::md5_hash(user.as_bytes(), pass, body.salt());
+            let output = authentication::md5_hash(user.as_bytes(), &pass, body.salt());
             authenticate_password(stream, output.as_bytes()).await?;
         }
         Some(Message::AuthenticationSasl(body)) => {
-            authenticate_sasl(stream, body, config).await?;
+            authenticate_sasl(stream, body, config, user).await?;
         }
         Some(Message::AuthenticationKerberosV5)
         | Some(Message::AuthenticationScmCredential)
@@ -202,6 +227,50 @@ where
     }
 }
 
+fn negotiate_protocol_version(
+    body: &NegotiateProtocolVersionBody,
+    config: &Config,
+) -> Result<Vec<String>, Error> {
+    // The server only downgrades the minor version, and we request the lowest one.
+    if body.newest_minor_version() != PROTOCOL_MINOR_VERSION {
+        return Err(Error::unexpected_message());
+    }
+
+    let options = body
+        .options()
+        .map(|option| Ok(option.to_string()))
+        .collect::<Vec<_>>()
+        .map_err(Error::parse)?;
+    if !options.is_empty() {
+        let message = format!(
+            "server does not support the protocol options: {}",
+            options.join(", ")
+        );
+        if config.reject_unsupported_protocol_options {
+            return Err(Error::config(message.into()));
+        }
+        warn!("{}", message);
+    }
+
+    Ok(options)
+}
+
+/// Returns the configured password, or queries the password provider if none is set.
+async fn password<'a>(config: &'a Config, user: &str) -> Result<Cow<'a, [u8]>, Error> {
+    if let Some(password) = &config.password {
+        return Ok(Cow::Borrowed(password));
+    }
+
+    match &config.password_provider {
+        Some(provider) => {
+            let database = config.dbname.as_deref().unwrap_or(user);
+            let password = provider.0.get_password(user, database).await?;
+            Ok(Cow::Owned(password.into_bytes()))
+        }
+        None => Err(Error::config("password missing".into())),
+    }
+}
+
 fn can_skip_channel_binding(config: &Config) -> Result<(), Error> {
     match config.channel_binding {
         config::ChannelBinding::Disable | config::ChannelBinding::Prefer => Ok(()),
@@ -232,15 +301,13 @@ async fn authenticate_sasl<S, T>(
     stream: &mut StartupStream<S, T>,
     body: AuthenticationSaslBody,
     config: &Config,
+    user: &str,
 ) -> Result<(), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin,
     T: TlsStream + Unpin,
 {
-    let password = config
-        .password
-        .as_ref()
-        .ok_or_else(|| Error::config("password missing".into()))?;
+    let password = password(config, user).await?;
 
     let mut has_scram = false;
     let mut has_scram_plus = false;
@@ -279,7 +346,7 @@ where
         can_skip_channel_binding(config)?;
     }
 
-    let mut scram = ScramSha256::new(password, channel_binding);
+    let mut scram = ScramSha256::new(&password, channel_binding);
 
     let mut buf = BytesMut::new();
     frontend::sasl_initial_response(mechanism, scram.message(), &mut buf).map_err(Error::encode)?;
@@ -353,3 +420,56 @@ where
         }
     }
 }
+
+async fn set_timeouts<S, T>(
+    stream: &mut StartupStream<S, T>,
+    config: &Config,
+    parameters: &mut HashMap<String, String>,
+) -> Result<(), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: TlsStream + Unpin,
+{
+    let query = [
+        (
+            "idle_in_transaction_session_timeout",
+            config.idle_in_transaction_session_timeout,
+        ),
+        ("statement_timeout", config.statement_timeout),
+        ("lock_timeout", config.lock_timeout),
+    ]
+    .iter()
+    .filter_map(|(name, value)| value.map(|value| format!("SET {} = {}", name, value)))
+    .collect::<Vec<_>>()
+    .join("; ");
+
+    if query.is_empty() {
+        return Ok(());
+    }
+
+    let mut buf = BytesMut::new();
+    frontend::query(&query, &mut buf).map_err(Error::encode)?;
+    stream
+        .send(FrontendMessage::Raw(buf.freeze()))
+        .await
+        .map_err(Error::io)?;
+
+    loop {
+        match stream.try_next().await.map_err(Error::io)? {
+            Some(Message::CommandComplete(_)) => {}
+            Some(Message::ParameterStatus(body)) => {
+                parameters.insert(
+                    body.name().map_err(Error::parse)?.to_string(),
+                    body.value().map_err(Error::parse)?.to_string(),
+                );
+            }
+            Some(msg @ Message::NoticeResponse(_)) => {
+                stream.delayed.push_back(BackendMessage::Async(msg))
+            }
+            Some(Message::ReadyForQuery(_)) => return Ok(()),
+            Some(Message::ErrorResponse(body)) => return Err(Error::db(body)),
+            Some(_) => return Err(Error::unexpected_message()),
+            None => return Err(Error::closed()),
+        }
+    }
+}
@@ -14,7 +14,11 @@ use std::collections::{HashMap, VecDeque};
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+#[cfg(feature = "runtime")]
+use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
+#[cfg(feature = "runtime")]
+use tokio::time::{Instant, Sleep};
 use tokio_util::codec::Framed;
 
 pub enum RequestMessages {
@@ -31,6 +35,13 @@ pub struct Response {
     sender: mpsc::Sender<BackendMessages>,
 }
 
+/// The timer of the protocol-level keepalive.
+#[cfg(feature = "runtime")]
+struct Keepalive {
+    interval: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
 #[derive(PartialEq, Debug)]
 enum State {
     Active,
@@ -54,6 +65,8 @@ pub struct Connection<S, T> {
     pending_responses: VecDeque<BackendMessage>,
     responses: VecDeque<Response>,
     state: State,
+    #[cfg(feature = "runtime")]
+    keepalive: Option<Keepalive>,
 }
 
 impl<S, T> Connection<S, T>
@@ -75,7 +88,54 @@ where
             pending_responses,
             responses: VecDeque::new(),
             state: State::Active,
+            #[cfg(feature = "runtime")]
+            keepalive: None,
+        }
+    }
+
+    /// Enables the protocol-level keepalive, sending a Sync message whenever the connection has been idle for
+    /// `interval`.
+    #[cfg(feature = "runtime")]
+    pub(crate) fn set_keepalive_interval(&mut self, interval: Duration) {
+        self.keepalive = Some(Keepalive {
+            interval,
+            sleep: Box::pin(tokio::time::sleep(interval)),
+        });
+    }
+
+    /// Queues a keepalive Sync message if the connection has been idle for long enough.
+    ///
+    /// The responses to the keepalive are discarded.
+    #[cfg(feature = "runtime")]
+    fn poll_keepalive(&mut self, cx: &mut Context<'_>) {
+        let keepalive = match &mut self.keepalive {
+            Some(keepalive) => keepalive,
+            None => return,
+        };
+
+        let idle = self.state == State::Active
+            && self.responses.is_empty()
+            && self.pending_request.is_none();
+        if !idle {
+            let deadline = Instant::now() + keepalive.interval;
+            keepalive.sleep.as_mut().reset(deadline);
+            return;
         }
+
+        if keepalive.sleep.as_mut().poll(cx).is_pending() {
+            return;
+        }
+        let deadline = Instant::now() + keepalive.interval;
+        keepalive.sleep.as_mut().reset(deadline);
+
+        trace!("poll_keepalive: sending keepalive");
+        let (sender, _) = mpsc::channel(1);
+        self.responses.push_back(Response { sender });
+        let mut request = BytesMut::new();
+        frontend::sync(&mut request);
+        self.pending_request = Some(RequestMessages::Single(FrontendMessage::Raw(
+            request.freeze(),
+        )));
     }
 
     fn poll_response(
@@ -310,6 +370,8 @@ where
         cx: &mut Context<'_>,
     ) -> Poll<Option<Result<AsyncMessage, Error>>> {
         let message = self.poll_read(cx)?;
+        #[cfg(feature = "runtime")]
+        self.poll_keepalive(cx);
         let want_flush = self.poll_write(cx)?;
         if want_flush {
             self.poll_flush(cx)?;
@@ -1,9 +1,10 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
+use crate::error::SqlState;
 use crate::query::extract_row_affected;
 use crate::{query, slice_iter, Error, Statement};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use futures_channel::mpsc;
 use futures_util::{future, ready, Sink, SinkExt, Stream, StreamExt};
 use log::debug;
@@ -18,6 +19,7 @@ use std::task::{Context, Poll};
 enum CopyInMessage {
     Message(FrontendMessage),
     Done,
+    Fail(Bytes),
 }
 
 pub struct CopyInReceiver {
@@ -51,6 +53,10 @@ impl Stream for CopyInReceiver {
                 frontend::sync(&mut buf);
                 Poll::Ready(Some(FrontendMessage::Raw(buf.freeze())))
             }
+            Some(CopyInMessage::Fail(buf)) => {
+                self.done = true;
+                Poll::Ready(Some(FrontendMessage::Raw(buf)))
+            }
             None => {
                 self.done = true;
                 let mut buf = BytesMut::new();
@@ -128,6 +134,35 @@ where
     pub async fn finish(mut self: Pin<&mut Self>) -> Result<u64, Error> {
         future::poll_fn(|cx| self.as_mut().poll_finish(cx)).await
     }
+
+    /// Aborts the copy, discarding the data which has not been sent yet.
+    ///
+    /// A `CopyFail` message is sent with `reason`, and the server fails the `COPY` command with a `COPY from stdin
+    /// failed` error, which is expected and not returned. No rows are inserted. The copy must not have been completed
+    /// with `Sink::close` or `finish`.
+    pub async fn abort<E>(mut self: Pin<&mut Self>, reason: &str) -> Result<(), E>
+    where
+        E: From<Error>,
+    {
+        let mut buf = BytesMut::new();
+        frontend::copy_fail(reason, &mut buf).map_err(Error::encode)?;
+        frontend::sync(&mut buf);
+
+        let mut this = self.as_mut().project();
+        this.buf.clear();
+        *this.state = SinkState::Reading;
+        this.sender
+            .send(CopyInMessage::Fail(buf.freeze()))
+            .await
+            .map_err(|_| Error::closed())?;
+        this.sender.close().await.map_err(|_| Error::closed())?;
+
+        match this.responses.next().await {
+            Err(e) if e.code() == Some(&SqlState::QUERY_CANCELED) => Ok(()),
+            Err(e) => Err(e.into()),
+            Ok(_) => Err(Error::unexpected_message().into()),
+        }
+    }
 }
 
 impl<T> Sink<T> for CopyInSink<T>
@@ -5,6 +5,7 @@ use postgres_protocol::message::backend::{ErrorFields, ErrorResponseBody};
 use std::error::{self, Error as _Error};
 use std::fmt;
 use std::io;
+use std::iter;
 
 pub use self::sqlstate::*;
 
@@ -354,6 +355,12 @@ enum Kind {
     ConfigParse,
     Config,
     RowCount,
+    #[cfg(feature = "raw")]
+    NoRows,
+    #[cfg(feature = "raw")]
+    TooManyRows,
+    #[cfg(feature = "raw")]
+    RowLimit(usize),
     #[cfg(feature = "runtime")]
     Connect,
     Timeout,
@@ -396,6 +403,12 @@ impl fmt::Display for Error {
             Kind::ConfigParse => fmt.write_str("invalid connection string")?,
             Kind::Config => fmt.write_str("invalid configuration")?,
             Kind::RowCount => fmt.write_str("query returned an unexpected number of rows")?,
+            #[cfg(feature = "raw")]
+            Kind::NoRows => fmt.write_str("query returned no rows")?,
+            #[cfg(feature = "raw")]
+            Kind::TooManyRows => fmt.write_str("query returned more than one row")?,
+            #[cfg(feature = "raw")]
+            Kind::RowLimit(limit) => write!(fmt, "query returned more than {} rows", limit)?,
             #[cfg(feature = "runtime")]
             Kind::Connect => fmt.write_str("error connecting to server")?,
             Kind::Timeout => fmt.write_str("timeout waiting for server")?,
@@ -438,6 +451,45 @@ impl Error {
         self.as_db_error().map(DbError::code)
     }
 
+    /// Returns the position of the error in the query, if the server reported one.
+    ///
+    /// This is a convenience method that downcasts the cause to a `DbError` and returns its position.
+    pub fn position(&self) -> Option<&ErrorPosition> {
+        self.as_db_error().and_then(DbError::position)
+    }
+
+    /// Returns the query with a `^` marker inserted before the character at the position of the error.
+    ///
+    /// `query` must be the query which caused the error. It is ignored if the error lies in a query generated by the
+    /// server, which is annotated instead. Returns `None` if the error has no position, or if the position is out of
+    /// the query.
+    pub fn annotated_query(&self, query: &str) -> Option<String> {
+        let (position, query) = match self.position()? {
+            ErrorPosition::Original(position) => (*position, query),
+            ErrorPosition::Internal { position, query } => (*position, query.as_str()),
+        };
+
+        // The position is a 1-based index of characters.
+        let offset = query
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(iter::once(query.len()))
+            .nth((position as usize).checked_sub(1)?)?;
+
+        let mut annotated = String::with_capacity(query.len() + 1);
+        annotated.push_str(&query[..offset]);
+        annotated.push('^');
+        annotated.push_str(&query[offset..]);
+        Some(annotated)
+    }
+
+    /// Determines if the error was reported because a lock could not be acquired immediately.
+    ///
+    /// This is the error returned by statements using `NOWAIT`, or when the `lock_timeout` expires.
+    pub fn is_lock_not_available(&self) -> bool {
+        self.code() == Some(&SqlState::LOCK_NOT_AVAILABLE)
+    }
+
     fn new(kind: Kind, cause: Option<Box<dyn error::Error + Sync + Send>>) -> Error {
         Error(Box::new(ErrorInner { kind, cause }))
     }
@@ -507,13 +559,32 @@ impl Error {
         Error::new(Kind::RowCount, None)
     }
 
+    #[cfg(feature = "raw")]
+    pub(crate) fn no_rows() -> Error {
+        Error::new(Kind::NoRows, None)
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn too_many_rows() -> Error {
+        Error::new(Kind::TooManyRows, None)
+    }
+
+    #[cfg(feature = "raw")]
+    pub(crate) fn row_limit(limit: usize) -> Error {
+        Error::new(Kind::RowLimit(limit), None)
+    }
+
     #[cfg(feature = "runtime")]
     pub(crate) fn connect(e: io::Error) -> Error {
         Error::new(Kind::Connect, Some(Box::new(e)))
     }
 
+    pub(crate) fn timeout() -> Error {
+        Error::new(Kind::Timeout, None)
+    }
+
     #[doc(hidden)]
     pub fn __private_api_timeout() -> Error {
-        Error::new(Kind::Timeout, None)
+        Error::timeout()
     }
 }
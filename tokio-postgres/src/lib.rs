@@ -127,6 +127,8 @@ pub use crate::copy_out::CopyOutStream;
 use crate::error::DbError;
 pub use crate::error::Error;
 pub use crate::generic_client::GenericClient;
+pub use crate::lifecycle::{ConnectionLifecycle, DisconnectReason};
+pub use crate::password::PasswordProvider;
 pub use crate::portal::Portal;
 pub use crate::query::RowStream;
 pub use crate::row::{Row, SimpleQueryRow};
@@ -164,7 +166,9 @@ pub mod error;
 mod generic_client;
 #[cfg(not(target_arch = "wasm32"))]
 mod keepalive;
+mod lifecycle;
 mod maybe_tls_stream;
+mod password;
 mod portal;
 mod prepare;
 mod query;
@@ -0,0 +1,53 @@
+//! Connection lifecycle hooks.
+
+use crate::{Client, Error};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hooks invoked at significant points of the lifetime of a connection.
+///
+/// Hooks are registered with [`Config::lifecycle`](crate::Config::lifecycle) and are called synchronously from the
+/// task using the client, so implementations should return quickly. All methods have empty default implementations.
+pub trait ConnectionLifecycle: Send + Sync {
+    /// Called once the connection has been established and authenticated, before the client is returned.
+    fn on_connect(&self, client: &Client) {
+        let _ = client;
+    }
+
+    /// Called when the client is dropped.
+    fn on_disconnect(&self, client: &Client, reason: DisconnectReason) {
+        let _ = (client, reason);
+    }
+
+    /// Called before a query is sent with the simple query protocol.
+    fn on_query_start(&self, client: &Client, query: &str) {
+        let _ = (client, query);
+    }
+
+    /// Called once all the responses of a query sent with the simple query protocol have been received.
+    fn on_query_end(&self, client: &Client, duration: Duration, result: &Result<(), Error>) {
+        let _ = (client, duration, result);
+    }
+}
+
+/// The reason a client was disconnected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DisconnectReason {
+    /// The client was dropped while the connection was still open.
+    ClientDropped,
+    /// The connection had already been closed, by the server or because of an error.
+    ConnectionClosed,
+}
+
+/// A registered set of hooks, compared by identity.
+#[derive(Clone)]
+pub(crate) struct Lifecycle(pub Arc<dyn ConnectionLifecycle>);
+
+impl PartialEq for Lifecycle {
+    fn eq(&self, other: &Lifecycle) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Lifecycle {}
@@ -0,0 +1,32 @@
+//! Password providers.
+
+use crate::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A source of passwords, queried each time a connection is authenticated.
+///
+/// Providers are registered with [`Config::password_provider`](crate::Config::password_provider), and allow the
+/// password to be fetched when connecting, e.g. from a secret store or a token service issuing short-lived
+/// credentials. The returned password is used for the cleartext, MD5 and SCRAM-SHA-256 authentication methods.
+pub trait PasswordProvider: Send + Sync {
+    /// Returns the password of `username` for the connection to `database`.
+    fn get_password(
+        &self,
+        username: &str,
+        database: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send>>;
+}
+
+/// A registered password provider, compared by identity.
+#[derive(Clone)]
+pub(crate) struct Provider(pub Arc<dyn PasswordProvider>);
+
+impl PartialEq for Provider {
+    fn eq(&self, other: &Provider) -> bool {
+        Arc::as_ptr(&self.0) as *const () == Arc::as_ptr(&other.0) as *const ()
+    }
+}
+
+impl Eq for Provider {}
@@ -0,0 +1,79 @@
+use crate::raw::query::internal_query;
+use crate::types::{FromSql, Type};
+use crate::{Client, Error};
+
+async fn try_lock(client: &Client, query: &str, params: &[Option<&str>]) -> Result<bool, Error> {
+    let rows = internal_query(client.inner(), query, params).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    bool::from_sql_nullable(&Type::BOOL, row.get(0)).map_err(|e| Error::from_sql(e, 0))
+}
+
+/// Tries to obtain an exclusive transaction-level advisory lock, without waiting.
+///
+/// Returns `true` if the lock has been acquired. The lock is automatically released at the end of
+/// the current transaction; outside of a transaction block, it is released as soon as it is taken.
+pub async fn try_advisory_xact_lock<E>(client: &Client, key: i64) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let key = key.to_string();
+    Ok(try_lock(
+        client,
+        "SELECT pg_try_advisory_xact_lock($1::int8)",
+        &[Some(&key)],
+    )
+    .await?)
+}
+
+/// Like `try_advisory_xact_lock`, but identifies the lock with a pair of `int4` keys.
+pub async fn try_advisory_xact_lock_pair<E>(
+    client: &Client,
+    key1: i32,
+    key2: i32,
+) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let key1 = key1.to_string();
+    let key2 = key2.to_string();
+    Ok(try_lock(
+        client,
+        "SELECT pg_try_advisory_xact_lock($1::int4, $2::int4)",
+        &[Some(&key1), Some(&key2)],
+    )
+    .await?)
+}
+
+/// Obtains an exclusive transaction-level advisory lock, waiting until it becomes available.
+///
+/// The lock is automatically released at the end of the current transaction.
+pub async fn advisory_xact_lock<E>(client: &Client, key: i64) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let key = key.to_string();
+    internal_query(
+        client.inner(),
+        "SELECT pg_advisory_xact_lock($1::int8)",
+        &[Some(&key)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Like `advisory_xact_lock`, but identifies the lock with a pair of `int4` keys.
+pub async fn advisory_xact_lock_pair<E>(client: &Client, key1: i32, key2: i32) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let key1 = key1.to_string();
+    let key2 = key2.to_string();
+    internal_query(
+        client.inner(),
+        "SELECT pg_advisory_xact_lock($1::int4, $2::int4)",
+        &[Some(&key1), Some(&key2)],
+    )
+    .await?;
+    Ok(())
+}
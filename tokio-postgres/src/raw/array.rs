@@ -0,0 +1,68 @@
+use crate::types::Oid;
+use crate::Error;
+use bytes::BytesMut;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types::{self, ArrayDimension};
+use postgres_protocol::IsNull;
+use std::convert::TryFrom;
+
+/// Encodes a one-dimensional array in the binary format, suitable as a parameter value of an array type.
+///
+/// `element_oid` is the OID of the element type (not of the array type), and `encode_element` writes the binary
+/// representation of a single element. An element may be encoded as NULL by returning `IsNull::Yes` without
+/// writing anything; the array header then records that NULLs are present. The lower bound of the dimension is 1.
+///
+/// Multi-dimensional arrays are not supported by this function: their elements are laid out in row-major order
+/// after a header listing every dimension, which `postgres_protocol::types::array_to_sql` can write.
+pub fn encode_array<I, T, F>(
+    values: I,
+    element_oid: Oid,
+    encode_element: F,
+) -> Result<BytesMut, Error>
+where
+    I: IntoIterator<Item = T>,
+    F: Fn(T, &mut BytesMut) -> Result<IsNull, Error>,
+{
+    let values = values.into_iter().collect::<Vec<_>>();
+    let len = i32::try_from(values.len())
+        .map_err(|_| Error::to_sql("too many array elements".into(), 0))?;
+    let dimension = ArrayDimension {
+        len,
+        lower_bound: 1,
+    };
+
+    let mut buf = BytesMut::new();
+    types::array_to_sql(
+        Some(dimension),
+        element_oid,
+        values,
+        |value, buf| encode_element(value, buf).map_err(|e| e.into()),
+        &mut buf,
+    )
+    .map_err(|e| match e.downcast::<Error>() {
+        Ok(e) => *e,
+        Err(e) => Error::to_sql(e, 0),
+    })?;
+
+    Ok(buf)
+}
+
+/// Decodes an array in the binary format, returning its elements with `None` for NULLs.
+///
+/// `decode_element` is called with the binary representation of each non-NULL element. The header is validated but
+/// otherwise ignored: elements of multi-dimensional arrays are returned flattened in row-major order, and the
+/// element type and lower bounds are not checked.
+pub fn decode_array<T, F>(bytes: &[u8], decode_element: F) -> Result<Vec<Option<T>>, Error>
+where
+    F: Fn(&[u8]) -> Result<T, Error>,
+{
+    let array = types::array_from_sql(bytes).map_err(|e| Error::from_sql(e, 0))?;
+
+    let mut values = vec![];
+    let mut elements = array.values();
+    while let Some(element) = elements.next().map_err(|e| Error::from_sql(e, 0))? {
+        values.push(element.map(&decode_element).transpose()?);
+    }
+
+    Ok(values)
+}
@@ -0,0 +1,370 @@
+use crate::raw::query::{QueryStream, Row};
+use crate::{Client, CopyOutStream, Error};
+use arrow_array::builder::{
+    BinaryBuilder, BooleanBuilder, Date32Builder, Float32Builder, Float64Builder, Int16Builder,
+    Int32Builder, Int64Builder, LargeBinaryBuilder, LargeStringBuilder, StringBuilder,
+    Time64MicrosecondBuilder, TimestampMicrosecondBuilder,
+};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, SchemaRef, TimeUnit};
+use bytes::{Buf, Bytes};
+use futures_util::{pin_mut, stream, Stream, TryStreamExt};
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::types;
+use std::error;
+use std::io;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+
+// Offsets between the PostgreSQL epoch (2000-01-01) and the Unix epoch.
+const EPOCH_OFFSET_DAYS: i32 = 10_957;
+const EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+
+/// The maximum number of rows in the record batches produced from a stream.
+pub(crate) const BATCH_SIZE: usize = 8192;
+
+const COPY_MAGIC: &[u8] = b"PGCOPY\n\xff\r\n\0";
+const COPY_HEADER_LEN: usize = COPY_MAGIC.len() + 4 + 4;
+
+type BoxError = Box<dyn error::Error + Sync + Send>;
+
+enum ColumnBuilder {
+    Boolean(BooleanBuilder),
+    Int16(Int16Builder),
+    Int32(Int32Builder),
+    Int64(Int64Builder),
+    Float32(Float32Builder),
+    Float64(Float64Builder),
+    Utf8(StringBuilder),
+    LargeUtf8(LargeStringBuilder),
+    Binary(BinaryBuilder),
+    LargeBinary(LargeBinaryBuilder),
+    Date32(Date32Builder),
+    Time64(Time64MicrosecondBuilder),
+    Timestamp(TimestampMicrosecondBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(field: &Field) -> Result<ColumnBuilder, BoxError> {
+        let builder = match field.data_type() {
+            DataType::Boolean => ColumnBuilder::Boolean(BooleanBuilder::new()),
+            DataType::Int16 => ColumnBuilder::Int16(Int16Builder::new()),
+            DataType::Int32 => ColumnBuilder::Int32(Int32Builder::new()),
+            DataType::Int64 => ColumnBuilder::Int64(Int64Builder::new()),
+            DataType::Float32 => ColumnBuilder::Float32(Float32Builder::new()),
+            DataType::Float64 => ColumnBuilder::Float64(Float64Builder::new()),
+            DataType::Utf8 => ColumnBuilder::Utf8(StringBuilder::new()),
+            DataType::LargeUtf8 => ColumnBuilder::LargeUtf8(LargeStringBuilder::new()),
+            DataType::Binary => ColumnBuilder::Binary(BinaryBuilder::new()),
+            DataType::LargeBinary => ColumnBuilder::LargeBinary(LargeBinaryBuilder::new()),
+            DataType::Date32 => ColumnBuilder::Date32(Date32Builder::new()),
+            DataType::Time64(TimeUnit::Microsecond) => {
+                ColumnBuilder::Time64(Time64MicrosecondBuilder::new())
+            }
+            DataType::Timestamp(TimeUnit::Microsecond, tz) => ColumnBuilder::Timestamp(
+                TimestampMicrosecondBuilder::new().with_timezone_opt(tz.clone()),
+            ),
+            data_type => return Err(format!("unsupported Arrow data type {}", data_type).into()),
+        };
+
+        Ok(builder)
+    }
+
+    fn append(&mut self, value: Option<&[u8]>) -> Result<(), BoxError> {
+        let value = match value {
+            Some(value) => value,
+            None => {
+                self.append_null();
+                return Ok(());
+            }
+        };
+
+        match self {
+            ColumnBuilder::Boolean(b) => b.append_value(types::bool_from_sql(value)?),
+            ColumnBuilder::Int16(b) => b.append_value(types::int2_from_sql(value)?),
+            ColumnBuilder::Int32(b) => b.append_value(types::int4_from_sql(value)?),
+            ColumnBuilder::Int64(b) => b.append_value(types::int8_from_sql(value)?),
+            ColumnBuilder::Float32(b) => b.append_value(types::float4_from_sql(value)?),
+            ColumnBuilder::Float64(b) => b.append_value(types::float8_from_sql(value)?),
+            ColumnBuilder::Utf8(b) => b.append_value(types::text_from_sql(value)?),
+            ColumnBuilder::LargeUtf8(b) => b.append_value(types::text_from_sql(value)?),
+            ColumnBuilder::Binary(b) => b.append_value(types::bytea_from_sql(value)),
+            ColumnBuilder::LargeBinary(b) => b.append_value(types::bytea_from_sql(value)),
+            ColumnBuilder::Date32(b) => {
+                let days = types::date_from_sql(value)?
+                    .checked_add(EPOCH_OFFSET_DAYS)
+                    .ok_or("date out of range")?;
+                b.append_value(days)
+            }
+            ColumnBuilder::Time64(b) => b.append_value(types::time_from_sql(value)?),
+            ColumnBuilder::Timestamp(b) => {
+                let micros = types::timestamp_from_sql(value)?
+                    .checked_add(EPOCH_OFFSET_MICROS)
+                    .ok_or("timestamp out of range")?;
+                b.append_value(micros)
+            }
+        }
+
+        Ok(())
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            ColumnBuilder::Boolean(b) => b.append_null(),
+            ColumnBuilder::Int16(b) => b.append_null(),
+            ColumnBuilder::Int32(b) => b.append_null(),
+            ColumnBuilder::Int64(b) => b.append_null(),
+            ColumnBuilder::Float32(b) => b.append_null(),
+            ColumnBuilder::Float64(b) => b.append_null(),
+            ColumnBuilder::Utf8(b) => b.append_null(),
+            ColumnBuilder::LargeUtf8(b) => b.append_null(),
+            ColumnBuilder::Binary(b) => b.append_null(),
+            ColumnBuilder::LargeBinary(b) => b.append_null(),
+            ColumnBuilder::Date32(b) => b.append_null(),
+            ColumnBuilder::Time64(b) => b.append_null(),
+            ColumnBuilder::Timestamp(b) => b.append_null(),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Boolean(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int16(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Int64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Float64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Utf8(b) => Arc::new(b.finish()),
+            ColumnBuilder::LargeUtf8(b) => Arc::new(b.finish()),
+            ColumnBuilder::Binary(b) => Arc::new(b.finish()),
+            ColumnBuilder::LargeBinary(b) => Arc::new(b.finish()),
+            ColumnBuilder::Date32(b) => Arc::new(b.finish()),
+            ColumnBuilder::Time64(b) => Arc::new(b.finish()),
+            ColumnBuilder::Timestamp(b) => Arc::new(b.finish()),
+        }
+    }
+}
+
+/// Accumulates binary-format field values into Arrow arrays following a schema.
+pub(crate) struct BatchBuilder {
+    schema: SchemaRef,
+    columns: Vec<ColumnBuilder>,
+}
+
+impl BatchBuilder {
+    pub(crate) fn new(schema: SchemaRef) -> Result<BatchBuilder, Error> {
+        let columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| ColumnBuilder::new(field).map_err(|e| Error::from_sql(e, idx)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(BatchBuilder { schema, columns })
+    }
+
+    /// Appends a row, given the binary-format value of each of its fields.
+    pub(crate) fn append_row<'a, I>(&mut self, fields: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = Option<&'a [u8]>>,
+    {
+        let mut count = 0;
+        for (idx, value) in fields.into_iter().enumerate() {
+            let column = match self.columns.get_mut(idx) {
+                Some(column) => column,
+                None => {
+                    return Err(Error::from_sql(
+                        format!("row has more than {} fields", self.columns.len()).into(),
+                        idx,
+                    ))
+                }
+            };
+            if value.is_none() && !self.schema.field(idx).is_nullable() {
+                return Err(Error::from_sql(
+                    "unexpected NULL in non-nullable field".into(),
+                    idx,
+                ));
+            }
+            column.append(value).map_err(|e| Error::from_sql(e, idx))?;
+            count += 1;
+        }
+
+        if count != self.columns.len() {
+            return Err(Error::from_sql(
+                format!("expected {} fields, got {}", self.columns.len(), count).into(),
+                count,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Builds a `RecordBatch` out of the rows appended so far, resetting the builder.
+    pub(crate) fn finish(&mut self) -> Result<RecordBatch, Error> {
+        let columns = self.columns.iter_mut().map(|c| c.finish()).collect();
+        RecordBatch::try_new(self.schema.clone(), columns)
+            .map_err(|e| Error::from_sql(Box::new(e), 0))
+    }
+}
+
+impl<E> QueryStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    /// Collects the rows of the stream into an Arrow `RecordBatch`.
+    ///
+    /// Each field is decoded according to the data type of the corresponding field of `schema`, so the portal must
+    /// have been bound requesting binary results. Supported data types are `Boolean`, `Int16`, `Int32`, `Int64`,
+    /// `Float32`, `Float64`, `Utf8`, `LargeUtf8`, `Binary`, `LargeBinary`, `Date32`, `Time64(Microsecond)` and
+    /// `Timestamp(Microsecond, _)`.
+    ///
+    /// Requires the `arrow` Cargo feature.
+    pub async fn collect_arrow(self, schema: SchemaRef) -> Result<RecordBatch, E> {
+        let mut builder = BatchBuilder::new(schema)?;
+
+        let stream = self;
+        pin_mut!(stream);
+        while let Some(message) = stream.try_next().await? {
+            if let Message::DataRow(body) = message {
+                let row = Row::new(body)?;
+                builder.append_row((0..row.len()).map(|idx| row.get(idx)))?;
+            }
+        }
+
+        Ok(builder.finish()?)
+    }
+}
+
+struct CopyOutArrow {
+    stream: Pin<Box<CopyOutStream>>,
+    builder: BatchBuilder,
+    ranges: Vec<Option<Range<usize>>>,
+    header: bool,
+    done: bool,
+}
+
+impl CopyOutArrow {
+    /// Decodes a row of a binary copy stream into the batch builder, returning `false` on the trailer.
+    fn decode_row(&mut self, chunk: &Bytes) -> Result<bool, Error> {
+        let mut buf = &chunk[..];
+
+        if !self.header {
+            check_remaining(buf, COPY_HEADER_LEN)?;
+            if !buf.starts_with(COPY_MAGIC) {
+                return Err(Error::parse(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "invalid magic value",
+                )));
+            }
+            buf.advance(COPY_MAGIC.len());
+
+            let flags = buf.get_i32();
+            if flags & (1 << 16) != 0 {
+                return Err(Error::parse(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected OIDs in binary copy stream",
+                )));
+            }
+
+            let header_extension = buf.get_u32() as usize;
+            check_remaining(buf, header_extension)?;
+            buf.advance(header_extension);
+            self.header = true;
+        }
+
+        check_remaining(buf, 2)?;
+        let len = buf.get_i16();
+        if len == -1 {
+            return Ok(false);
+        }
+
+        self.ranges.clear();
+        for _ in 0..len {
+            check_remaining(buf, 4)?;
+            let len = buf.get_i32();
+            if len == -1 {
+                self.ranges.push(None);
+            } else {
+                let len = len as usize;
+                check_remaining(buf, len)?;
+                let start = chunk.len() - buf.len();
+                self.ranges.push(Some(start..start + len));
+                buf.advance(len);
+            }
+        }
+
+        self.builder.append_row(
+            self.ranges
+                .iter()
+                .map(|range| range.clone().map(|range| &chunk[range])),
+        )?;
+        Ok(true)
+    }
+
+    async fn next_batch(&mut self) -> Result<Option<RecordBatch>, Error> {
+        let mut rows = 0;
+        while !self.done && rows < BATCH_SIZE {
+            match self.stream.try_next().await? {
+                Some(chunk) => {
+                    if self.decode_row(&chunk)? {
+                        rows += 1;
+                    } else {
+                        self.done = true;
+                    }
+                }
+                None => self.done = true,
+            }
+        }
+
+        if rows == 0 {
+            return Ok(None);
+        }
+        self.builder.finish().map(Some)
+    }
+}
+
+fn check_remaining(buf: &[u8], len: usize) -> Result<(), Error> {
+    if buf.len() < len {
+        Err(Error::parse(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "unexpected EOF",
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Runs a query through `COPY (query) TO STDOUT (FORMAT BINARY)`, decoding its rows into Arrow record batches.
+///
+/// Field values are appended to the Arrow arrays straight from the copy stream, following the data types of
+/// `schema` (see `QueryStream::collect_arrow` for the supported ones). Each record batch holds up to 8192 rows.
+///
+/// Requires the `arrow` Cargo feature.
+pub async fn copy_out_arrow<E>(
+    client: &Client,
+    query: &str,
+    schema: SchemaRef,
+) -> Result<impl Stream<Item = Result<RecordBatch, E>>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let builder = BatchBuilder::new(schema)?;
+    let query = format!("COPY ({}) TO STDOUT (FORMAT BINARY)", query);
+    let stream = client.copy_out(&query).await?;
+
+    let state = CopyOutArrow {
+        stream: Box::pin(stream),
+        builder,
+        ranges: vec![],
+        header: false,
+        done: false,
+    };
+
+    Ok(stream::try_unfold(state, |mut state| async move {
+        match state.next_batch().await? {
+            Some(batch) => Ok(Some((batch, state))),
+            None => Ok(None),
+        }
+    }))
+}
@@ -0,0 +1,459 @@
+use crate::raw::identifier;
+use crate::types::ToSql;
+use crate::Error;
+use std::io;
+
+/// A boxed query parameter, as returned by the query builders.
+pub type BoxedParam = Box<dyn ToSql + Sync + Send>;
+
+/// The direction of an `ORDER BY` clause.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Order {
+    /// `ASC` order.
+    Asc,
+    /// `DESC` order.
+    Desc,
+}
+
+impl Order {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Condition {
+    Eq(String),
+    In(String, usize),
+}
+
+/// A builder of parameterized `SELECT` queries.
+///
+/// Table and column names are validated as plain identifiers when the query is built, while values are only ever
+/// sent as parameters, so that no user input ends up in the SQL text.
+#[derive(Debug, Default)]
+pub struct QueryBuilder {
+    columns: Vec<String>,
+    table: Option<String>,
+    conditions: Vec<Condition>,
+    params: Vec<BoxedParam>,
+    order_by: Vec<(String, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl QueryBuilder {
+    /// Creates a new builder, selecting all the columns.
+    pub fn new() -> QueryBuilder {
+        QueryBuilder::default()
+    }
+
+    /// Adds columns to the select list.
+    #[must_use]
+    pub fn select(mut self, columns: &[&str]) -> Self {
+        self.columns
+            .extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Sets the table to select from. It may be schema-qualified (`schema.table`).
+    #[must_use]
+    pub fn from(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    /// Restricts the rows to the ones where `column` is equal to `value`.
+    ///
+    /// Conditions are joined with `AND`.
+    #[must_use]
+    pub fn where_eq<T>(mut self, column: &str, value: T) -> Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.conditions.push(Condition::Eq(column.to_string()));
+        self.params.push(Box::new(value));
+        self
+    }
+
+    /// Restricts the rows to the ones where `column` is equal to one of `values`.
+    ///
+    /// No row matches if `values` is empty.
+    #[must_use]
+    pub fn where_in<I, T>(mut self, column: &str, values: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: ToSql + Sync + Send + 'static,
+    {
+        let len = self.params.len();
+        self.params.extend(
+            values
+                .into_iter()
+                .map(|value| Box::new(value) as BoxedParam),
+        );
+        self.conditions
+            .push(Condition::In(column.to_string(), self.params.len() - len));
+        self
+    }
+
+    /// Adds a column to the `ORDER BY` clause.
+    #[must_use]
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        self.order_by.push((column.to_string(), order));
+        self
+    }
+
+    /// Sets the maximum number of rows to return.
+    #[must_use]
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the number of rows to skip.
+    #[must_use]
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Builds the query, returning its SQL text and its parameters.
+    ///
+    /// Fails if no table has been set or if a name is not a valid identifier.
+    pub fn build(self) -> Result<(String, Vec<BoxedParam>), Error> {
+        let table = self
+            .table
+            .ok_or_else(|| invalid("no table to select from"))?;
+        identifier::validate(&table, true)?;
+
+        let select_list = if self.columns.is_empty() {
+            "*".to_string()
+        } else {
+            validate_all(&self.columns)?;
+            self.columns.join(", ")
+        };
+        let mut sql = format!("SELECT {} FROM {}", select_list, table);
+
+        let mut next_param = 1;
+        for (i, condition) in self.conditions.iter().enumerate() {
+            sql.push_str(if i == 0 { " WHERE " } else { " AND " });
+            match condition {
+                Condition::Eq(column) => {
+                    identifier::validate(column, false)?;
+                    sql.push_str(&format!("{} = ${}", column, next_param));
+                    next_param += 1;
+                }
+                Condition::In(_, 0) => sql.push_str("FALSE"),
+                Condition::In(column, len) => {
+                    identifier::validate(column, false)?;
+                    let placeholders = (next_param..next_param + len)
+                        .map(|i| format!("${}", i))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    sql.push_str(&format!("{} IN ({})", column, placeholders));
+                    next_param += len;
+                }
+            }
+        }
+
+        for (i, (column, order)) in self.order_by.iter().enumerate() {
+            identifier::validate(column, false)?;
+            sql.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            sql.push_str(&format!("{} {}", column, order.as_sql()));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+        if let Some(offset) = self.offset {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+
+        Ok((sql, self.params))
+    }
+}
+
+fn validate_all(columns: &[String]) -> Result<(), Error> {
+    for column in columns {
+        identifier::validate(column, false)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn invalid(message: &str) -> Error {
+    Error::encode(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+#[derive(Debug)]
+enum ConflictAction {
+    Error,
+    DoNothing,
+    DoUpdate(Vec<(String, BoxedParam)>),
+}
+
+/// A builder of parameterized `INSERT` statements.
+///
+/// Table and column names are validated as plain identifiers when the statement is built, and all values are sent as
+/// parameters.
+#[derive(Debug)]
+pub struct InsertBuilder {
+    table: Option<String>,
+    values: Vec<(String, BoxedParam)>,
+    conflict_target: Vec<String>,
+    conflict_action: ConflictAction,
+    returning: Vec<String>,
+}
+
+impl Default for InsertBuilder {
+    fn default() -> InsertBuilder {
+        InsertBuilder {
+            table: None,
+            values: vec![],
+            conflict_target: vec![],
+            conflict_action: ConflictAction::Error,
+            returning: vec![],
+        }
+    }
+}
+
+impl InsertBuilder {
+    /// Creates a new builder.
+    pub fn new() -> InsertBuilder {
+        InsertBuilder::default()
+    }
+
+    /// Sets the table to insert into. It may be schema-qualified (`schema.table`).
+    #[must_use]
+    pub fn into_table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    /// Sets the value of a column of the inserted row.
+    ///
+    /// Columns which are not set get their default value.
+    #[must_use]
+    pub fn column<T>(mut self, column: &str, value: T) -> Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.values.push((column.to_string(), Box::new(value)));
+        self
+    }
+
+    /// Sets the columns of the unique index used to detect conflicts.
+    ///
+    /// A target is required by `on_conflict_do_update`, and optional with `on_conflict_do_nothing`.
+    #[must_use]
+    pub fn on_conflict(mut self, columns: &[&str]) -> Self {
+        self.conflict_target = columns.iter().map(|column| column.to_string()).collect();
+        self
+    }
+
+    /// Skips the insertion if the row conflicts with an existing one.
+    #[must_use]
+    pub fn on_conflict_do_nothing(mut self) -> Self {
+        self.conflict_action = ConflictAction::DoNothing;
+        self
+    }
+
+    /// Sets a column of the existing row to `value` if the row conflicts with it.
+    ///
+    /// This replaces `on_conflict_do_nothing`, and may be called several times to update several columns.
+    #[must_use]
+    pub fn on_conflict_do_update<T>(mut self, column: &str, value: T) -> Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        let assignment = (column.to_string(), Box::new(value) as BoxedParam);
+        match &mut self.conflict_action {
+            ConflictAction::DoUpdate(assignments) => assignments.push(assignment),
+            action => *action = ConflictAction::DoUpdate(vec![assignment]),
+        }
+        self
+    }
+
+    /// Adds columns of the inserted row to return.
+    #[must_use]
+    pub fn returning(mut self, columns: &[&str]) -> Self {
+        self.returning
+            .extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Builds the statement, returning its SQL text and its parameters.
+    ///
+    /// Fails if no table has been set, if `on_conflict_do_update` is used without a conflict target, or if a name is
+    /// not a valid identifier.
+    pub fn build(self) -> Result<(String, Vec<BoxedParam>), Error> {
+        let table = self
+            .table
+            .ok_or_else(|| invalid("no table to insert into"))?;
+        identifier::validate(&table, true)?;
+
+        let mut params = vec![];
+        let mut sql = format!("INSERT INTO {}", table);
+        if self.values.is_empty() {
+            sql.push_str(" DEFAULT VALUES");
+        } else {
+            let mut columns = vec![];
+            let mut placeholders = vec![];
+            for (column, value) in self.values {
+                identifier::validate(&column, false)?;
+                columns.push(column);
+                params.push(value);
+                placeholders.push(format!("${}", params.len()));
+            }
+            sql.push_str(&format!(
+                " ({}) VALUES ({})",
+                columns.join(", "),
+                placeholders.join(", ")
+            ));
+        }
+
+        let target = if self.conflict_target.is_empty() {
+            String::new()
+        } else {
+            validate_all(&self.conflict_target)?;
+            format!(" ({})", self.conflict_target.join(", "))
+        };
+        match self.conflict_action {
+            ConflictAction::Error => {}
+            ConflictAction::DoNothing => {
+                sql.push_str(&format!(" ON CONFLICT{} DO NOTHING", target));
+            }
+            ConflictAction::DoUpdate(assignments) => {
+                if target.is_empty() {
+                    return Err(invalid("ON CONFLICT DO UPDATE requires a conflict target"));
+                }
+                let mut set = vec![];
+                for (column, value) in assignments {
+                    identifier::validate(&column, false)?;
+                    params.push(value);
+                    set.push(format!("{} = ${}", column, params.len()));
+                }
+                sql.push_str(&format!(
+                    " ON CONFLICT{} DO UPDATE SET {}",
+                    target,
+                    set.join(", ")
+                ));
+            }
+        }
+
+        push_returning(&mut sql, &self.returning)?;
+
+        Ok((sql, params))
+    }
+}
+
+fn push_returning(sql: &mut String, columns: &[String]) -> Result<(), Error> {
+    if !columns.is_empty() {
+        validate_all(columns)?;
+        sql.push_str(&format!(" RETURNING {}", columns.join(", ")));
+    }
+    Ok(())
+}
+
+/// A builder of parameterized `UPDATE` statements.
+///
+/// Table and column names are validated as plain identifiers when the statement is built, and all values are sent as
+/// parameters. To guard against updating a whole table by mistake, building a statement without any condition fails
+/// unless `allow_update_all` has been called.
+#[derive(Debug, Default)]
+pub struct UpdateBuilder {
+    table: Option<String>,
+    assignments: Vec<(String, BoxedParam)>,
+    conditions: Vec<(String, BoxedParam)>,
+    returning: Vec<String>,
+    allow_update_all: bool,
+}
+
+impl UpdateBuilder {
+    /// Creates a new builder.
+    pub fn new() -> UpdateBuilder {
+        UpdateBuilder::default()
+    }
+
+    /// Sets the table to update. It may be schema-qualified (`schema.table`).
+    #[must_use]
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = Some(table.to_string());
+        self
+    }
+
+    /// Sets a column of the updated rows to `value`.
+    #[must_use]
+    pub fn set<T>(mut self, column: &str, value: T) -> Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.assignments.push((column.to_string(), Box::new(value)));
+        self
+    }
+
+    /// Restricts the update to the rows where `column` is equal to `value`.
+    ///
+    /// Conditions are joined with `AND`.
+    #[must_use]
+    pub fn where_eq<T>(mut self, column: &str, value: T) -> Self
+    where
+        T: ToSql + Sync + Send + 'static,
+    {
+        self.conditions.push((column.to_string(), Box::new(value)));
+        self
+    }
+
+    /// Adds columns of the updated rows to return.
+    #[must_use]
+    pub fn returning(mut self, columns: &[&str]) -> Self {
+        self.returning
+            .extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Allows building a statement without any condition, which updates every row of the table.
+    #[must_use]
+    pub fn allow_update_all(mut self) -> Self {
+        self.allow_update_all = true;
+        self
+    }
+
+    /// Builds the statement, returning its SQL text and its parameters.
+    ///
+    /// Fails if no table or no column to set has been given, if there is no condition and `allow_update_all` has not
+    /// been called, or if a name is not a valid identifier.
+    pub fn build(self) -> Result<(String, Vec<BoxedParam>), Error> {
+        let table = self.table.ok_or_else(|| invalid("no table to update"))?;
+        identifier::validate(&table, true)?;
+        if self.assignments.is_empty() {
+            return Err(invalid("no column to update"));
+        }
+        if self.conditions.is_empty() && !self.allow_update_all {
+            return Err(invalid(
+                "UPDATE without a WHERE clause requires allow_update_all",
+            ));
+        }
+
+        let mut params = vec![];
+        let mut set = vec![];
+        for (column, value) in self.assignments {
+            identifier::validate(&column, false)?;
+            params.push(value);
+            set.push(format!("{} = ${}", column, params.len()));
+        }
+        let mut sql = format!("UPDATE {} SET {}", table, set.join(", "));
+
+        for (i, (column, value)) in self.conditions.into_iter().enumerate() {
+            identifier::validate(&column, false)?;
+            params.push(value);
+            sql.push_str(if i == 0 { " WHERE " } else { " AND " });
+            sql.push_str(&format!("{} = ${}", column, params.len()));
+        }
+
+        push_returning(&mut sql, &self.returning)?;
+
+        Ok((sql, params))
+    }
+}
@@ -0,0 +1,44 @@
+use crate::types::Oid;
+use crate::Error;
+use bytes::{BufMut, BytesMut};
+use postgres_protocol::IsNull;
+use std::convert::TryFrom;
+
+/// Encodes a composite value in the binary format into `buf`, suitable as a parameter value of a composite type.
+///
+/// Each field is given as the OID of its type and a function writing its binary representation, in the order the
+/// attributes are declared in the type. A field is encoded as NULL when its function is `None`, or when the function
+/// returns `IsNull::Yes`; anything it wrote is discarded in that case.
+///
+/// All the field functions share the same type, so fields of different types are usually passed as boxed closures or
+/// function pointers.
+pub fn encode_composite<F>(fields: &[(Oid, Option<F>)], buf: &mut BytesMut) -> Result<(), Error>
+where
+    F: Fn(&mut BytesMut) -> Result<IsNull, Error>,
+{
+    let count = i32::try_from(fields.len())
+        .map_err(|_| Error::to_sql("too many composite fields".into(), 0))?;
+    buf.put_i32(count);
+
+    for (oid, encode_field) in fields {
+        buf.put_u32(*oid);
+
+        let base = buf.len();
+        buf.put_i32(0);
+        let is_null = match encode_field {
+            Some(encode_field) => encode_field(buf)?,
+            None => IsNull::Yes,
+        };
+        let len = match is_null {
+            IsNull::No => i32::try_from(buf.len() - base - 4)
+                .map_err(|_| Error::to_sql("value too large to transmit".into(), 0))?,
+            IsNull::Yes => {
+                buf.truncate(base + 4);
+                -1
+            }
+        };
+        buf[base..base + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,248 @@
+use crate::raw::identifier;
+use crate::{Client, Error};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{pin_mut, SinkExt, Stream, StreamExt, TryStreamExt};
+use std::future::Future;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Options of a CSV `COPY` command.
+///
+/// Options left unset use the server defaults.
+#[derive(Debug, Clone, Default)]
+pub struct CsvOptions {
+    delimiter: Option<char>,
+    quote: Option<char>,
+    escape: Option<char>,
+    null: Option<String>,
+    header: bool,
+}
+
+impl CsvOptions {
+    /// Creates a new set of options, using the server defaults.
+    pub fn new() -> CsvOptions {
+        CsvOptions::default()
+    }
+
+    /// Sets the character separating columns. Defaults to `,`.
+    #[must_use]
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+
+    /// Sets the quoting character. Defaults to `"`.
+    #[must_use]
+    pub fn quote(mut self, quote: char) -> Self {
+        self.quote = Some(quote);
+        self
+    }
+
+    /// Sets the character escaping the quoting character. Defaults to the quoting character.
+    #[must_use]
+    pub fn escape(mut self, escape: char) -> Self {
+        self.escape = Some(escape);
+        self
+    }
+
+    /// Sets the string representing a null value. Defaults to an unquoted empty string.
+    #[must_use]
+    pub fn null(mut self, null: impl Into<String>) -> Self {
+        self.null = Some(null.into());
+        self
+    }
+
+    /// Sets whether the data starts with a header line. Defaults to `false`.
+    #[must_use]
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    fn to_sql(&self) -> String {
+        let mut sql = "FORMAT CSV".to_string();
+        if let Some(delimiter) = self.delimiter {
+            sql.push_str(&format!(
+                ", DELIMITER {}",
+                identifier::literal(&delimiter.to_string())
+            ));
+        }
+        if let Some(quote) = self.quote {
+            sql.push_str(&format!(
+                ", QUOTE {}",
+                identifier::literal(&quote.to_string())
+            ));
+        }
+        if let Some(escape) = self.escape {
+            sql.push_str(&format!(
+                ", ESCAPE {}",
+                identifier::literal(&escape.to_string())
+            ));
+        }
+        if let Some(null) = &self.null {
+            sql.push_str(&format!(", NULL {}", identifier::literal(null)));
+        }
+        if self.header {
+            sql.push_str(", HEADER");
+        }
+        sql
+    }
+}
+
+/// Imports CSV data read from `reader` into a table.
+///
+/// `table` may be schema-qualified (`schema.table`). If `columns` is empty, the data must contain
+/// all the columns of the table, in order. Returns the number of rows copied.
+pub async fn copy_in_csv<R, E>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    options: CsvOptions,
+    reader: R,
+) -> Result<u64, E>
+where
+    R: AsyncRead + Unpin,
+    E: std::convert::From<crate::error::Error>,
+{
+    let query = format!(
+        "COPY {} FROM STDIN WITH ({})",
+        copy_target(table, columns)?,
+        options.to_sql()
+    );
+
+    let sink = client.copy_in::<_, Bytes>(&query).await?;
+    pin_mut!(sink);
+
+    let mut reader = reader;
+    let mut buf = BytesMut::with_capacity(8 * 1024);
+    loop {
+        buf.reserve(8 * 1024);
+        if reader.read_buf(&mut buf).await.map_err(Error::io)? == 0 {
+            break;
+        }
+        sink.send(buf.split().freeze()).await?;
+    }
+
+    Ok(sink.finish().await?)
+}
+
+/// Imports rows of text values into a table, using the text format of `COPY`.
+///
+/// `table` may be schema-qualified (`schema.table`). If `columns` is empty, each row must contain all the columns of
+/// the table, in order. Values are written in their text representation, escaping backslashes, tabs, newlines and
+/// carriage returns; `None` is written as `NULL`. Returns the number of rows copied.
+pub async fn copy_in_rows<E>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    rows: impl IntoIterator<Item = Vec<Option<String>>>,
+) -> Result<u64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let query = format!("COPY {} FROM STDIN", copy_target(table, columns)?);
+    let sink = client.copy_in::<_, Bytes>(&query).await?;
+    pin_mut!(sink);
+
+    let mut buf = BytesMut::with_capacity(8 * 1024);
+    for row in rows {
+        for (i, value) in row.iter().enumerate() {
+            if i > 0 {
+                buf.extend_from_slice(b"\t");
+            }
+            match value {
+                Some(value) => escape_text(value, &mut buf),
+                None => buf.extend_from_slice(b"\\N"),
+            }
+        }
+        buf.extend_from_slice(b"\n");
+
+        if buf.len() >= 8 * 1024 {
+            sink.send(buf.split().freeze()).await?;
+        }
+    }
+    if !buf.is_empty() {
+        sink.send(buf.split().freeze()).await?;
+    }
+
+    Ok(sink.finish().await?)
+}
+
+fn escape_text(value: &str, buf: &mut BytesMut) {
+    for byte in value.bytes() {
+        match byte {
+            b'\\' => buf.extend_from_slice(b"\\\\"),
+            b'\t' => buf.extend_from_slice(b"\\t"),
+            b'\n' => buf.extend_from_slice(b"\\n"),
+            b'\r' => buf.extend_from_slice(b"\\r"),
+            byte => buf.put_u8(byte),
+        }
+    }
+}
+
+/// Returns the table and column list of a `COPY` command, after validating the identifiers.
+fn copy_target(table: &str, columns: &[&str]) -> Result<String, Error> {
+    identifier::validate(table, true)?;
+    for column in columns {
+        identifier::validate(column, false)?;
+    }
+
+    if columns.is_empty() {
+        Ok(table.to_string())
+    } else {
+        Ok(format!("{} ({})", table, columns.join(", ")))
+    }
+}
+
+/// Runs a `COPY ... FROM STDIN` query, serializing the objects of `source` with `transform`.
+///
+/// `transform` writes each object into the buffer, in the format expected by the `COPY` query (e.g. CSV lines).
+/// The returned future is awaited before the next object is serialized; since it cannot borrow the buffer, the
+/// bytes must be written before it is returned. The buffer is sent as `CopyData` messages of about 8KiB. Returns
+/// the number of rows copied.
+pub async fn copy_in_with_transform<T, S, F, Fut, E>(
+    client: &Client,
+    query: &str,
+    source: S,
+    transform: F,
+) -> Result<u64, E>
+where
+    S: Stream<Item = T>,
+    F: Fn(T, &mut BytesMut) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: std::convert::From<crate::error::Error>,
+{
+    let sink = client.copy_in::<_, Bytes>(query).await?;
+    pin_mut!(sink);
+    pin_mut!(source);
+
+    let mut buf = BytesMut::with_capacity(8 * 1024);
+    while let Some(object) = source.next().await {
+        transform(object, &mut buf).await?;
+        if buf.len() >= 8 * 1024 {
+            sink.send(buf.split().freeze()).await?;
+        }
+    }
+    if !buf.is_empty() {
+        sink.send(buf.split().freeze()).await?;
+    }
+
+    Ok(sink.finish().await?)
+}
+
+/// Exports the rows returned by `query` as CSV data.
+///
+/// The query is wrapped into a `COPY (query) TO STDOUT` command, and the returned stream yields
+/// the CSV data as sent by the server.
+pub async fn copy_out_csv<E>(
+    client: &Client,
+    query: &str,
+    options: CsvOptions,
+) -> Result<impl Stream<Item = Result<Bytes, E>>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let query = format!("COPY ({}) TO STDOUT WITH ({})", query, options.to_sql());
+    let stream = client.copy_out(&query).await?;
+
+    Ok(stream.map_err(E::from))
+}
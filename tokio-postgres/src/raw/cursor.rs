@@ -0,0 +1,107 @@
+use crate::client::InnerClient;
+use crate::raw::identifier;
+use crate::raw::simple_query::{internal_simple_query, SimpleQueryRow};
+use crate::simple_query::batch_execute;
+use crate::{Client, Error};
+use futures_util::{stream, Stream, TryStreamExt};
+use postgres_protocol::message::backend::Message;
+use std::sync::Arc;
+
+/// The number of rows fetched at once by default.
+const DEFAULT_FETCH_SIZE: u32 = 100;
+
+/// A server-side cursor, declared by `declare_cursor`.
+///
+/// Cursors only exist for the duration of the transaction in which they were declared.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    name: String,
+    fetch_size: u32,
+}
+
+impl Cursor {
+    /// Returns the name of the cursor.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of rows fetched at once by `fetch_stream`.
+    pub fn fetch_size(&self) -> u32 {
+        self.fetch_size
+    }
+
+    /// Sets the number of rows fetched at once by `fetch_stream`.
+    ///
+    /// Defaults to 100.
+    pub fn set_fetch_size(&mut self, fetch_size: u32) -> &mut Cursor {
+        self.fetch_size = fetch_size.max(1);
+        self
+    }
+
+    /// Returns a stream of the remaining rows of the cursor.
+    ///
+    /// Rows are fetched lazily in batches of `fetch_size` rows with `FETCH n FROM cursor`, so that only a batch is
+    /// held in memory at once. The stream ends when the cursor is exhausted.
+    pub fn fetch_stream<E>(&self, client: &Client) -> impl Stream<Item = Result<SimpleQueryRow, E>>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let inner = client.inner().clone();
+        let query = format!("FETCH {} FROM {}", self.fetch_size, self.name);
+        let fetch_size = self.fetch_size as usize;
+
+        stream::try_unfold(false, move |exhausted| {
+            let inner = inner.clone();
+            let query = query.clone();
+            async move {
+                if exhausted {
+                    return Ok::<_, E>(None);
+                }
+
+                let rows = fetch(&inner, &query).await?;
+                if rows.is_empty() {
+                    return Ok(None);
+                }
+
+                // A short batch means that the cursor has been exhausted.
+                let exhausted = rows.len() < fetch_size;
+                Ok(Some((stream::iter(rows.into_iter().map(Ok)), exhausted)))
+            }
+        })
+        .try_flatten()
+    }
+}
+
+/// Runs a `FETCH` command, returning the fetched rows.
+async fn fetch(client: &Arc<InnerClient>, query: &str) -> Result<Vec<SimpleQueryRow>, Error> {
+    let mut responses = internal_simple_query(client, query)?;
+    let mut rows = vec![];
+    loop {
+        match responses.next().await? {
+            Message::RowDescription(_) | Message::CommandComplete(_) => {}
+            Message::DataRow(body) => rows.push(SimpleQueryRow::new(body)?),
+            Message::ReadyForQuery(_) => return Ok(rows),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Declares a cursor named `name` over the results of `query`.
+///
+/// Cursors must be declared within a transaction block. The rows are then fetched through the returned `Cursor`.
+pub async fn declare_cursor<E>(client: &Client, name: &str, query: &str) -> Result<Cursor, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(name, false)?;
+    batch_execute(
+        client.inner(),
+        &format!("DECLARE {} NO SCROLL CURSOR FOR {}", name, query),
+    )
+    .await?;
+
+    Ok(Cursor {
+        name: name.to_string(),
+        fetch_size: DEFAULT_FETCH_SIZE,
+    })
+}
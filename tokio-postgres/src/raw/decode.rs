@@ -0,0 +1,93 @@
+use crate::raw::query::{QueryStream, Row};
+use crate::raw::simple_query::SimpleColumn;
+use crate::types::FromSqlOwned;
+use crate::Error;
+use futures_util::{pin_mut, TryStreamExt};
+use postgres_protocol::message::backend::Message;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// A type which can be decoded from a row returned by the raw interface.
+///
+/// Implementations are handed the description of the row columns, which is shared between all the rows of a result
+/// set. Tuples of up to 8 `FromSql` types are decoded from the columns in order.
+pub trait FromRow: Sized {
+    /// Decodes a value from a row described by `columns`.
+    fn from_row(row: &Row, columns: &[SimpleColumn]) -> Result<Self, Error>;
+}
+
+macro_rules! tuple_from_row {
+    ($($t:ident $idx:tt),+) => {
+        impl<$($t),+> FromRow for ($($t,)+)
+        where
+            $($t: FromSqlOwned),+
+        {
+            fn from_row(row: &Row, columns: &[SimpleColumn]) -> Result<Self, Error> {
+                Ok(($(row.try_get::<$t>(columns, $idx)?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(T0 0);
+tuple_from_row!(T0 0, T1 1);
+tuple_from_row!(T0 0, T1 1, T2 2);
+tuple_from_row!(T0 0, T1 1, T2 2, T3 3);
+tuple_from_row!(T0 0, T1 1, T2 2, T3 3, T4 4);
+tuple_from_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+tuple_from_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+tuple_from_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
+
+/// Decodes the rows of a stream into values of type `T`.
+pub struct RowDecoder<T> {
+    _p: PhantomData<fn() -> T>,
+}
+
+impl<T> fmt::Debug for RowDecoder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RowDecoder").finish()
+    }
+}
+
+impl<T> Default for RowDecoder<T> {
+    fn default() -> RowDecoder<T> {
+        RowDecoder::new()
+    }
+}
+
+impl<T> RowDecoder<T> {
+    /// Creates a new decoder.
+    pub fn new() -> RowDecoder<T> {
+        RowDecoder { _p: PhantomData }
+    }
+}
+
+impl<T> RowDecoder<T>
+where
+    T: FromRow,
+{
+    /// Decodes all the rows of a stream.
+    ///
+    /// `columns` describes the rows of the stream and is shared by all of them, so no column metadata is allocated
+    /// per row. Messages other than data rows are skipped.
+    pub async fn decode_batch<E>(
+        &self,
+        stream: QueryStream<E>,
+        columns: &[SimpleColumn],
+    ) -> Result<Vec<T>, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let mut values = vec![];
+
+        pin_mut!(stream);
+        while let Some(message) = stream.try_next().await? {
+            if let Message::DataRow(body) = message {
+                let row = Row::new(body)?;
+                values.push(T::from_row(&row, columns)?);
+            }
+        }
+
+        Ok(values)
+    }
+}
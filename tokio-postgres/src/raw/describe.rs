@@ -1,5 +1,16 @@
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::raw::function;
+use crate::raw::identifier;
+use crate::raw::simple_query::SimpleColumn;
+use crate::raw::statement::Statement;
 use crate::{Client, Error};
+use fallible_iterator::FallibleIterator;
+use log::debug;
+use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
+use postgres_protocol::Oid;
+use std::sync::Arc;
 
 /// Enumerate the targets of a describe command
 #[derive(Debug)]
@@ -8,9 +19,39 @@ pub enum DescribeTarget {
     Statement(String),
     /// Describe a portal (rows only)
     Portal(String),
+    /// Describe a call to a schema-qualified function (parameters + rows)
+    ///
+    /// `SELECT schema.name($1, $2, ...)` is prepared as the unnamed statement, with a parameter for each of
+    /// `arg_types`, and then described.
+    Function {
+        /// The schema of the function
+        schema: String,
+        /// The name of the function
+        name: String,
+        /// The types of the function arguments
+        arg_types: Vec<Oid>,
+    },
+}
+
+impl DescribeTarget {
+    /// Creates a target describing a call to the function `name` of `schema`.
+    pub fn with_schema<S, N>(schema: S, name: N, arg_types: Vec<Oid>) -> DescribeTarget
+    where
+        S: Into<String>,
+        N: Into<String>,
+    {
+        DescribeTarget::Function {
+            schema: schema.into(),
+            name: name.into(),
+            arg_types,
+        }
+    }
 }
 
 /// Issue a describe command. Will be fired on next sync.
+///
+/// Describing a `DescribeTarget::Function` also parses the function call, so the responses start with a
+/// `ParseComplete` message.
 pub fn describe<E>(client: &Client, what: DescribeTarget) -> Result<(), E>
 where
     E: std::convert::From<crate::error::Error>,
@@ -24,6 +65,22 @@ where
             DescribeTarget::Portal(name) => {
                 frontend::describe(b'P', &name, buf).map_err(Error::encode)?
             }
+            DescribeTarget::Function {
+                schema,
+                name,
+                arg_types,
+            } => {
+                identifier::validate(&schema, false)?;
+                identifier::validate(&name, false)?;
+                let query = format!(
+                    "SELECT {}.{}{}",
+                    schema,
+                    name,
+                    function::arguments(arg_types.len())
+                );
+                frontend::parse("", &query, arg_types, buf).map_err(Error::encode)?;
+                frontend::describe(b'S', "", buf).map_err(Error::encode)?
+            }
         };
 
         Ok(())
@@ -31,3 +88,158 @@ where
 
     Ok(())
 }
+
+/// The description of a prepared statement, as reported by the server.
+pub struct StatementDescription {
+    params: Vec<Oid>,
+    columns: Arc<[SimpleColumn]>,
+}
+
+impl StatementDescription {
+    /// Returns the types of the statement's parameters.
+    pub fn params(&self) -> &[Oid] {
+        &self.params
+    }
+
+    /// Returns the columns returned by the statement.
+    ///
+    /// The slice is empty if the statement does not return rows.
+    pub fn columns(&self) -> &[SimpleColumn] {
+        &self.columns
+    }
+}
+
+/// Creates a new prepared statement and describes it in a single round trip.
+///
+/// Parse, Describe and Sync messages are sent together, and the returned statement reports the
+/// parameter types inferred by the server. The raw buffer is expected to be empty: commands buffered
+/// earlier are sent along, but their responses are discarded.
+pub async fn prepare_and_describe<E>(
+    client: &Client,
+    query: &str,
+    name: &str,
+    types_oid: &[Oid],
+) -> Result<(Statement, StatementDescription), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    debug!(
+        "preparing query {} with types {:?}: {}",
+        name, types_oid, query
+    );
+
+    let inner = client.inner();
+    let bytes = inner.with_buf(|buf| {
+        frontend::parse(name, query, types_oid.iter().copied(), buf).map_err(Error::encode)?;
+        frontend::describe(b'S', name, buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut params: Option<Vec<Oid>> = None;
+    let mut columns = None;
+    loop {
+        match responses.next().await? {
+            Message::ParameterDescription(body) => {
+                params = Some(body.parameters().collect().map_err(Error::parse)?);
+            }
+            Message::RowDescription(body) => {
+                columns = Some(SimpleColumn::from_row_description_body(body)?);
+            }
+            Message::NoData => columns = Some(Arc::from(vec![])),
+            Message::ReadyForQuery(_) => break,
+            _ => {}
+        }
+    }
+
+    match (params, columns) {
+        (Some(params), Some(columns)) => Ok((
+            Statement::new(inner, name.to_string(), params.clone()),
+            StatementDescription { params, columns },
+        )),
+        _ => Err(Error::unexpected_message().into()),
+    }
+}
+
+/// Returns the types the server infers for the parameters of `query`.
+///
+/// The query is prepared as the unnamed statement, described and closed in a single round trip,
+/// without being bound or executed.
+pub async fn get_parameter_types<E>(client: &Client, query: &str) -> Result<Vec<Oid>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    debug!("describing parameters of query: {}", query);
+
+    let inner = client.inner();
+    let bytes = inner.with_buf(|buf| {
+        frontend::parse("", query, None, buf).map_err(Error::encode)?;
+        frontend::describe(b'S', "", buf).map_err(Error::encode)?;
+        frontend::close(b'S', "", buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut params = None;
+    loop {
+        match responses.next().await? {
+            Message::ParameterDescription(body) => {
+                params = Some(body.parameters().collect().map_err(Error::parse)?);
+            }
+            Message::ReadyForQuery(_) => break,
+            _ => {}
+        }
+    }
+
+    Ok(params.ok_or_else(Error::unexpected_message)?)
+}
+
+/// Describes several prepared statements in a single round trip.
+///
+/// A Describe message is sent for each statement of `names`, followed by a single Sync, and the descriptions are
+/// returned in the same order. The statements must have been prepared already; if one of them does not exist, the
+/// server reports an error for the whole batch. The raw buffer is expected to be empty.
+pub async fn describe_many<E>(
+    client: &Client,
+    names: &[&str],
+) -> Result<Vec<StatementDescription>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    debug!("describing statements {:?}", names);
+
+    let inner = client.inner();
+    let bytes = inner.with_buf(|buf| {
+        for name in names {
+            frontend::describe(b'S', name, buf).map_err(Error::encode)?;
+        }
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut descriptions = Vec::with_capacity(names.len());
+    let mut params: Option<Vec<Oid>> = None;
+    loop {
+        let columns = match responses.next().await? {
+            Message::ParameterDescription(body) => {
+                params = Some(body.parameters().collect().map_err(Error::parse)?);
+                continue;
+            }
+            Message::RowDescription(body) => SimpleColumn::from_row_description_body(body)?,
+            Message::NoData => Arc::from(vec![]),
+            Message::ReadyForQuery(_) => break,
+            _ => return Err(Error::unexpected_message().into()),
+        };
+
+        let params = params.take().ok_or_else(Error::unexpected_message)?;
+        descriptions.push(StatementDescription { params, columns });
+    }
+
+    if descriptions.len() != names.len() {
+        return Err(Error::unexpected_message().into());
+    }
+    Ok(descriptions)
+}
@@ -0,0 +1,49 @@
+use crate::types::{IsNull, ToSql, Type};
+use bytes::BytesMut;
+use std::error::Error;
+
+/// A type which can be encoded as a row by the raw interface.
+///
+/// Each column is encoded on its own, in the binary format of its type. Tuples of up to 8 `ToSql` types are encoded
+/// as columns in order.
+pub trait ToRow {
+    /// Writes the binary representation of the column at index `idx`, of type `type_`, into `buf`.
+    ///
+    /// Returns `IsNull::Yes` without writing anything if the value is NULL.
+    fn to_row_bytes(
+        &self,
+        idx: usize,
+        type_: &Type,
+        buf: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>>;
+}
+
+macro_rules! tuple_to_row {
+    ($($t:ident $idx:tt),+) => {
+        impl<$($t),+> ToRow for ($($t,)+)
+        where
+            $($t: ToSql),+
+        {
+            fn to_row_bytes(
+                &self,
+                idx: usize,
+                type_: &Type,
+                buf: &mut BytesMut,
+            ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+                match idx {
+                    $($idx => self.$idx.to_sql_checked(type_, buf),)+
+                    _ => Err(format!("no column at index {}", idx).into()),
+                }
+            }
+        }
+    };
+}
+
+tuple_to_row!(T0 0);
+tuple_to_row!(T0 0, T1 1);
+tuple_to_row!(T0 0, T1 1, T2 2);
+tuple_to_row!(T0 0, T1 1, T2 2, T3 3);
+tuple_to_row!(T0 0, T1 1, T2 2, T3 3, T4 4);
+tuple_to_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5);
+tuple_to_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6);
+tuple_to_row!(T0 0, T1 1, T2 2, T3 3, T4 4, T5 5, T6 6, T7 7);
@@ -0,0 +1,113 @@
+use crate::Error;
+use std::error;
+use std::fmt;
+
+/// The maximum number of characters of a parameter value displayed by `PgError`.
+const MAX_DISPLAYED_PARAMETER_LEN: usize = 32;
+
+/// An error along with the query which caused it.
+///
+/// `PgError` can be used as the error type of the raw functions, as it can be converted from an `Error`; the context
+/// is then attached with `with_context` where the query is known. The query, the statement name and the parameters
+/// are included in the displayed message, each parameter value being truncated to 32 characters so that large or
+/// sensitive values are not logged in full.
+#[derive(Debug)]
+pub struct PgError {
+    inner: Error,
+    query: Option<String>,
+    parameters: Vec<String>,
+    statement_name: Option<String>,
+}
+
+impl PgError {
+    /// Wraps an error, with the query and the text representation of its parameters.
+    pub fn with_context(error: Error, query: &str, params: &[String]) -> PgError {
+        PgError {
+            inner: error,
+            query: Some(query.to_string()),
+            parameters: params.to_vec(),
+            statement_name: None,
+        }
+    }
+
+    /// Sets the name of the prepared statement which caused the error.
+    #[must_use]
+    pub fn with_statement_name(mut self, name: impl Into<String>) -> PgError {
+        self.statement_name = Some(name.into());
+        self
+    }
+
+    /// Returns the wrapped error.
+    pub fn inner(&self) -> &Error {
+        &self.inner
+    }
+
+    /// Consumes the `PgError`, returning the wrapped error.
+    pub fn into_inner(self) -> Error {
+        self.inner
+    }
+
+    /// Returns the query which caused the error, if known.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// Returns the parameters of the query.
+    pub fn parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Returns the name of the prepared statement which caused the error, if known.
+    pub fn statement_name(&self) -> Option<&str> {
+        self.statement_name.as_deref()
+    }
+
+    /// Returns the query with a `^` marker inserted at the position of the error, as `Error::annotated_query`.
+    pub fn annotated_query(&self) -> Option<String> {
+        self.inner
+            .annotated_query(self.query.as_deref().unwrap_or_default())
+    }
+}
+
+impl fmt::Display for PgError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, fmt)?;
+        if let Some(name) = &self.statement_name {
+            write!(fmt, ", statement `{}`", name)?;
+        }
+        if let Some(query) = &self.query {
+            write!(fmt, ", query `{}`", query)?;
+        }
+        if !self.parameters.is_empty() {
+            fmt.write_str(", parameters [")?;
+            for (i, param) in self.parameters.iter().enumerate() {
+                if i > 0 {
+                    fmt.write_str(", ")?;
+                }
+                match param.char_indices().nth(MAX_DISPLAYED_PARAMETER_LEN) {
+                    Some((end, _)) => write!(fmt, "${} = {:?}...", i + 1, &param[..end])?,
+                    None => write!(fmt, "${} = {:?}", i + 1, param)?,
+                }
+            }
+            fmt.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for PgError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.inner)
+    }
+}
+
+impl From<Error> for PgError {
+    fn from(error: Error) -> PgError {
+        PgError {
+            inner: error,
+            query: None,
+            parameters: vec![],
+            statement_name: None,
+        }
+    }
+}
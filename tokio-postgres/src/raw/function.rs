@@ -0,0 +1,118 @@
+use crate::client::InnerClient;
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::raw::identifier;
+use crate::raw::query::{GenericRow, Row};
+use crate::types::Type;
+use crate::{Client, Error};
+use bytes::{BufMut, Bytes};
+use log::debug;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+
+/// Builds the argument list `($1, $2, ...)` of a routine call.
+pub(crate) fn arguments(count: usize) -> String {
+    let placeholders = (1..=count)
+        .map(|i| format!("${}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("({})", placeholders)
+}
+
+/// Runs a query through the unnamed statement and portal in a single round trip, returning its first row.
+///
+/// Both the parameters and the result columns are in binary format.
+async fn first_row(
+    client: &InnerClient,
+    query: &str,
+    args: &[(&Type, Option<&[u8]>)],
+) -> Result<Option<GenericRow>, Error> {
+    debug!("executing routine call: {}", query);
+
+    let buf = client.with_buf(|buf| {
+        frontend::parse("", query, args.iter().map(|(type_, _)| type_.oid()), buf)
+            .map_err(Error::encode)?;
+        let r = frontend::bind(
+            "",
+            "",
+            Some(1),
+            args.iter(),
+            |(_, value), buf| match value {
+                Some(value) => {
+                    buf.put_slice(value);
+                    Ok(postgres_protocol::IsNull::No)
+                }
+                None => Ok(postgres_protocol::IsNull::Yes),
+            },
+            Some(1),
+            buf,
+        );
+        match r {
+            Ok(()) => {}
+            Err(frontend::BindError::Serialization(e)) => return Err(Error::encode(e)),
+            Err(_) => return Err(Error::unexpected_message()),
+        }
+        frontend::execute("", 0, buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok(buf.split().freeze())
+    })?;
+
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+    let mut row = None;
+    loop {
+        match responses.next().await? {
+            Message::ParseComplete
+            | Message::BindComplete
+            | Message::CommandComplete(_)
+            | Message::EmptyQueryResponse => {}
+            Message::DataRow(body) => {
+                if row.is_none() {
+                    row = Some(GenericRow::from_row(Row::new(body)?));
+                }
+            }
+            Message::ReadyForQuery(_) => return Ok(row),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Calls a function with `SELECT function_name($1, $2, ...)`, returning the raw value of its result.
+///
+/// `function_name` may be schema-qualified. Each argument is given with its type and its value in binary format,
+/// `None` being NULL; the returned value is in binary format too, and must be decoded by the caller. Only the first
+/// row is considered for set-returning functions.
+pub async fn execute_function<E>(
+    client: &Client,
+    function_name: &str,
+    args: &[(&Type, Option<&[u8]>)],
+) -> Result<Option<Bytes>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(function_name, true)?;
+    let query = format!("SELECT {}{}", function_name, arguments(args.len()));
+
+    match first_row(client.inner(), &query, args).await? {
+        Some(row) if !row.is_empty() => Ok(row[0].clone()),
+        _ => Err(Error::no_rows().into()),
+    }
+}
+
+/// Calls a stored procedure with `CALL procedure_name($1, $2, ...)`.
+///
+/// `procedure_name` may be schema-qualified, and the arguments are given as in `execute_function`. If the procedure
+/// has `INOUT` or `OUT` parameters, their raw values are returned in binary format; otherwise `None` is returned.
+pub async fn call_procedure<E>(
+    client: &Client,
+    procedure_name: &str,
+    args: &[(&Type, Option<&[u8]>)],
+) -> Result<Option<Vec<Option<Bytes>>>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(procedure_name, true)?;
+    let query = format!("CALL {}{}", procedure_name, arguments(args.len()));
+
+    let row = first_row(client.inner(), &query, args).await?;
+    Ok(row.map(GenericRow::into_values))
+}
@@ -0,0 +1,52 @@
+use crate::Error;
+use std::io;
+
+/// The maximum length of a channel name, in bytes.
+const MAX_CHANNEL_LEN: usize = 63;
+
+/// Checks that `name` is safe to be interpolated into a query as an identifier.
+///
+/// Only ASCII alphanumeric characters and `_` are accepted. If `qualified` is set, `.` may be used
+/// to separate a schema (or database) name from the object name, but no part may be empty.
+pub(crate) fn validate(name: &str, qualified: bool) -> Result<(), Error> {
+    let valid = if qualified {
+        name.split('.').all(is_valid_part)
+    } else {
+        is_valid_part(name)
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::encode(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid identifier `{}`", name),
+        )))
+    }
+}
+
+/// Checks that `channel` is a valid identifier of at most 63 bytes, usable as a `LISTEN`/`NOTIFY` channel name.
+pub(crate) fn validate_channel(channel: &str) -> Result<(), Error> {
+    validate(channel, false)?;
+    if channel.len() > MAX_CHANNEL_LEN {
+        return Err(Error::encode(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("channel name `{}` is too long", channel),
+        )));
+    }
+    Ok(())
+}
+
+fn is_valid_part(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quotes `value` as a string literal.
+pub(crate) fn literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quotes `name` as an identifier, preserving its case.
+pub(crate) fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
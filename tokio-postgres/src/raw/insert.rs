@@ -0,0 +1,241 @@
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::query::extract_row_affected;
+use crate::raw::builder::invalid;
+use crate::raw::encode::ToRow;
+use crate::raw::identifier;
+use crate::raw::statement::Statement;
+use crate::raw::statement_cache;
+use crate::types::{IsNull, Type};
+use crate::{Client, Error};
+use bytes::{BufMut, Bytes, BytesMut};
+use futures_util::{pin_mut, SinkExt};
+use log::debug;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use std::convert::TryFrom;
+use std::io;
+
+const MAGIC: &[u8] = b"PGCOPY\n\xff\r\n\0";
+
+/// Returns the types of `columns` of `table`, or of all its columns if `columns` is empty.
+///
+/// The types are read from the description of a `SELECT` statement, which is kept in the statement cache.
+async fn column_types(client: &Client, table: &str, columns: &[&str]) -> Result<Vec<Type>, Error> {
+    identifier::validate(table, true)?;
+    for column in columns {
+        identifier::validate(column, false)?;
+    }
+
+    let select_list = if columns.is_empty() {
+        "*".to_string()
+    } else {
+        columns.join(", ")
+    };
+    let query = format!("SELECT {} FROM {}", select_list, table);
+    let statement = statement_cache::get_or_prepare(client.inner(), &query).await?;
+
+    statement
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            column.type_().clone().ok_or_else(|| {
+                Error::to_sql(
+                    format!("unknown type of column `{}`", column.name()).into(),
+                    idx,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Writes a row in the binary copy format.
+fn write_row<T>(row: &T, types: &[Type], buf: &mut BytesMut) -> Result<(), Error>
+where
+    T: ToRow,
+{
+    let count =
+        i16::try_from(types.len()).map_err(|_| Error::to_sql("too many columns".into(), 0))?;
+    buf.put_i16(count);
+
+    for (idx, type_) in types.iter().enumerate() {
+        let base = buf.len();
+        buf.put_i32(0);
+        let len = match row
+            .to_row_bytes(idx, type_, buf)
+            .map_err(|e| Error::to_sql(e, idx))?
+        {
+            IsNull::Yes => -1,
+            IsNull::No => i32::try_from(buf.len() - base - 4)
+                .map_err(|e| Error::encode(io::Error::new(io::ErrorKind::InvalidInput, e)))?,
+        };
+        buf[base..base + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    Ok(())
+}
+
+/// Inserts rows into a table with a binary `COPY ... FROM STDIN` command.
+///
+/// `table` may be schema-qualified (`schema.table`). If `columns` is empty, the rows must contain all the columns of
+/// the table, in order. The column types are looked up once with a cached statement, and each value is encoded with
+/// `ToRow` in the binary format of its column. If a row fails to encode, the copy is aborted and nothing is inserted.
+/// Returns the number of rows inserted.
+pub async fn insert_many<T, I, E>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    rows: I,
+) -> Result<u64, E>
+where
+    T: ToRow,
+    I: IntoIterator<Item = T>,
+    E: std::convert::From<crate::error::Error>,
+{
+    let types = column_types(client, table, columns).await?;
+
+    let columns = if columns.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", columns.join(", "))
+    };
+    let query = format!("COPY {}{} FROM STDIN (FORMAT BINARY)", table, columns);
+
+    let sink = client.copy_in::<_, Bytes>(&query).await?;
+    pin_mut!(sink);
+
+    let mut buf = BytesMut::new();
+    buf.put_slice(MAGIC);
+    buf.put_i32(0); // flags
+    buf.put_i32(0); // header extension
+
+    for row in rows {
+        write_row(&row, &types, &mut buf)?;
+        if buf.len() > 4096 {
+            sink.send(buf.split().freeze()).await?;
+        }
+    }
+
+    buf.put_i16(-1);
+    sink.send(buf.split().freeze()).await?;
+    Ok(sink.finish().await?)
+}
+
+/// Writes a Bind message for the unnamed portal, taking the parameters from a row.
+fn encode_bind_row<T>(
+    statement: &Statement,
+    types: &[Type],
+    row: &T,
+    buf: &mut BytesMut,
+) -> Result<(), Error>
+where
+    T: ToRow,
+{
+    let mut error_idx = 0;
+    let r = frontend::bind(
+        "",
+        statement.name(),
+        Some(1),
+        types.iter().enumerate(),
+        |(idx, type_), buf| match row.to_row_bytes(idx, type_, buf) {
+            Ok(IsNull::No) => Ok(postgres_protocol::IsNull::No),
+            Ok(IsNull::Yes) => Ok(postgres_protocol::IsNull::Yes),
+            Err(e) => {
+                error_idx = idx;
+                Err(e)
+            }
+        },
+        Some(1),
+        buf,
+    );
+    match r {
+        Ok(()) => Ok(()),
+        Err(frontend::BindError::Conversion(e)) => Err(Error::to_sql(e, error_idx)),
+        Err(frontend::BindError::Serialization(e)) => Err(Error::encode(e)),
+    }
+}
+
+/// Inserts rows into a table, updating the rows which conflict with existing ones.
+///
+/// An `INSERT INTO table (columns) VALUES ($1, ...) ON CONFLICT (conflict_columns) DO UPDATE SET column =
+/// EXCLUDED.column, ...` statement is prepared through the statement cache, then bound and executed for every row in
+/// a single pipeline, ending with one Sync. If `update_columns` is empty, conflicting rows are left untouched with
+/// `DO NOTHING`. As the whole pipeline runs in an implicit transaction, an error on any row discards all of them.
+///
+/// `table` may be schema-qualified (`schema.table`). `columns` and `conflict_columns` must not be empty. Returns the
+/// number of rows inserted or updated.
+pub async fn upsert_many<T, I, E>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    conflict_columns: &[&str],
+    update_columns: &[&str],
+    rows: I,
+) -> Result<u64, E>
+where
+    T: ToRow,
+    I: IntoIterator<Item = T>,
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(table, true)?;
+    if columns.is_empty() {
+        return Err(invalid("no column to insert").into());
+    }
+    if conflict_columns.is_empty() {
+        return Err(invalid("no conflict column").into());
+    }
+    for column in columns.iter().chain(conflict_columns).chain(update_columns) {
+        identifier::validate(column, false)?;
+    }
+
+    let values = (1..=columns.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let action = if update_columns.is_empty() {
+        "NOTHING".to_string()
+    } else {
+        let assignments = update_columns
+            .iter()
+            .map(|column| format!("{0} = EXCLUDED.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("UPDATE SET {}", assignments)
+    };
+    let query = format!(
+        "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO {}",
+        table,
+        columns.join(", "),
+        values,
+        conflict_columns.join(", "),
+        action
+    );
+
+    let inner = client.inner();
+    let statement = statement_cache::get_or_prepare(inner, &query).await?;
+
+    debug!(
+        "executing statement {} in a pipeline",
+        statement.statement.name()
+    );
+    let bytes = inner.with_buf(|buf| {
+        for row in rows {
+            encode_bind_row(&statement.statement, &statement.param_types, &row, buf)?;
+            frontend::execute("", 0, buf).map_err(Error::encode)?;
+        }
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut rows_affected = 0;
+    loop {
+        match responses.next().await? {
+            Message::BindComplete => {}
+            Message::CommandComplete(body) => rows_affected += extract_row_affected(&body)?,
+            Message::ReadyForQuery(_) => return Ok(rows_affected),
+            _ => return Err(Error::unexpected_message().into()),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::raw::decode::FromRow;
+use crate::raw::identifier;
+use crate::raw::query::{internal_query, Row};
+use crate::raw::simple_query::SimpleColumn;
+use crate::raw::typed_query;
+use crate::types::{FromSql, Type};
+use crate::{Client, Error};
+use bytes::Bytes;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+async fn exists(client: &Client, query: &str, params: &[Option<&str>]) -> Result<bool, Error> {
+    let rows = internal_query(client.inner(), query, params).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    bool::from_sql_nullable(&Type::BOOL, row.get(0)).map_err(|e| Error::from_sql(e, 0))
+}
+
+/// Checks whether a table exists in the given schema.
+pub async fn table_exists<E>(client: &Client, schema: &str, table: &str) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(exists(
+        client,
+        "SELECT EXISTS(SELECT 1 FROM information_schema.tables \
+         WHERE table_schema = $1 AND table_name = $2)",
+        &[Some(schema), Some(table)],
+    )
+    .await?)
+}
+
+/// Checks whether a column exists in the given table.
+pub async fn column_exists<E>(
+    client: &Client,
+    schema: &str,
+    table: &str,
+    column: &str,
+) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(exists(
+        client,
+        "SELECT EXISTS(SELECT 1 FROM information_schema.columns \
+         WHERE table_schema = $1 AND table_name = $2 AND column_name = $3)",
+        &[Some(schema), Some(table), Some(column)],
+    )
+    .await?)
+}
+
+/// Checks whether an index exists in the given schema.
+pub async fn index_exists<E>(client: &Client, schema: &str, index: &str) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(exists(
+        client,
+        "SELECT EXISTS(SELECT 1 FROM pg_catalog.pg_indexes \
+         WHERE schemaname = $1 AND indexname = $2)",
+        &[Some(schema), Some(index)],
+    )
+    .await?)
+}
+
+/// Checks whether a function (or procedure) with the given name exists in the given schema.
+///
+/// Overloads are not distinguished: any routine with a matching name is enough.
+pub async fn function_exists<E>(client: &Client, schema: &str, function: &str) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(exists(
+        client,
+        "SELECT EXISTS(SELECT 1 FROM information_schema.routines \
+         WHERE routine_schema = $1 AND routine_name = $2)",
+        &[Some(schema), Some(function)],
+    )
+    .await?)
+}
+
+/// Counts the rows of a table.
+///
+/// Both `schema` and `table` must be plain identifiers, as they are interpolated into the query.
+pub async fn row_count<E>(client: &Client, schema: &str, table: &str) -> Result<i64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(schema, false)?;
+    identifier::validate(table, false)?;
+
+    let query = format!("SELECT COUNT(*) FROM {}.{}", schema, table);
+    let rows = internal_query(client.inner(), &query, &[]).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    Ok(i64::from_sql_nullable(&Type::INT8, row.get(0)).map_err(|e| Error::from_sql(e, 0))?)
+}
+
+/// Reads the contents of a server-side file with `pg_read_binary_file`.
+///
+/// Relative paths are resolved against the data directory of the cluster. The path is sent as a query
+/// parameter, and reading files requires superuser rights or the `pg_read_server_files` role.
+pub async fn pg_read_binary_file<E>(client: &Client, path: &str) -> Result<Bytes, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = internal_query(
+        client.inner(),
+        "SELECT pg_read_binary_file($1)",
+        &[Some(path)],
+    )
+    .await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    let contents =
+        <&[u8]>::from_sql_nullable(&Type::BYTEA, row.get(0)).map_err(|e| Error::from_sql(e, 0))?;
+    Ok(Bytes::copy_from_slice(contents))
+}
+
+/// Cancels the current query of the backend with the given process ID.
+///
+/// Returns `false` if no backend was signalled, for example because `pid` is not a PostgreSQL backend. The caller
+/// must be a superuser, a member of the role owning the backend or of `pg_signal_backend`.
+pub async fn pg_cancel_backend<E>(client: &Client, pid: u32) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let pid = pid.to_string();
+    Ok(exists(client, "SELECT pg_cancel_backend($1::int4)", &[Some(&pid)]).await?)
+}
+
+/// Terminates the session of the backend with the given process ID.
+///
+/// Returns `false` if no backend was signalled. The same privileges as for `pg_cancel_backend` are required.
+pub async fn pg_terminate_backend<E>(client: &Client, pid: u32) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let pid = pid.to_string();
+    Ok(exists(
+        client,
+        "SELECT pg_terminate_backend($1::int4)",
+        &[Some(&pid)],
+    )
+    .await?)
+}
+
+/// Returns the process IDs of the backends blocking the backend with the given process ID from acquiring a lock.
+///
+/// The list is empty if the backend is not waiting for a lock, or if `pid` is not a PostgreSQL backend.
+pub async fn pg_blocking_pids<E>(client: &Client, pid: u32) -> Result<Vec<u32>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let pid = pid.to_string();
+    let rows = internal_query(
+        client.inner(),
+        "SELECT pg_blocking_pids($1::int4)",
+        &[Some(&pid)],
+    )
+    .await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    let pids = Vec::<i32>::from_sql_nullable(&Type::INT4_ARRAY, row.get(0))
+        .map_err(|e| Error::from_sql(e, 0))?;
+    Ok(pids.into_iter().map(|pid| pid as u32).collect())
+}
+
+/// A column of a query result, as reported by a describe pass.
+struct DescribedColumn {
+    name: String,
+    type_oid: u32,
+    type_modifier: i32,
+    table_oid: u32,
+    column_id: i16,
+}
+
+/// Describes the columns returned by `query` without executing it.
+async fn describe_columns(client: &Client, query: &str) -> Result<Vec<DescribedColumn>, Error> {
+    let inner = client.inner();
+    let bytes = inner.with_buf(|buf| {
+        frontend::parse("", query, None, buf).map_err(Error::encode)?;
+        frontend::describe(b'S', "", buf).map_err(Error::encode)?;
+        frontend::close(b'S', "", buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut columns = None;
+    loop {
+        match responses.next().await? {
+            Message::RowDescription(body) => {
+                columns = Some(
+                    body.fields()
+                        .map(|f| {
+                            Ok(DescribedColumn {
+                                name: f.name().to_string(),
+                                type_oid: f.type_oid(),
+                                type_modifier: f.type_modifier(),
+                                table_oid: f.table_oid(),
+                                column_id: f.column_id(),
+                            })
+                        })
+                        .collect()
+                        .map_err(Error::parse)?,
+                );
+            }
+            Message::NoData => columns = Some(vec![]),
+            Message::ReadyForQuery(_) => break,
+            _ => {}
+        }
+    }
+
+    columns.ok_or_else(Error::unexpected_message)
+}
+
+/// Reconstructs the `CREATE TABLE` statement of the table `CREATE TABLE tmp AS (query)` would create.
+///
+/// The query is described without being executed, and the types of its columns are formatted by the server with
+/// `format_type`, including their modifiers (e.g. `character varying(10)`). Columns read directly from a table
+/// column are looked up in `information_schema.columns`, and marked `NOT NULL` if the source column is. Note that
+/// `CREATE TABLE AS` itself does not copy such constraints: the statement is meant to document the shape of the
+/// query result.
+pub async fn pg_dump_query<E>(client: &Client, query: &str) -> Result<String, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let columns = describe_columns(client, query).await?;
+    if columns.is_empty() {
+        return Ok("CREATE TABLE tmp ()".to_string());
+    }
+
+    let array = |values: Vec<String>| format!("{{{}}}", values.join(","));
+    let type_oids = array(columns.iter().map(|c| c.type_oid.to_string()).collect());
+    let type_modifiers = array(
+        columns
+            .iter()
+            .map(|c| c.type_modifier.to_string())
+            .collect(),
+    );
+    let table_oids = array(columns.iter().map(|c| c.table_oid.to_string()).collect());
+    let column_ids = array(columns.iter().map(|c| c.column_id.to_string()).collect());
+
+    let rows = internal_query(
+        client.inner(),
+        "SELECT format_type(t.type_oid, t.type_modifier), c.is_nullable = 'NO' \
+         FROM unnest($1::oid[], $2::int4[], $3::oid[], $4::int4[]) \
+         WITH ORDINALITY AS t(type_oid, type_modifier, table_oid, column_id, n) \
+         LEFT JOIN pg_catalog.pg_class r ON r.oid = t.table_oid \
+         LEFT JOIN pg_catalog.pg_namespace s ON s.oid = r.relnamespace \
+         LEFT JOIN information_schema.columns c ON c.table_schema = s.nspname \
+         AND c.table_name = r.relname AND c.ordinal_position = t.column_id \
+         ORDER BY t.n",
+        &[
+            Some(type_oids.as_str()),
+            Some(type_modifiers.as_str()),
+            Some(table_oids.as_str()),
+            Some(column_ids.as_str()),
+        ],
+    )
+    .await?;
+    if rows.len() != columns.len() {
+        return Err(Error::row_count().into());
+    }
+
+    let mut definitions = vec![];
+    for (column, row) in columns.iter().zip(&rows) {
+        let type_ = <&str>::from_sql_nullable(&Type::TEXT, row.get(0))
+            .map_err(|e| Error::from_sql(e, 0))?;
+        let not_null = Option::<bool>::from_sql_nullable(&Type::BOOL, row.get(1))
+            .map_err(|e| Error::from_sql(e, 1))?
+            .unwrap_or(false);
+
+        let mut definition = format!("    {} {}", identifier::quote(&column.name), type_);
+        if not_null {
+            definition.push_str(" NOT NULL");
+        }
+        definitions.push(definition);
+    }
+
+    Ok(format!(
+        "CREATE TABLE tmp (\n{}\n)",
+        definitions.join(",\n")
+    ))
+}
+
+/// A server process, as reported by the `pg_stat_activity` view.
+///
+/// Apart from the process ID, the fields are `None` when they do not apply to the process (e.g. background workers
+/// have no user) or when the current user is not allowed to see them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgStatActivity {
+    pid: i32,
+    usename: Option<String>,
+    application_name: Option<String>,
+    client_addr: Option<IpAddr>,
+    state: Option<String>,
+    query: Option<String>,
+    wait_event_type: Option<String>,
+    wait_event: Option<String>,
+    query_start: Option<SystemTime>,
+    state_change: Option<SystemTime>,
+}
+
+impl PgStatActivity {
+    /// Returns the process ID of the backend.
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    /// Returns the name of the user logged into the backend.
+    pub fn usename(&self) -> Option<&str> {
+        self.usename.as_deref()
+    }
+
+    /// Returns the name of the application connected to the backend.
+    pub fn application_name(&self) -> Option<&str> {
+        self.application_name.as_deref()
+    }
+
+    /// Returns the address of the client, or `None` for Unix socket connections and internal processes.
+    pub fn client_addr(&self) -> Option<IpAddr> {
+        self.client_addr
+    }
+
+    /// Returns the state of the backend, e.g. `active` or `idle in transaction`.
+    pub fn state(&self) -> Option<&str> {
+        self.state.as_deref()
+    }
+
+    /// Returns the text of the most recent query of the backend.
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    /// Returns the type of event the backend is waiting for, if any.
+    pub fn wait_event_type(&self) -> Option<&str> {
+        self.wait_event_type.as_deref()
+    }
+
+    /// Returns the name of the event the backend is waiting for, if any.
+    pub fn wait_event(&self) -> Option<&str> {
+        self.wait_event.as_deref()
+    }
+
+    /// Returns the time the current query, or the most recent one if the backend is idle, was started.
+    pub fn query_start(&self) -> Option<SystemTime> {
+        self.query_start
+    }
+
+    /// Returns the time the state of the backend last changed.
+    pub fn state_change(&self) -> Option<SystemTime> {
+        self.state_change
+    }
+}
+
+impl FromRow for PgStatActivity {
+    fn from_row(row: &Row, columns: &[SimpleColumn]) -> Result<Self, Error> {
+        Ok(PgStatActivity {
+            pid: row.try_get(columns, 0)?,
+            usename: row.try_get(columns, 1)?,
+            application_name: row.try_get(columns, 2)?,
+            client_addr: row.try_get(columns, 3)?,
+            state: row.try_get(columns, 4)?,
+            query: row.try_get(columns, 5)?,
+            wait_event_type: row.try_get(columns, 6)?,
+            wait_event: row.try_get(columns, 7)?,
+            query_start: row.try_get(columns, 8)?,
+            state_change: row.try_get(columns, 9)?,
+        })
+    }
+}
+
+/// Lists the server processes, as reported by the `pg_stat_activity` view.
+///
+/// This includes the backends of all the client connections, the current one included, as well as the background
+/// processes of the server.
+pub async fn pg_stat_activity<E>(client: &Client) -> Result<Vec<PgStatActivity>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let mut execution = typed_query::start(
+        client.inner(),
+        "SELECT pid, usename, application_name, client_addr, state, query, wait_event_type, wait_event, \
+         query_start, state_change FROM pg_catalog.pg_stat_activity",
+        std::iter::empty(),
+    )
+    .await?;
+    Ok(execution.collect().await?)
+}
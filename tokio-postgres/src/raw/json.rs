@@ -0,0 +1,27 @@
+use crate::raw::query::internal_query;
+use crate::types::{FromSql, Type};
+use crate::{Client, Error};
+use serde_json_1::Value;
+
+/// Executes a query and returns each of its rows as a JSON object, serialized server-side by `row_to_json`.
+///
+/// The query is wrapped as `SELECT row_to_json(t) FROM (query) t`, so it must be a single `SELECT` (or `VALUES`)
+/// statement without parameters. Each object maps the column names to their values.
+///
+/// Requires the `with-serde_json-1` Cargo feature.
+pub async fn query_as_json<E>(client: &Client, query: &str) -> Result<Vec<Value>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let query = format!("SELECT row_to_json(t) FROM ({}) t", query);
+    let rows = internal_query(client.inner(), &query, &[]).await?;
+
+    let values = rows
+        .iter()
+        .map(|row| {
+            Value::from_sql_nullable(&Type::JSON, row.get(0)).map_err(|e| Error::from_sql(e, 0))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(values)
+}
@@ -0,0 +1,203 @@
+use crate::client::InnerClient;
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::raw::query::{internal_query, Row};
+use crate::types::{FromSql, Oid, Type};
+use crate::{Client, Error};
+use bytes::{Bytes, BytesMut};
+use postgres_protocol::message::frontend;
+use std::fmt::Write;
+use std::io::SeekFrom;
+use std::sync::{Arc, Weak};
+
+const INV_WRITE: i32 = 0x0002_0000;
+const INV_READ: i32 = 0x0004_0000;
+
+/// The access mode of a large object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LargeObjectMode {
+    /// The large object can only be read.
+    Read,
+    /// The large object can only be written.
+    Write,
+    /// The large object can be both read and written.
+    ReadWrite,
+}
+
+impl LargeObjectMode {
+    fn flags(self) -> i32 {
+        match self {
+            LargeObjectMode::Read => INV_READ,
+            LargeObjectMode::Write => INV_WRITE,
+            LargeObjectMode::ReadWrite => INV_READ | INV_WRITE,
+        }
+    }
+}
+
+/// Creates a new, empty large object, returning its server-assigned OID.
+pub async fn lo_create<E>(client: &Client) -> Result<Oid, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = internal_query(client.inner(), "SELECT lo_create(0)", &[]).await?;
+    Ok(scalar(&rows, &Type::OID)?)
+}
+
+/// Deletes the large object identified by `oid`.
+pub async fn lo_unlink<E>(client: &Client, oid: Oid) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let oid = oid.to_string();
+    internal_query(client.inner(), "SELECT lo_unlink($1::oid)", &[Some(&oid)]).await?;
+    Ok(())
+}
+
+/// An open large object.
+///
+/// Large object descriptors only exist for the duration of the transaction in which they were
+/// opened, so a large object must be opened, used and closed within a transaction block.
+pub struct LargeObject {
+    client: Weak<InnerClient>,
+    oid: Oid,
+    fd: i32,
+    unlink_on_drop: bool,
+}
+
+impl Drop for LargeObject {
+    fn drop(&mut self) {
+        if !self.unlink_on_drop {
+            return;
+        }
+
+        if let Some(client) = self.client.upgrade() {
+            // The query is sent on its own, so that the commands pending in the raw buffer are left untouched.
+            let mut buf = BytesMut::new();
+            if frontend::query(&format!("SELECT lo_unlink({})", self.oid), &mut buf).is_ok() {
+                let _ = client.send(RequestMessages::Single(FrontendMessage::Raw(buf.freeze())));
+            }
+        }
+    }
+}
+
+impl LargeObject {
+    /// Opens the large object identified by `oid`.
+    pub async fn open<E>(client: &Client, oid: Oid, mode: LargeObjectMode) -> Result<LargeObject, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let oid_param = oid.to_string();
+        let flags = mode.flags().to_string();
+        let rows = internal_query(
+            client.inner(),
+            "SELECT lo_open($1::oid, $2::int4)",
+            &[Some(&oid_param), Some(&flags)],
+        )
+        .await?;
+
+        Ok(LargeObject {
+            client: Arc::downgrade(client.inner()),
+            oid,
+            fd: scalar(&rows, &Type::INT4)?,
+            unlink_on_drop: false,
+        })
+    }
+
+    /// Sets whether the large object is deleted when this value is dropped.
+    ///
+    /// The deletion is sent to the server as a separate query, without waiting for its outcome.
+    /// Commands pending in the raw buffer are not affected. Defaults to `false`.
+    pub fn set_unlink_on_drop(&mut self, unlink_on_drop: bool) {
+        self.unlink_on_drop = unlink_on_drop;
+    }
+
+    /// Returns the OID of the large object.
+    pub fn oid(&self) -> Oid {
+        self.oid
+    }
+
+    /// Reads up to `len` bytes from the current position.
+    ///
+    /// An empty buffer is returned at the end of the large object.
+    pub async fn read<E>(&self, len: usize) -> Result<Bytes, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let len = len.min(i32::MAX as usize).to_string();
+        let rows = self.call("SELECT loread($1::int4, $2::int4)", &len).await?;
+        let row = rows.first().ok_or_else(Error::row_count)?;
+
+        Ok(Bytes::copy_from_slice(row.get(0).unwrap_or_default()))
+    }
+
+    /// Writes `data` at the current position, returning the number of bytes written.
+    pub async fn write<E>(&self, data: &[u8]) -> Result<usize, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let mut hex = String::with_capacity(2 + data.len() * 2);
+        hex.push_str("\\x");
+        for byte in data {
+            write!(hex, "{:02x}", byte).unwrap();
+        }
+
+        let rows = self
+            .call("SELECT lowrite($1::int4, $2::bytea)", &hex)
+            .await?;
+        Ok(scalar::<i32>(&rows, &Type::INT4)? as usize)
+    }
+
+    /// Moves the current position, returning the new position from the start of the large object.
+    pub async fn seek<E>(&self, pos: SeekFrom) -> Result<u64, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            SeekFrom::Current(offset) => (offset, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+
+        let client = self.client()?;
+        let fd = self.fd.to_string();
+        let offset = offset.to_string();
+        let whence = whence.to_string();
+        let rows = internal_query(
+            &client,
+            "SELECT lo_lseek64($1::int4, $2::int8, $3::int4)",
+            &[Some(&fd), Some(&offset), Some(&whence)],
+        )
+        .await?;
+
+        Ok(scalar::<i64>(&rows, &Type::INT8)? as u64)
+    }
+
+    /// Closes the large object descriptor.
+    pub async fn close<E>(self) -> Result<(), E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let client = self.client()?;
+        let fd = self.fd.to_string();
+        internal_query(&client, "SELECT lo_close($1::int4)", &[Some(&fd)]).await?;
+        Ok(())
+    }
+
+    fn client(&self) -> Result<Arc<InnerClient>, Error> {
+        self.client.upgrade().ok_or_else(Error::closed)
+    }
+
+    async fn call(&self, query: &str, param: &str) -> Result<Vec<Row>, Error> {
+        let client = self.client()?;
+        let fd = self.fd.to_string();
+        internal_query(&client, query, &[Some(&fd), Some(param)]).await
+    }
+}
+
+fn scalar<'a, T>(rows: &'a [Row], type_: &Type) -> Result<T, Error>
+where
+    T: FromSql<'a>,
+{
+    let row = rows.first().ok_or_else(Error::row_count)?;
+    T::from_sql_nullable(type_, row.get(0)).map_err(|e| Error::from_sql(e, 0))
+}
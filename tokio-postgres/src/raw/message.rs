@@ -0,0 +1,186 @@
+use crate::raw::query::Row;
+use crate::raw::simple_query::SimpleColumn;
+use crate::Error;
+use bytes::Bytes;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::Oid;
+use std::fmt;
+use std::sync::Arc;
+
+/// The tag of a completed command, e.g. `INSERT 0 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandCompleteTag(String);
+
+impl CommandCompleteTag {
+    /// Returns the whole tag.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the name of the command, without the number of rows, e.g. `INSERT`.
+    pub fn command(&self) -> &str {
+        self.0
+            .trim_end_matches(|c: char| c.is_ascii_digit() || c == ' ')
+    }
+
+    /// Returns the number of rows processed by the command, if it reports it.
+    pub fn rows(&self) -> Option<u64> {
+        self.0.rsplit(' ').next()?.parse().ok()
+    }
+}
+
+impl fmt::Display for CommandCompleteTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A message sent by the server, decoded for convenient access.
+///
+/// Unlike `Message`, the bodies of the messages commonly handled by the raw interface are already decoded, and can
+/// be accessed without a `match`. Messages which could not be decoded are reported as `Malformed`.
+#[non_exhaustive]
+pub enum ServerMessage {
+    /// The response to a Parse message.
+    ParseComplete,
+    /// The response to a Bind message.
+    BindComplete,
+    /// The response to a Close message.
+    CloseComplete,
+    /// The types of the parameters of a described statement.
+    ParameterDescription(Vec<Oid>),
+    /// The columns of the rows returned by a described statement or portal, or by a simple query.
+    RowDescription(Arc<[SimpleColumn]>),
+    /// The response to the description of a statement or portal which does not return rows.
+    NoData,
+    /// A row returned by a query.
+    DataRow(Row),
+    /// The completion of a command.
+    CommandComplete(CommandCompleteTag),
+    /// The response to an empty query.
+    EmptyQueryResponse,
+    /// The end of an execution whose row limit has been reached.
+    PortalSuspended,
+    /// The end of the responses to a batch, with the transaction status (`I`, `T` or `E`).
+    ReadyForQuery(u8),
+    /// An error reported by the server.
+    ErrorResponse(Error),
+    /// A chunk of the data of a `COPY` command.
+    CopyData(Bytes),
+    /// The end of the data of a `COPY` command.
+    CopyDone,
+    /// Any other message.
+    Other(Message),
+    /// A message which could not be decoded.
+    Malformed(Error),
+}
+
+impl ServerMessage {
+    /// Returns the types of the parameters, if this is a `ParameterDescription` message.
+    pub fn parameter_description(&self) -> Option<&[Oid]> {
+        match self {
+            ServerMessage::ParameterDescription(params) => Some(params),
+            _ => None,
+        }
+    }
+
+    /// Returns the columns, if this is a `RowDescription` message.
+    pub fn row_description(&self) -> Option<Arc<[SimpleColumn]>> {
+        match self {
+            ServerMessage::RowDescription(columns) => Some(columns.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the row, if this is a `DataRow` message.
+    pub fn data_row(&self) -> Option<&Row> {
+        match self {
+            ServerMessage::DataRow(row) => Some(row),
+            _ => None,
+        }
+    }
+
+    /// Returns the command tag, if this is a `CommandComplete` message.
+    pub fn command_complete(&self) -> Option<&CommandCompleteTag> {
+        match self {
+            ServerMessage::CommandComplete(tag) => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// Returns the transaction status, if this is a `ReadyForQuery` message.
+    pub fn ready_for_query(&self) -> Option<u8> {
+        match self {
+            ServerMessage::ReadyForQuery(status) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Returns the error, if this is an `ErrorResponse` or a malformed message.
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            ServerMessage::ErrorResponse(e) | ServerMessage::Malformed(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the data, if this is a `CopyData` message.
+    pub fn copy_data(&self) -> Option<&Bytes> {
+        match self {
+            ServerMessage::CopyData(data) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl From<Message> for ServerMessage {
+    fn from(message: Message) -> ServerMessage {
+        let decoded = match message {
+            Message::ParseComplete => Ok(ServerMessage::ParseComplete),
+            Message::BindComplete => Ok(ServerMessage::BindComplete),
+            Message::CloseComplete => Ok(ServerMessage::CloseComplete),
+            Message::ParameterDescription(body) => body
+                .parameters()
+                .collect()
+                .map(ServerMessage::ParameterDescription)
+                .map_err(Error::parse),
+            Message::RowDescription(body) => {
+                SimpleColumn::from_row_description_body(body).map(ServerMessage::RowDescription)
+            }
+            Message::NoData => Ok(ServerMessage::NoData),
+            Message::DataRow(body) => Row::new(body).map(ServerMessage::DataRow),
+            Message::CommandComplete(body) => body
+                .tag()
+                .map(|tag| ServerMessage::CommandComplete(CommandCompleteTag(tag.to_string())))
+                .map_err(Error::parse),
+            Message::EmptyQueryResponse => Ok(ServerMessage::EmptyQueryResponse),
+            Message::PortalSuspended => Ok(ServerMessage::PortalSuspended),
+            Message::ReadyForQuery(body) => Ok(ServerMessage::ReadyForQuery(body.status())),
+            Message::ErrorResponse(body) => Ok(ServerMessage::ErrorResponse(Error::db(body))),
+            Message::CopyData(body) => Ok(ServerMessage::CopyData(body.into_bytes())),
+            Message::CopyDone => Ok(ServerMessage::CopyDone),
+            message => Ok(ServerMessage::Other(message)),
+        };
+
+        decoded.unwrap_or_else(ServerMessage::Malformed)
+    }
+}
+
+/// Splits a buffer of wire format messages into their tags and bodies.
+///
+/// Both frontend and backend messages start with a tag byte, followed by the length of the message (including
+/// itself) as a big endian `i32`. A truncated message ends the iteration with the available part of its body.
+pub(crate) fn split_messages(mut buf: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < 5 {
+            return None;
+        }
+        let tag = buf[0];
+        let len = i32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
+        let end = (len + 1).min(buf.len()).max(5);
+        let body = &buf[5..end];
+        buf = &buf[end..];
+        Some((tag, body))
+    })
+}
@@ -0,0 +1,66 @@
+use crate::raw::query::internal_query;
+use crate::{simple_query, Client, Error};
+
+/// Applies a migration unless it has already been applied.
+///
+/// Applied migrations are recorded in a `_migrations` table, which is created if it does not exist
+/// yet. The `sql` batch is executed and the migration recorded within a single transaction, so a
+/// failing migration leaves no trace and concurrent callers apply it only once. For this reason,
+/// this function must not be called while a transaction is already open on the connection.
+///
+/// Returns `true` if the migration has been applied, or `false` if it was already present.
+pub async fn execute_if_not_exists<E>(
+    client: &Client,
+    migration_id: &str,
+    sql: &str,
+) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(apply(client, migration_id, sql).await?)
+}
+
+async fn apply(client: &Client, migration_id: &str, sql: &str) -> Result<bool, Error> {
+    let inner = client.inner();
+    simple_query::batch_execute(
+        inner,
+        "CREATE TABLE IF NOT EXISTS _migrations (
+            id TEXT PRIMARY KEY,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        )",
+    )
+    .await?;
+
+    simple_query::batch_execute(inner, "BEGIN").await?;
+    let result = async {
+        let inserted = internal_query(
+            inner,
+            "INSERT INTO _migrations (id, applied_at) VALUES ($1, now()) \
+             ON CONFLICT (id) DO NOTHING RETURNING id",
+            &[Some(migration_id)],
+        )
+        .await?;
+        if inserted.is_empty() {
+            return Ok(false);
+        }
+
+        simple_query::batch_execute(inner, sql).await?;
+        Ok(true)
+    }
+    .await;
+
+    match result {
+        Ok(true) => {
+            simple_query::batch_execute(inner, "COMMIT").await?;
+            Ok(true)
+        }
+        Ok(false) => {
+            simple_query::batch_execute(inner, "ROLLBACK").await?;
+            Ok(false)
+        }
+        Err(e) => {
+            let _ = simple_query::batch_execute(inner, "ROLLBACK").await;
+            Err(e)
+        }
+    }
+}
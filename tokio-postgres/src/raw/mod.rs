@@ -1,22 +1,191 @@
 //! Raw (low-level) interface.
 
+#[cfg(feature = "raw")]
+pub(crate) mod advisory_lock;
+#[cfg(feature = "raw")]
+pub(crate) mod array;
+#[cfg(feature = "arrow")]
+pub(crate) mod arrow;
+#[cfg(feature = "raw")]
+pub(crate) mod builder;
+#[cfg(feature = "raw")]
+pub(crate) mod composite;
+#[cfg(feature = "raw")]
+pub(crate) mod copy;
+#[cfg(feature = "raw")]
+pub(crate) mod cursor;
+#[cfg(feature = "raw")]
+pub(crate) mod decode;
 #[cfg(feature = "raw")]
 pub(crate) mod describe;
 #[cfg(feature = "raw")]
+pub(crate) mod encode;
+#[cfg(feature = "raw")]
+pub(crate) mod error;
+#[cfg(feature = "raw")]
+pub(crate) mod function;
+#[cfg(feature = "raw")]
+pub(crate) mod identifier;
+#[cfg(feature = "raw")]
+pub(crate) mod insert;
+#[cfg(feature = "raw")]
+pub(crate) mod introspection;
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+pub(crate) mod json;
+#[cfg(feature = "raw")]
+pub(crate) mod large_object;
+#[cfg(feature = "raw")]
+pub(crate) mod message;
+#[cfg(feature = "raw")]
+pub(crate) mod migration;
+#[cfg(feature = "raw")]
+pub(crate) mod notify;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "raw")]
+pub(crate) mod pipeline;
+#[cfg(feature = "raw")]
 pub(crate) mod portal;
 #[cfg(feature = "raw")]
 pub(crate) mod query;
+#[cfg(feature = "raw")]
+pub(crate) mod range;
+#[cfg(feature = "raw")]
+pub(crate) mod replay;
+#[cfg(feature = "raw")]
+pub(crate) mod replication;
+#[cfg(feature = "raw")]
+pub(crate) mod scram;
+#[cfg(feature = "raw")]
+pub(crate) mod sequence;
+#[cfg(feature = "raw")]
+pub(crate) mod session;
 pub(crate) mod simple_query;
 #[cfg(feature = "raw")]
 pub(crate) mod statement;
+#[cfg(feature = "raw")]
+pub(crate) mod statement_cache;
+#[cfg(feature = "raw")]
+pub(crate) mod statement_pool;
+#[cfg(feature = "raw")]
+pub(crate) mod statistics;
+#[cfg(feature = "raw")]
+pub(crate) mod table;
+#[cfg(feature = "raw")]
+pub(crate) mod trace;
+#[cfg(feature = "raw")]
+pub(crate) mod transaction;
+#[cfg(feature = "raw")]
+pub(crate) mod typed_query;
+#[cfg(feature = "raw")]
+pub(crate) mod utility;
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+pub(crate) mod watch;
 
 #[cfg(feature = "raw")]
-pub use describe::{describe, DescribeTarget};
+pub use advisory_lock::{
+    advisory_xact_lock, advisory_xact_lock_pair, try_advisory_xact_lock,
+    try_advisory_xact_lock_pair,
+};
+#[cfg(feature = "raw")]
+pub use array::{decode_array, encode_array};
+#[cfg(feature = "arrow")]
+pub use arrow::copy_out_arrow;
+#[cfg(feature = "raw")]
+pub use builder::{BoxedParam, InsertBuilder, Order, QueryBuilder, UpdateBuilder};
+#[cfg(feature = "raw")]
+pub use composite::encode_composite;
+#[cfg(feature = "raw")]
+pub use copy::{copy_in_csv, copy_in_rows, copy_in_with_transform, copy_out_csv, CsvOptions};
+#[cfg(feature = "raw")]
+pub use cursor::{declare_cursor, Cursor};
+#[cfg(feature = "raw")]
+pub use decode::{FromRow, RowDecoder};
+#[cfg(feature = "raw")]
+pub use describe::{
+    describe, describe_many, get_parameter_types, prepare_and_describe, DescribeTarget,
+    StatementDescription,
+};
+#[cfg(feature = "raw")]
+pub use encode::ToRow;
+#[cfg(feature = "raw")]
+pub use error::PgError;
+#[cfg(feature = "raw")]
+pub use function::{call_procedure, execute_function};
+#[cfg(feature = "raw")]
+pub use insert::{insert_many, upsert_many};
+#[cfg(feature = "raw")]
+pub use introspection::{
+    column_exists, function_exists, index_exists, pg_blocking_pids, pg_cancel_backend,
+    pg_dump_query, pg_read_binary_file, pg_stat_activity, pg_terminate_backend, row_count,
+    table_exists, PgStatActivity,
+};
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+pub use json::query_as_json;
+#[cfg(feature = "raw")]
+pub use large_object::{lo_create, lo_unlink, LargeObject, LargeObjectMode};
+#[cfg(feature = "raw")]
+pub use message::{CommandCompleteTag, ServerMessage};
+#[cfg(feature = "raw")]
+pub use migration::execute_if_not_exists;
+#[cfg(feature = "raw")]
+pub use notify::{
+    invalidate_on_schema_change, pg_notify, subscribe_schema_changes, SchemaChangeEvent,
+    SchemaChangeInvalidator,
+};
+#[cfg(feature = "raw")]
+pub use pipeline::PipelineBuilder;
+#[cfg(feature = "raw")]
+pub use portal::{AtomicPortalNameGenerator, Portal, PortalNameGenerator, TypedPortal};
+#[cfg(all(feature = "raw", feature = "runtime"))]
+pub use query::sync_with_timeout;
+#[cfg(feature = "raw")]
+pub use query::{
+    bind, bind_with_generator, execute, flush_raw_buf, keepalive, prepare, prepare_with_guard,
+    raw_buf_is_empty, sync, GenericRow, LimitedQueryStream, PeekableQueryStream, QueryStream, Row,
+};
+#[cfg(feature = "raw")]
+pub use range::{decode_range, encode_range, PgRange, RangeFlags};
+#[cfg(feature = "raw")]
+pub use replay::{decode_recording, encode_recording, RecordedExchange, ReplayClient};
+#[cfg(feature = "raw")]
+pub use replication::{
+    base_backup, identify_system, timeline_history, BaseBackupOptions, BaseBackupStream,
+    SystemIdentification, TimelineHistory,
+};
+#[cfg(feature = "raw")]
+pub use scram::ScramClient;
+#[cfg(feature = "raw")]
+pub use sequence::{sequence_currval, sequence_lastval, sequence_nextval, sequence_setval};
+#[cfg(all(feature = "raw", feature = "with-chrono-0_4"))]
+pub use session::current_timestamp;
+#[cfg(feature = "raw")]
+pub use session::{
+    current_schema, current_schemas, set_transaction_isolation, transaction_isolation,
+    xact_is_read_only,
+};
+#[cfg(feature = "raw")]
+pub use simple_query::{
+    simple_query, OwnedSimpleQueryRow, SimpleColumn, SimpleQueryRow, SimpleQueryStream,
+};
+#[cfg(feature = "raw")]
+pub use statement::{Statement, WithStatement};
+#[cfg(feature = "raw")]
+pub use statement_pool::SharedStatementPool;
+#[cfg(feature = "raw")]
+pub use statistics::Statistics;
+#[cfg(feature = "raw")]
+pub use table::{delete_where, select_for_update, select_where};
 #[cfg(feature = "raw")]
-pub use portal::Portal;
+pub use trace::{trace_session, TraceClient};
 #[cfg(feature = "raw")]
-pub use query::{bind, execute, prepare, sync, QueryStream, Row};
+pub use transaction::{begin, savepoint, NestedTransaction, RawTransaction};
 #[cfg(feature = "raw")]
-pub use simple_query::{simple_query, SimpleColumn, SimpleQueryRow, SimpleQueryStream};
+pub use typed_query::{batch_select, query_one, query_opt, query_page, stream_query, Page};
 #[cfg(feature = "raw")]
-pub use statement::Statement;
+pub use utility::{
+    import_foreign_schema, lock_table, refresh_materialized_view, reset_role, set_role, truncate,
+    ImportOptions, LockMode,
+};
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+pub use watch::{watch_table, Operation, TableChange, TableWatch};
@@ -0,0 +1,209 @@
+use crate::client::InnerClient;
+use crate::raw::identifier;
+use crate::raw::query::internal_query;
+use crate::simple_query::batch_execute;
+use crate::{AsyncMessage, Client};
+use futures_util::{future, ready, Stream, StreamExt};
+use pin_project_lite::pin_project;
+use postgres_protocol::Oid;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+
+/// The channel schema change notifications are sent on.
+const SCHEMA_CHANGES_CHANNEL: &str = "schema_changes";
+
+const SCHEMA_CHANGES_SETUP: &str = "\
+DO $setup$
+BEGIN
+    IF (SELECT rolsuper FROM pg_roles WHERE rolname = current_user) THEN
+        CREATE OR REPLACE FUNCTION public.schema_changes_notify() RETURNS event_trigger
+        LANGUAGE plpgsql AS $fn$
+        DECLARE
+            r record;
+        BEGIN
+            FOR r IN SELECT * FROM pg_event_trigger_ddl_commands() LOOP
+                PERFORM pg_notify('schema_changes', concat_ws(E'\\n',
+                    r.command_tag, r.object_type, coalesce(r.schema_name, ''), r.object_identity,
+                    r.objid));
+            END LOOP;
+        END
+        $fn$;
+        IF NOT EXISTS (SELECT 1 FROM pg_event_trigger WHERE evtname = 'schema_changes_notify') THEN
+            CREATE EVENT TRIGGER schema_changes_notify ON ddl_command_end
+                EXECUTE FUNCTION public.schema_changes_notify();
+        END IF;
+    END IF;
+END
+$setup$;
+LISTEN schema_changes";
+
+/// Sends a notification on `channel` with the given payload.
+///
+/// The notification is sent through `pg_notify`, so the payload does not need to be quoted. The
+/// channel name must be a plain identifier of at most 63 bytes. Within a transaction, the
+/// notification is only delivered when the transaction commits.
+pub async fn pg_notify<E>(client: &Client, channel: &str, payload: &str) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate_channel(channel)?;
+
+    internal_query(
+        client.inner(),
+        "SELECT pg_notify($1, $2)",
+        &[Some(channel), Some(payload)],
+    )
+    .await?;
+    Ok(())
+}
+
+/// A DDL command reported by the schema changes event trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaChangeEvent {
+    command_tag: String,
+    object_type: String,
+    schema_name: Option<String>,
+    object_identity: String,
+    object_id: Option<Oid>,
+}
+
+impl SchemaChangeEvent {
+    fn parse(payload: &str) -> Option<SchemaChangeEvent> {
+        let mut parts = payload.splitn(5, '\n');
+        let command_tag = parts.next()?.to_string();
+        let object_type = parts.next()?.to_string();
+        let schema_name = Some(parts.next()?)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+        let object_identity = parts.next()?.to_string();
+        // Triggers created by older versions do not report the OID.
+        let object_id = parts.next().and_then(|s| s.parse().ok());
+
+        Some(SchemaChangeEvent {
+            command_tag,
+            object_type,
+            schema_name,
+            object_identity,
+            object_id,
+        })
+    }
+
+    /// Returns the command tag, e.g. `CREATE TABLE`.
+    pub fn command_tag(&self) -> &str {
+        &self.command_tag
+    }
+
+    /// Returns the type of the affected object, e.g. `table`.
+    pub fn object_type(&self) -> &str {
+        &self.object_type
+    }
+
+    /// Returns the schema of the affected object, if it belongs to one.
+    pub fn schema_name(&self) -> Option<&str> {
+        self.schema_name.as_deref()
+    }
+
+    /// Returns the qualified identity of the affected object, e.g. `public.foo`.
+    pub fn object_identity(&self) -> &str {
+        &self.object_identity
+    }
+
+    /// Returns the OID of the affected object, or of the table for table columns and constraints.
+    ///
+    /// This is `None` if the event trigger was created by an older version of this crate.
+    pub fn object_id(&self) -> Option<Oid> {
+        self.object_id
+    }
+}
+
+/// Subscribes to the schema changes of the database.
+///
+/// Schema changes are reported by the `schema_changes_notify` event trigger, which sends a notification on the
+/// `schema_changes` channel at the end of every DDL command. The trigger and its function are created if they do not
+/// exist yet and the current user is a superuser; the client then starts listening on the channel.
+///
+/// Notifications are delivered through the `Connection` rather than the `Client`, so the asynchronous messages polled
+/// from the connection must be passed as `messages`. The returned stream yields the schema changes among them,
+/// ignoring any other message.
+pub async fn subscribe_schema_changes<S, E>(
+    client: &Client,
+    messages: S,
+) -> Result<impl Stream<Item = SchemaChangeEvent>, E>
+where
+    S: Stream<Item = AsyncMessage>,
+    E: std::convert::From<crate::error::Error>,
+{
+    batch_execute(client.inner(), SCHEMA_CHANGES_SETUP).await?;
+
+    Ok(messages.filter_map(|message| {
+        future::ready(match message {
+            AsyncMessage::Notification(n) if n.channel() == SCHEMA_CHANGES_CHANNEL => {
+                SchemaChangeEvent::parse(n.payload())
+            }
+            _ => None,
+        })
+    }))
+}
+
+pin_project! {
+    /// A future invalidating cached statements on schema changes, returned by `invalidate_on_schema_change`.
+    pub struct SchemaChangeInvalidator<S> {
+        #[pin]
+        messages: S,
+        client: Weak<InnerClient>,
+    }
+}
+
+impl<S> Future for SchemaChangeInvalidator<S>
+where
+    S: Stream<Item = AsyncMessage>,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut this = self.project();
+        loop {
+            let event = match ready!(this.messages.as_mut().poll_next(cx)) {
+                Some(AsyncMessage::Notification(n)) if n.channel() == SCHEMA_CHANGES_CHANNEL => {
+                    SchemaChangeEvent::parse(n.payload())
+                }
+                Some(_) => None,
+                None => return Poll::Ready(()),
+            };
+
+            if let Some(event) = event {
+                match this.client.upgrade() {
+                    Some(client) => {
+                        client.raw_statements(|cache| cache.invalidate(event.object_id()))
+                    }
+                    None => return Poll::Ready(()),
+                }
+            }
+        }
+    }
+}
+
+/// Invalidates the cached statements of the client which may be affected by schema changes.
+///
+/// This subscribes to the schema changes like `subscribe_schema_changes`, and returns a future which must be spawned
+/// onto a runtime. The future removes from the cache of statements prepared by the high level raw helpers (such as
+/// `stream_query`) those returning columns of a table altered by a DDL command, so that they are prepared again on
+/// next use. Statements which do not return table columns, and all statements when the affected object is unknown,
+/// are invalidated by every schema change. The future completes when `messages` ends or the client is dropped.
+pub async fn invalidate_on_schema_change<S, E>(
+    client: &Client,
+    messages: S,
+) -> Result<SchemaChangeInvalidator<S>, E>
+where
+    S: Stream<Item = AsyncMessage>,
+    E: std::convert::From<crate::error::Error>,
+{
+    batch_execute(client.inner(), SCHEMA_CHANGES_SETUP).await?;
+
+    Ok(SchemaChangeInvalidator {
+        messages,
+        client: Arc::downgrade(client.inner()),
+    })
+}
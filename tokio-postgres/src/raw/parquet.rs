@@ -0,0 +1,125 @@
+//! Parquet output for raw query streams.
+//!
+//! Requires the `parquet` Cargo feature.
+
+use crate::raw::arrow::{BatchBuilder, BATCH_SIZE};
+use crate::raw::query::{QueryStream, Row};
+use crate::raw::simple_query::SimpleColumn;
+use crate::types::{Oid, Type};
+use crate::Error;
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use futures_util::{pin_mut, TryStreamExt};
+use parquet_50::arrow::AsyncArrowWriter;
+use postgres_protocol::message::backend::Message;
+use std::io;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Maps PostgreSQL types to the Arrow data types used to write them.
+///
+/// The Parquet physical type of each column follows from its Arrow data type: for example `Int32` is written as
+/// `INT32`, `Utf8` as a UTF-8 annotated `BYTE_ARRAY` and `Timestamp(Microsecond, _)` as `INT64` annotated with
+/// `TIMESTAMP(MICROS)`.
+pub trait TypeMapper {
+    /// Returns the Arrow data type for a column of the type identified by `oid`, or `None` if the type is not
+    /// supported.
+    fn map(&self, oid: Oid) -> Option<DataType>;
+}
+
+/// The default `TypeMapper`, supporting the common scalar types.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultTypeMapper;
+
+impl TypeMapper for DefaultTypeMapper {
+    fn map(&self, oid: Oid) -> Option<DataType> {
+        let data_type = match Type::from_oid(oid)? {
+            Type::BOOL => DataType::Boolean,
+            Type::INT2 => DataType::Int16,
+            Type::INT4 => DataType::Int32,
+            Type::INT8 => DataType::Int64,
+            Type::FLOAT4 => DataType::Float32,
+            Type::FLOAT8 => DataType::Float64,
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => DataType::Utf8,
+            Type::BYTEA => DataType::Binary,
+            Type::DATE => DataType::Date32,
+            Type::TIME => DataType::Time64(TimeUnit::Microsecond),
+            Type::TIMESTAMP => DataType::Timestamp(TimeUnit::Microsecond, None),
+            Type::TIMESTAMPTZ => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            _ => return None,
+        };
+
+        Some(data_type)
+    }
+}
+
+/// Writes the rows of a stream to `writer` in the Parquet format.
+///
+/// `columns` describes the rows of the stream, as returned by `prepare_and_describe`, and `mapper` determines how
+/// each of them is written. The portal must have been bound requesting binary results. Rows are converted to Arrow
+/// record batches of up to 8192 rows before being written out in row groups.
+pub async fn rows_to_parquet<W, E>(
+    stream: QueryStream<E>,
+    columns: &[SimpleColumn],
+    mapper: &dyn TypeMapper,
+    writer: W,
+) -> Result<(), E>
+where
+    W: AsyncWrite + Unpin + Send,
+    E: std::convert::From<crate::error::Error>,
+{
+    let fields = columns
+        .iter()
+        .enumerate()
+        .map(|(idx, column)| {
+            let data_type = column
+                .type_()
+                .as_ref()
+                .and_then(|type_| mapper.map(type_.oid()))
+                .ok_or_else(|| {
+                    Error::from_sql(
+                        format!("unsupported type for column `{}`", column.name()).into(),
+                        idx,
+                    )
+                })?;
+            Ok(Field::new(column.name(), data_type, true))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let schema = Arc::new(Schema::new(fields));
+
+    let mut builder = BatchBuilder::new(schema.clone())?;
+    let mut writer =
+        AsyncArrowWriter::try_new(writer, schema, BUFFER_SIZE, None).map_err(parquet_error)?;
+
+    let mut rows = 0;
+    pin_mut!(stream);
+    while let Some(message) = stream.try_next().await? {
+        if let Message::DataRow(body) = message {
+            let row = Row::new(body)?;
+            builder.append_row((0..row.len()).map(|idx| row.get(idx)))?;
+            rows += 1;
+            if rows == BATCH_SIZE {
+                writer
+                    .write(&builder.finish()?)
+                    .await
+                    .map_err(parquet_error)?;
+                rows = 0;
+            }
+        }
+    }
+
+    if rows > 0 {
+        writer
+            .write(&builder.finish()?)
+            .await
+            .map_err(parquet_error)?;
+    }
+    writer.close().await.map_err(parquet_error)?;
+
+    Ok(())
+}
+
+fn parquet_error(e: parquet_50::errors::ParquetError) -> Error {
+    Error::io(io::Error::new(io::ErrorKind::Other, e))
+}
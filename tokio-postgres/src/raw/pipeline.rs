@@ -0,0 +1,128 @@
+use crate::raw::describe::{describe, DescribeTarget};
+use crate::raw::portal::Portal;
+use crate::raw::query::{bind, execute, prepare, sync, QueryStream};
+use crate::raw::statement::Statement;
+use crate::{Client, Error};
+use bytes::BytesMut;
+use postgres_protocol::message::frontend;
+use postgres_protocol::Oid;
+
+/// A builder of pipelines of extended query protocol commands.
+///
+/// Each method queues a command into the raw buffer of the client, without sending anything: the whole pipeline is
+/// sent in a single network write by `sync`, and the responses of the commands are returned in order. This is the
+/// same as calling `prepare`, `bind`, `execute` and `describe` before `sync`, but keeps the queued commands
+/// together.
+pub struct PipelineBuilder<'a> {
+    client: &'a Client,
+    len: usize,
+}
+
+impl<'a> PipelineBuilder<'a> {
+    /// Creates a new, empty pipeline.
+    ///
+    /// Commands buffered earlier on the client are sent along with the pipeline.
+    pub fn new(client: &'a Client) -> PipelineBuilder<'a> {
+        PipelineBuilder { client, len: 0 }
+    }
+
+    /// Returns the number of commands queued by the builder.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Determines if no command has been queued by the builder.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Queues the preparation of a statement, like `prepare`.
+    pub fn prepare<E>(&mut self, query: &str, name: &str, types_oid: &[Oid]) -> Result<Statement, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let statement = prepare(self.client, query, name, types_oid)?;
+        self.len += 1;
+        Ok(statement)
+    }
+
+    /// Queues the binding of a statement to a portal, like `bind`.
+    pub fn bind<'b, I, E>(
+        &mut self,
+        statement: &Statement,
+        portal: &str,
+        params_formats: &[i16],
+        params: I,
+        result_formats: &[i16],
+    ) -> Result<Portal, E>
+    where
+        I: IntoIterator<Item = &'b Option<BytesMut>>,
+        I::IntoIter: ExactSizeIterator,
+        E: std::convert::From<crate::error::Error>,
+    {
+        let portal = bind(
+            self.client,
+            statement,
+            portal,
+            params_formats,
+            params,
+            result_formats,
+        )?;
+        self.len += 1;
+        Ok(portal)
+    }
+
+    /// Queues the execution of a portal, like `execute`.
+    pub fn execute<E>(&mut self, portal: &Portal, max_rows: i32) -> Result<&mut Self, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        execute(self.client, portal, max_rows)?;
+        self.len += 1;
+        Ok(self)
+    }
+
+    /// Queues the description of a statement or a portal, like `describe`.
+    pub fn describe<E>(&mut self, target: DescribeTarget) -> Result<&mut Self, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        describe(self.client, target)?;
+        self.len += 1;
+        Ok(self)
+    }
+
+    /// Queues the closing of a prepared statement.
+    pub fn close_statement<E>(&mut self, name: &str) -> Result<&mut Self, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.close(b'S', name)?;
+        Ok(self)
+    }
+
+    /// Queues the closing of a portal.
+    pub fn close_portal<E>(&mut self, name: &str) -> Result<&mut Self, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.close(b'P', name)?;
+        Ok(self)
+    }
+
+    fn close(&mut self, variant: u8, name: &str) -> Result<(), Error> {
+        self.client
+            .inner()
+            .raw_buf(|buf| frontend::close(variant, name, buf).map_err(Error::encode))?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Sends the queued commands followed by a Sync message, like `sync`.
+    pub async fn sync<E>(self) -> Result<QueryStream<E>, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        sync(self.client).await
+    }
+}
@@ -1,20 +1,27 @@
 use crate::client::InnerClient;
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
+use crate::raw::decode::FromRow;
+use crate::raw::query::{execute, sync, Row};
+use crate::raw::simple_query::SimpleColumn;
+use crate::Client;
+use futures_util::{future, stream, Stream, TryFutureExt, TryStreamExt};
+use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 
 struct Inner {
     client: Weak<InnerClient>,
     name: String,
+    max_rows: AtomicI32,
+    fetched_count: AtomicU64,
+    suspended: AtomicBool,
 }
 
-impl Drop for Inner {
-    fn drop(&mut self) {
-        if self.name.is_empty() {
-            return;
-        }
-
+impl Inner {
+    fn close(&self) {
         if let Some(client) = self.client.upgrade() {
             if client.raw_buf(|buf| buf.is_empty()) {
                 let buf = client.with_buf(|buf| {
@@ -33,6 +40,16 @@ impl Drop for Inner {
     }
 }
 
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if self.name.is_empty() {
+            return;
+        }
+
+        self.close();
+    }
+}
+
 /// A portal.
 ///
 /// Portals can only be used with the connection that created them, and only exist for the duration of the transaction
@@ -45,6 +62,9 @@ impl Portal {
         Portal(Arc::new(Inner {
             client: Arc::downgrade(client),
             name: name.to_string(),
+            max_rows: AtomicI32::new(0),
+            fetched_count: AtomicU64::new(0),
+            suspended: AtomicBool::new(false),
         }))
     }
 
@@ -52,4 +72,151 @@ impl Portal {
     pub fn name(&self) -> &str {
         &self.0.name
     }
+
+    /// Returns the row limit passed to the last `execute` of the portal, 0 meaning no limit.
+    pub fn max_rows(&self) -> i32 {
+        self.0.max_rows.load(Ordering::SeqCst)
+    }
+
+    /// Returns the number of rows fetched from the portal so far.
+    ///
+    /// The count is updated as the `DataRow` messages of the portal executions are consumed from the `QueryStream`
+    /// returned by `sync`, and accumulates across executions.
+    pub fn fetched_count(&self) -> u64 {
+        self.0.fetched_count.load(Ordering::SeqCst)
+    }
+
+    /// Determines if the last completed execution of the portal stopped at its row limit.
+    ///
+    /// In that case the portal has more rows to return, and can be executed again. The flag is updated as the
+    /// `PortalSuspended` and `CommandComplete` messages of the portal executions are consumed from the `QueryStream`
+    /// returned by `sync`.
+    pub fn is_suspended(&self) -> bool {
+        self.0.suspended.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_max_rows(&self, max_rows: i32) {
+        self.0.max_rows.store(max_rows, Ordering::SeqCst);
+    }
+
+    pub(crate) fn increment_fetched_count(&self) {
+        self.0.fetched_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn set_suspended(&self, suspended: bool) {
+        self.0.suspended.store(suspended, Ordering::SeqCst);
+    }
+
+    /// Closes the portal on the server, before it is dropped.
+    ///
+    /// The Close message is sent right away if no command is buffered, or along with the buffered commands otherwise.
+    pub(crate) fn close(&self) {
+        self.0.close();
+    }
+}
+
+/// A portal decoding its rows into values of type `T`.
+///
+/// The columns describe the rows returned by the portal, and must match the result formats it was bound with:
+/// describing the portal itself reports them.
+pub struct TypedPortal<T> {
+    portal: Portal,
+    columns: Arc<[SimpleColumn]>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> TypedPortal<T>
+where
+    T: FromRow,
+{
+    /// Creates a typed portal from a portal and the description of its rows.
+    pub fn new(portal: Portal, columns: Arc<[SimpleColumn]>) -> TypedPortal<T> {
+        TypedPortal {
+            portal,
+            columns,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Returns the wrapped portal.
+    pub fn portal(&self) -> &Portal {
+        &self.portal
+    }
+
+    /// Returns the columns of the rows returned by the portal.
+    pub fn columns(&self) -> &[SimpleColumn] {
+        &self.columns
+    }
+
+    /// Executes the portal and decodes the returned rows.
+    ///
+    /// The execution is buffered along with the previously buffered commands, which are sent when the stream is
+    /// first polled. "max_rows" could be set to 0 to not apply any limit; otherwise the portal can be executed again
+    /// to fetch the next rows. Messages other than data rows are skipped.
+    pub fn execute<'a, E>(
+        &self,
+        client: &'a Client,
+        max_rows: i32,
+    ) -> impl Stream<Item = Result<T, E>> + 'a
+    where
+        T: 'a,
+        E: std::convert::From<crate::error::Error> + 'a,
+    {
+        let columns = self.columns.clone();
+        let responses = future::ready(execute::<E>(client, &self.portal, max_rows))
+            .and_then(move |()| sync::<E>(client));
+
+        stream::once(responses)
+            .try_flatten()
+            .try_filter_map(move |message| {
+                future::ready(match message {
+                    Message::DataRow(body) => Row::new(body)
+                        .and_then(|row| T::from_row(&row, &columns))
+                        .map(Some)
+                        .map_err(E::from),
+                    _ => Ok(None),
+                })
+            })
+    }
+}
+
+/// A source of portal names.
+///
+/// Generators must never return the same name twice, so that portals bound concurrently on the
+/// same connection do not clash.
+pub trait PortalNameGenerator: Send + Sync {
+    /// Returns a new portal name.
+    fn next_name(&self) -> String;
+}
+
+/// A portal name generator backed by an atomic counter.
+///
+/// Names are made of a prefix followed by an increasing number.
+#[derive(Debug)]
+pub struct AtomicPortalNameGenerator {
+    prefix: String,
+    next_id: AtomicU64,
+}
+
+impl AtomicPortalNameGenerator {
+    /// Creates a new generator using the given name prefix.
+    pub fn new(prefix: impl Into<String>) -> AtomicPortalNameGenerator {
+        AtomicPortalNameGenerator {
+            prefix: prefix.into(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Default for AtomicPortalNameGenerator {
+    fn default() -> AtomicPortalNameGenerator {
+        AtomicPortalNameGenerator::new("p")
+    }
+}
+
+impl PortalNameGenerator for AtomicPortalNameGenerator {
+    fn next_name(&self) -> String {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        format!("{}{}", self.prefix, id)
+    }
 }
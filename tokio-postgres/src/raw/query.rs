@@ -1,23 +1,30 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::raw::portal::Portal;
-use crate::raw::statement::Statement;
+use crate::raw::portal::{Portal, PortalNameGenerator};
+use crate::raw::simple_query::SimpleColumn;
+use crate::raw::statement::{Statement, WithStatement};
+#[cfg(feature = "runtime")]
+use crate::tls::NoTls;
+use crate::types::{FromSql, IsNull, ToSql, Type, WrongType};
 use crate::{Client, Error};
-use bytes::{BufMut, BytesMut};
+use bytes::{BufMut, Bytes, BytesMut};
 use fallible_iterator::FallibleIterator;
 use futures_util::task::{Context, Poll};
-use futures_util::{ready, Stream};
+use futures_util::{future, ready, Stream};
 use log::debug;
 use pin_project_lite::pin_project;
 use postgres_protocol::message::backend::{DataRowBody, Message};
 use postgres_protocol::message::frontend;
 use postgres_protocol::Oid;
+use std::collections::VecDeque;
 use std::fmt;
 use std::marker::{PhantomData, PhantomPinned};
-use std::ops::Range;
+use std::ops::{Index, Range};
 use std::pin::Pin;
 use std::sync::Arc;
+#[cfg(feature = "runtime")]
+use std::time::Duration;
 
 /// Creates a new prepared statement.
 ///
@@ -35,6 +42,23 @@ where
     Ok(internal_prepare(client.inner(), query, name, types_oid)?)
 }
 
+/// Creates a new prepared statement, closed on the server when the returned guard is dropped.
+///
+/// The statement is prepared like with `prepare`, but it is closed synchronously by the destructor of the guard. See
+/// `WithStatement` for the details.
+pub fn prepare_with_guard<'a, E>(
+    client: &'a Client,
+    query: &str,
+    name: &str,
+    types_oid: &[Oid],
+) -> Result<WithStatement<'a>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let statement = internal_prepare(client.inner(), query, name, types_oid)?;
+    Ok(WithStatement::new(statement, client))
+}
+
 pub fn internal_prepare(
     client: &Arc<InnerClient>,
     query: &str,
@@ -54,6 +78,59 @@ pub fn internal_prepare(
     Ok(Statement::new(client, name.to_string(), types_oid.to_vec()))
 }
 
+/// Runs a query through the unnamed statement and portal in a single round trip.
+///
+/// Parameters are sent as `text` values, while the result columns are requested in binary format.
+pub(crate) async fn internal_query(
+    client: &InnerClient,
+    query: &str,
+    params: &[Option<&str>],
+) -> Result<Vec<Row>, Error> {
+    debug!("executing query with parameters {:?}: {}", params, query);
+
+    let buf = client.with_buf(|buf| {
+        frontend::parse("", query, params.iter().map(|_| Type::TEXT.oid()), buf)
+            .map_err(Error::encode)?;
+        let r = frontend::bind(
+            "",
+            "",
+            Some(0),
+            params.iter(),
+            |param, buf| match param {
+                Some(value) => {
+                    buf.put_slice(value.as_bytes());
+                    Ok(postgres_protocol::IsNull::No)
+                }
+                None => Ok(postgres_protocol::IsNull::Yes),
+            },
+            Some(1),
+            buf,
+        );
+        match r {
+            Ok(()) => {}
+            Err(frontend::BindError::Serialization(e)) => return Err(Error::encode(e)),
+            Err(_) => return Err(Error::unexpected_message()),
+        }
+        frontend::execute("", 0, buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok(buf.split().freeze())
+    })?;
+
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(buf)))?;
+    let mut rows = vec![];
+    loop {
+        match responses.next().await? {
+            Message::ParseComplete
+            | Message::BindComplete
+            | Message::CommandComplete(_)
+            | Message::EmptyQueryResponse => {}
+            Message::DataRow(body) => rows.push(Row::new(body)?),
+            Message::ReadyForQuery(_) => return Ok(rows),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
 /// Binds some parameters to a prepared statement, thus creating a portal
 /// Portals could be then executed or dropped when no more needed.
 pub fn bind<'a, I, E>(
@@ -78,6 +155,30 @@ where
     Ok(Portal::new(inner, name))
 }
 
+/// Like `bind`, but names the portal with the given generator.
+pub fn bind_with_generator<'a, I, E>(
+    client: &Client,
+    statement: &Statement,
+    gen: &dyn PortalNameGenerator,
+    params_formats: &[i16],
+    params: I,
+    result_formats: &[i16],
+) -> Result<Portal, E>
+where
+    I: IntoIterator<Item = &'a Option<BytesMut>>,
+    I::IntoIter: ExactSizeIterator,
+    E: std::convert::From<crate::error::Error>,
+{
+    bind(
+        client,
+        statement,
+        &gen.next_name(),
+        params_formats,
+        params,
+        result_formats,
+    )
+}
+
 pub fn encode_bind<'a, I>(
     statement: &Statement,
     params_formats: &[i16],
@@ -114,10 +215,60 @@ where
     }
 }
 
+/// Like `encode_bind`, but serializes the parameters with `ToSql`, in the format each of them prefers.
+///
+/// `param_types` are the types of the statement's parameters. All the result columns are requested in binary format.
+pub(crate) fn encode_bind_tosql<'a, I>(
+    statement: &Statement,
+    param_types: &[Type],
+    params: I,
+    portal: &str,
+    buf: &mut BytesMut,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let params = params.into_iter();
+    if param_types.len() != params.len() {
+        return Err(Error::parameters(params.len(), param_types.len()));
+    }
+
+    let (param_formats, params): (Vec<_>, Vec<_>) = params
+        .zip(param_types)
+        .map(|(param, ty)| (param.encode_format(ty) as i16, param))
+        .unzip();
+
+    let mut error_idx = 0;
+    let r = frontend::bind(
+        portal,
+        statement.name(),
+        param_formats,
+        params.into_iter().zip(param_types).enumerate(),
+        |(idx, (param, ty)), buf| match param.to_sql_checked(ty, buf) {
+            Ok(IsNull::No) => Ok(postgres_protocol::IsNull::No),
+            Ok(IsNull::Yes) => Ok(postgres_protocol::IsNull::Yes),
+            Err(e) => {
+                error_idx = idx;
+                Err(e)
+            }
+        },
+        Some(1),
+        buf,
+    );
+    match r {
+        Ok(()) => Ok(()),
+        Err(frontend::BindError::Conversion(e)) => Err(Error::to_sql(e, error_idx)),
+        Err(frontend::BindError::Serialization(e)) => Err(Error::encode(e)),
+    }
+}
+
 pin_project! {
     /// A stream of table rows.
     pub struct QueryStream<E> {
         responses: Responses,
+        buffered: VecDeque<Result<Message, Error>>,
+        executions: VecDeque<Portal>,
         #[pin]
         _p: PhantomPinned,
         _e: PhantomData<E>
@@ -132,7 +283,30 @@ where
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         let this = self.project();
-        let message = ready!(this.responses.poll_next(cx));
+        let message = match this.buffered.pop_front() {
+            Some(message) => message,
+            None => ready!(this.responses.poll_next(cx)),
+        };
+        match &message {
+            Ok(Message::DataRow(_)) => {
+                if let Some(portal) = this.executions.front() {
+                    portal.increment_fetched_count();
+                }
+            }
+            Ok(Message::CommandComplete(_)) | Ok(Message::EmptyQueryResponse) => {
+                if let Some(portal) = this.executions.pop_front() {
+                    portal.set_suspended(false);
+                }
+            }
+            Ok(Message::PortalSuspended) => {
+                if let Some(portal) = this.executions.pop_front() {
+                    portal.set_suspended(true);
+                }
+            }
+            // The server skips the remaining commands up to the sync.
+            Ok(Message::ErrorResponse(_)) | Err(_) => this.executions.clear(),
+            _ => {}
+        }
         match message {
             Ok(Message::DataRow(_))
             | Ok(Message::ParseComplete)
@@ -158,6 +332,133 @@ where
     }
 }
 
+impl<E> QueryStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    /// Wraps the stream into a stream whose next message can be peeked.
+    pub fn peekable(self) -> PeekableQueryStream<E> {
+        PeekableQueryStream {
+            stream: Box::pin(self),
+            peeked: None,
+        }
+    }
+
+    /// Caps the number of rows streamed, as a client-side safety net.
+    ///
+    /// The returned stream counts the `DataRow` messages, and yields an error instead of the row exceeding `limit`.
+    /// The portal being executed is then closed, so that it cannot be fetched further. The remaining responses of the
+    /// batch are drained and discarded when the stream is polled again, up to the final `ReadyForQuery`, and the
+    /// stream then ends, leaving the client ready for the next command.
+    pub fn with_row_limit(self, limit: usize) -> LimitedQueryStream<E> {
+        LimitedQueryStream {
+            stream: self,
+            limit,
+            rows: 0,
+            limited: false,
+            done: false,
+        }
+    }
+}
+
+pin_project! {
+    /// A `QueryStream` capping the number of rows streamed, returned by `QueryStream::with_row_limit`.
+    pub struct LimitedQueryStream<E> {
+        #[pin]
+        stream: QueryStream<E>,
+        limit: usize,
+        rows: usize,
+        limited: bool,
+        done: bool,
+    }
+}
+
+impl<E> LimitedQueryStream<E> {
+    /// Returns the number of rows streamed so far.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+}
+
+impl<E> Stream for LimitedQueryStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    type Item = Result<Message, E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        if *this.limited {
+            loop {
+                match ready!(this.stream.as_mut().poll_next(cx)) {
+                    Some(Ok(Message::ReadyForQuery(_))) | None => {
+                        *this.done = true;
+                        return Poll::Ready(None);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        let message = ready!(this.stream.as_mut().poll_next(cx));
+        if let Some(Ok(Message::DataRow(_))) = &message {
+            if *this.rows == *this.limit {
+                if let Some(portal) = this.stream.project().executions.front() {
+                    portal.close();
+                }
+                *this.limited = true;
+                return Poll::Ready(Some(Err(Error::row_limit(*this.limit).into())));
+            }
+            *this.rows += 1;
+        }
+        Poll::Ready(message)
+    }
+}
+
+/// A `QueryStream` whose next message can be inspected without consuming it, returned by `QueryStream::peekable`.
+pub struct PeekableQueryStream<E> {
+    stream: Pin<Box<QueryStream<E>>>,
+    peeked: Option<Result<Message, E>>,
+}
+
+// The wrapped stream is boxed, and the peeked message is never pinned.
+impl<E> Unpin for PeekableQueryStream<E> {}
+
+impl<E> PeekableQueryStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    /// Returns the next message of the stream, without consuming it.
+    ///
+    /// The message is polled from the underlying stream if needed, and then stashed until it is consumed. Returns
+    /// `None` if the stream is exhausted.
+    pub async fn peek(&mut self) -> Option<&Result<Message, E>> {
+        if self.peeked.is_none() {
+            let stream = &mut self.stream;
+            self.peeked = future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await;
+        }
+        self.peeked.as_ref()
+    }
+}
+
+impl<E> Stream for PeekableQueryStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    type Item = Result<Message, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(message) = self.peeked.take() {
+            return Poll::Ready(Some(message));
+        }
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
 /// Executes a bound statement (portal).
 /// "max_rows" could be set to 0 to not apply any limit to the query.
 pub fn execute<E>(client: &Client, portal: &Portal, max_rows: i32) -> Result<(), E>
@@ -167,8 +468,31 @@ where
     let inner = client.inner();
     inner.raw_buf(|buf| {
         frontend::execute(portal.name(), max_rows, buf).map_err(Error::encode)?;
+        inner.raw_executions(|executions| executions.push_back(portal.clone()));
         Ok(())
     })?;
+    portal.set_max_rows(max_rows);
+
+    Ok(())
+}
+
+/// Determines if no command is buffered, waiting to be sent by `sync`.
+pub fn raw_buf_is_empty(client: &Client) -> bool {
+    client.inner().raw_buf(|buf| buf.is_empty())
+}
+
+/// Discards the buffered commands without sending them.
+///
+/// This allows recovering from an error raised while buffering commands, which may have left partial messages in
+/// the buffer, without reconnecting. The statements and portals of the discarded commands are not created on the
+/// server.
+pub fn flush_raw_buf<E>(client: &Client) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    inner.raw_buf(|buf| buf.clear());
+    inner.raw_executions(|executions| executions.clear());
 
     Ok(())
 }
@@ -179,20 +503,91 @@ where
     E: std::convert::From<crate::error::Error>,
 {
     let inner = client.inner();
-    let bytes = inner.with_buf(|buf| {
+    let (bytes, executions) = inner.with_buf(|buf| {
         frontend::sync(buf);
-        buf.split().freeze()
+        (buf.split().freeze(), inner.raw_executions(std::mem::take))
     });
 
     let responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
 
     Ok(QueryStream {
         responses,
+        buffered: VecDeque::new(),
+        executions,
         _p: PhantomPinned,
-        _e: PhantomData::default(),
+        _e: PhantomData,
     })
 }
 
+/// Like `sync`, but waits for the server to process the whole batch within `timeout`.
+///
+/// All the responses up to the final `ReadyForQuery` are buffered and replayed by the returned stream, so the whole
+/// result set is held in memory before the first row is yielded; `sync` should be preferred for large results. If the
+/// timeout expires, a cancellation request is sent to the server and a timeout error is returned. The cancellation
+/// request is sent without TLS, so it is rejected by servers which only accept TLS connections.
+///
+/// Requires the `runtime` Cargo feature (enabled by default).
+#[cfg(feature = "runtime")]
+pub async fn sync_with_timeout<E>(client: &Client, timeout: Duration) -> Result<QueryStream<E>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    let (bytes, executions) = inner.with_buf(|buf| {
+        frontend::sync(buf);
+        (buf.split().freeze(), inner.raw_executions(std::mem::take))
+    });
+
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut buffered = VecDeque::new();
+    let wait = async {
+        loop {
+            match responses.next().await {
+                Ok(Message::ReadyForQuery(body)) => {
+                    buffered.push_back(Ok(Message::ReadyForQuery(body)));
+                    return;
+                }
+                Err(e) if e.is_closed() => return,
+                message => buffered.push_back(message),
+            }
+        }
+    };
+
+    if tokio::time::timeout(timeout, wait).await.is_err() {
+        let _ = client.cancel_token().cancel_query(NoTls).await;
+        return Err(Error::timeout().into());
+    }
+
+    Ok(QueryStream {
+        responses,
+        buffered,
+        executions,
+        _p: PhantomPinned,
+        _e: PhantomData,
+    })
+}
+
+/// Checks that the connection is alive by sending a standalone `Sync` message.
+///
+/// Unlike `sync`, the commands buffered by `bind` and `execute` are left untouched and are not sent. The server
+/// replies with a `ReadyForQuery` message, which is awaited. See `Config::keepalive_interval` to do this periodically
+/// in the background.
+pub async fn keepalive<E>(client: &Client) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let mut buf = BytesMut::new();
+    frontend::sync(&mut buf);
+
+    let mut responses = client
+        .inner()
+        .send(RequestMessages::Single(FrontendMessage::Raw(buf.freeze())))?;
+    match responses.next().await? {
+        Message::ReadyForQuery(_) => Ok(()),
+        _ => Err(Error::unexpected_message().into()),
+    }
+}
+
 /// A row of data returned from the database by a query.
 pub struct Row {
     body: DataRowBody,
@@ -227,4 +622,116 @@ impl Row {
         let range = self.ranges[idx].to_owned()?;
         Some(&self.body.buffer()[range])
     }
+
+    /// Deserializes the value of the column at the given index.
+    ///
+    /// `columns` describes the row, and determines both the format and the type of the value: text format values
+    /// are decoded as `TEXT`.
+    pub fn try_get<'a, T>(&'a self, columns: &[SimpleColumn], idx: usize) -> Result<T, Error>
+    where
+        T: FromSql<'a>,
+    {
+        let (column, range) = match (columns.get(idx), self.ranges.get(idx)) {
+            (Some(column), Some(range)) => (column, range),
+            _ => return Err(Error::column(idx.to_string())),
+        };
+
+        let type_ = match (column.format(), column.type_()) {
+            (0, _) => Type::TEXT,
+            (_, Some(type_)) => type_.clone(),
+            (_, None) => {
+                return Err(Error::from_sql(
+                    format!("unknown type of column `{}`", column.name()).into(),
+                    idx,
+                ))
+            }
+        };
+        if !T::accepts(&type_) {
+            return Err(Error::from_sql(Box::new(WrongType::new::<T>(type_)), idx));
+        }
+
+        let buf = range.clone().map(|r| &self.body.buffer()[r]);
+        FromSql::from_sql_nullable(&type_, buf).map_err(|e| Error::from_sql(e, idx))
+    }
+
+    /// Returns the value of the column at the given index as a string, validating it as UTF-8.
+    ///
+    /// With the `simd-utf8` Cargo feature, validation is SIMD-accelerated where the CPU supports it.
+    pub fn get_str(&self, idx: usize) -> Result<Option<&str>, Error> {
+        match self.ranges.get(idx) {
+            Some(Some(range)) => from_utf8(&self.body.buffer()[range.clone()])
+                .map(Some)
+                .map_err(|e| Error::from_sql(e, idx)),
+            Some(None) => Ok(None),
+            None => Err(Error::column(idx.to_string())),
+        }
+    }
+}
+
+/// A row owning the values of its columns.
+///
+/// Unlike `Row`, a `GenericRow` does not borrow the message it was received in, so it can be stored in collections
+/// without any schema information. Indexing returns the raw value of a column, `None` being NULL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericRow(Vec<Option<Bytes>>);
+
+impl GenericRow {
+    /// Creates a generic row from a row, sharing the buffer of the message it was received in.
+    pub fn from_row(row: Row) -> GenericRow {
+        let buffer = row.body.buffer_bytes();
+        GenericRow(
+            row.ranges
+                .iter()
+                .map(|range| range.clone().map(|r| buffer.slice(r)))
+                .collect(),
+        )
+    }
+
+    /// Determines if the row contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Get the raw bytes for the column at the given index.
+    pub fn get(&self, idx: usize) -> Option<&[u8]> {
+        self.0[idx].as_deref()
+    }
+
+    /// Returns the value of the column at the given index as a string, validating it as UTF-8.
+    pub fn get_text(&self, idx: usize) -> Result<Option<&str>, Error> {
+        match self.0.get(idx) {
+            Some(Some(value)) => from_utf8(value)
+                .map(Some)
+                .map_err(|e| Error::from_sql(e, idx)),
+            Some(None) => Ok(None),
+            None => Err(Error::column(idx.to_string())),
+        }
+    }
+
+    pub(crate) fn into_values(self) -> Vec<Option<Bytes>> {
+        self.0
+    }
+}
+
+impl Index<usize> for GenericRow {
+    type Output = Option<Bytes>;
+
+    fn index(&self, idx: usize) -> &Option<Bytes> {
+        &self.0[idx]
+    }
+}
+
+#[cfg(feature = "simd-utf8")]
+fn from_utf8(buf: &[u8]) -> Result<&str, Box<dyn std::error::Error + Sync + Send>> {
+    simdutf8::compat::from_utf8(buf).map_err(Into::into)
+}
+
+#[cfg(not(feature = "simd-utf8"))]
+fn from_utf8(buf: &[u8]) -> Result<&str, Box<dyn std::error::Error + Sync + Send>> {
+    std::str::from_utf8(buf).map_err(Into::into)
 }
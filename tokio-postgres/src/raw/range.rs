@@ -0,0 +1,145 @@
+use crate::Error;
+use bytes::{BufMut, BytesMut};
+use postgres_protocol::types::{self, Range, RangeBound};
+use std::convert::TryFrom;
+use std::ops::{BitOr, Bound};
+
+/// The flags of a range value in the binary format.
+///
+/// Flags are combined with `|`, e.g. `RangeFlags::LOWER_INCLUSIVE | RangeFlags::UPPER_INFINITE`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RangeFlags(u8);
+
+impl RangeFlags {
+    /// The range is empty.
+    pub const EMPTY: RangeFlags = RangeFlags(0x01);
+    /// The lower bound is included in the range.
+    pub const LOWER_INCLUSIVE: RangeFlags = RangeFlags(0x02);
+    /// The upper bound is included in the range.
+    pub const UPPER_INCLUSIVE: RangeFlags = RangeFlags(0x04);
+    /// The range has no lower bound.
+    pub const LOWER_INFINITE: RangeFlags = RangeFlags(0x08);
+    /// The range has no upper bound.
+    pub const UPPER_INFINITE: RangeFlags = RangeFlags(0x10);
+
+    /// Returns the flags as sent to the server.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Determines if all the flags of `other` are set.
+    pub fn contains(self, other: RangeFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for RangeFlags {
+    type Output = RangeFlags;
+
+    fn bitor(self, other: RangeFlags) -> RangeFlags {
+        RangeFlags(self.0 | other.0)
+    }
+}
+
+/// Returns the flags of a bound, which is infinite if it is `None`.
+fn bound_flags<T>(
+    bound: &Option<T>,
+    inclusive: bool,
+    infinite: RangeFlags,
+    set: RangeFlags,
+) -> RangeFlags {
+    match bound {
+        Some(_) if inclusive => set,
+        Some(_) => RangeFlags::default(),
+        None => infinite,
+    }
+}
+
+/// Encodes a range in the binary format into `buf`, suitable as a parameter value of a range type.
+///
+/// `encode_bound` writes the binary representation of a single bound, in the format of the element type of the range
+/// (e.g. `int4` for `int4range`). A bound is infinite if it is `None` or if its `INFINITE` flag is set, in which case
+/// it is not written and its `INCLUSIVE` flag is ignored. If the `EMPTY` flag is set, both bounds are ignored.
+pub fn encode_range<T, F>(
+    lower: Option<T>,
+    upper: Option<T>,
+    flags: RangeFlags,
+    encode_bound: F,
+    buf: &mut BytesMut,
+) -> Result<(), Error>
+where
+    F: Fn(T, &mut BytesMut) -> Result<(), Error>,
+{
+    if flags.contains(RangeFlags::EMPTY) {
+        buf.put_u8(RangeFlags::EMPTY.bits());
+        return Ok(());
+    }
+
+    let lower = lower.filter(|_| !flags.contains(RangeFlags::LOWER_INFINITE));
+    let upper = upper.filter(|_| !flags.contains(RangeFlags::UPPER_INFINITE));
+    let tag = bound_flags(
+        &lower,
+        flags.contains(RangeFlags::LOWER_INCLUSIVE),
+        RangeFlags::LOWER_INFINITE,
+        RangeFlags::LOWER_INCLUSIVE,
+    ) | bound_flags(
+        &upper,
+        flags.contains(RangeFlags::UPPER_INCLUSIVE),
+        RangeFlags::UPPER_INFINITE,
+        RangeFlags::UPPER_INCLUSIVE,
+    );
+    buf.put_u8(tag.bits());
+
+    for bound in lower.into_iter().chain(upper) {
+        let base = buf.len();
+        buf.put_i32(0);
+        encode_bound(bound, buf)?;
+        let len = i32::try_from(buf.len() - base - 4)
+            .map_err(|_| Error::to_sql("value too large to transmit".into(), 0))?;
+        buf[base..base + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    Ok(())
+}
+
+/// A range value decoded by `decode_range`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgRange<T> {
+    /// The empty range.
+    Empty,
+    /// A non-empty range.
+    Range {
+        /// The lower bound of the range.
+        lower: Bound<T>,
+        /// The upper bound of the range.
+        upper: Bound<T>,
+    },
+}
+
+/// Decodes a range in the binary format.
+///
+/// `decode_bound` is called with the binary representation of each finite bound, in the format of the element type of
+/// the range. Infinite bounds are returned as `Bound::Unbounded`.
+pub fn decode_range<T, F>(bytes: &[u8], decode_bound: F) -> Result<PgRange<T>, Error>
+where
+    F: Fn(&[u8]) -> Result<T, Error>,
+{
+    let decode = |bound: RangeBound<Option<&[u8]>>| -> Result<Bound<T>, Error> {
+        match bound {
+            RangeBound::Inclusive(Some(value)) => Ok(Bound::Included(decode_bound(value)?)),
+            RangeBound::Exclusive(Some(value)) => Ok(Bound::Excluded(decode_bound(value)?)),
+            RangeBound::Inclusive(None) | RangeBound::Exclusive(None) => {
+                Err(Error::from_sql("unexpected NULL range bound".into(), 0))
+            }
+            RangeBound::Unbounded => Ok(Bound::Unbounded),
+        }
+    };
+
+    match types::range_from_sql(bytes).map_err(|e| Error::from_sql(e, 0))? {
+        Range::Empty => Ok(PgRange::Empty),
+        Range::Nonempty(lower, upper) => Ok(PgRange::Range {
+            lower: decode(lower)?,
+            upper: decode(upper)?,
+        }),
+    }
+}
@@ -0,0 +1,174 @@
+use crate::codec::{BackendMessages, FrontendMessage};
+use crate::config::SslMode;
+use crate::connection::{Request, RequestMessages};
+use crate::{Client, Error};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::ops::Deref;
+
+/// A request of a recorded session, along with the responses the server replied with.
+///
+/// Both the request and the responses are kept in their wire format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordedExchange {
+    request: Bytes,
+    responses: Bytes,
+}
+
+impl RecordedExchange {
+    /// Creates a new exchange from the bytes of a request and of its responses.
+    pub fn new(request: impl Into<Bytes>, responses: impl Into<Bytes>) -> RecordedExchange {
+        RecordedExchange {
+            request: request.into(),
+            responses: responses.into(),
+        }
+    }
+
+    /// Returns the bytes of the request.
+    pub fn request(&self) -> &Bytes {
+        &self.request
+    }
+
+    /// Returns the bytes of the responses.
+    pub fn responses(&self) -> &Bytes {
+        &self.responses
+    }
+}
+
+/// Encodes recorded exchanges into the binary format read by `decode_recording`.
+///
+/// Each exchange is made of the length of the request as a big endian `u32`, the request itself, and then the
+/// length of the responses followed by the responses.
+pub fn encode_recording(exchanges: &[RecordedExchange]) -> Bytes {
+    let mut buf = BytesMut::new();
+    for exchange in exchanges {
+        for part in &[&exchange.request, &exchange.responses] {
+            buf.put_u32(part.len() as u32);
+            buf.put_slice(part);
+        }
+    }
+    buf.freeze()
+}
+
+/// Decodes recorded exchanges written by `encode_recording`.
+pub fn decode_recording(data: &[u8]) -> Result<Vec<RecordedExchange>, Error> {
+    fn part(data: &mut &[u8]) -> Result<Bytes, Error> {
+        if data.remaining() < 4 {
+            return Err(truncated());
+        }
+        let len = data.get_u32() as usize;
+        if data.remaining() < len {
+            return Err(truncated());
+        }
+        Ok(data.copy_to_bytes(len))
+    }
+
+    let mut data = data;
+    let mut exchanges = vec![];
+    while !data.is_empty() {
+        let request = part(&mut data)?;
+        let responses = part(&mut data)?;
+        exchanges.push(RecordedExchange { request, responses });
+    }
+    Ok(exchanges)
+}
+
+fn truncated() -> Error {
+    Error::parse(io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        "truncated recording",
+    ))
+}
+
+/// A client replaying a recorded session, for deterministic tests of raw protocol consumers.
+///
+/// The replay client dereferences to a `Client`, so it can be passed to the `raw` functions. Instead of being sent
+/// to a server, each request is compared to the next recorded exchange, and answered with its recorded responses.
+/// Only requests made of raw messages can be replayed, so `COPY ... FROM STDIN` is not supported.
+///
+/// Like the connection of a client, the replay is performed by a future returned alongside the replay client, which
+/// should be spawned onto an executor. If a request does not match the recording, the future panics with a
+/// description of the difference. It completes once the replay client and its clones are dropped, and panics if some
+/// recorded exchanges were not replayed.
+pub struct ReplayClient {
+    client: Client,
+}
+
+impl ReplayClient {
+    /// Creates a client replaying the given exchanges, in order, along with the future performing the replay.
+    pub fn new(exchanges: Vec<RecordedExchange>) -> (ReplayClient, impl Future<Output = ()>) {
+        let (sender, receiver) = mpsc::unbounded();
+        let client = Client::new(sender, SslMode::Disable, 0, 0);
+
+        (ReplayClient { client }, replay(receiver, exchanges.into()))
+    }
+
+    /// Creates a client replaying a recording in the format written by `encode_recording`, along with the future
+    /// performing the replay.
+    pub fn from_recording(data: &[u8]) -> Result<(ReplayClient, impl Future<Output = ()>), Error> {
+        Ok(ReplayClient::new(decode_recording(data)?))
+    }
+}
+
+impl Deref for ReplayClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+/// Answers the requests of the client with the recorded responses.
+async fn replay(
+    mut receiver: mpsc::UnboundedReceiver<Request>,
+    mut exchanges: VecDeque<RecordedExchange>,
+) {
+    let mut index = 0;
+    while let Some(request) = receiver.next().await {
+        let actual = match request.messages {
+            RequestMessages::Single(FrontendMessage::Raw(bytes)) => bytes,
+            _ => panic!(
+                "request #{} cannot be replayed: only raw messages are supported",
+                index
+            ),
+        };
+        let exchange = match exchanges.pop_front() {
+            Some(exchange) => exchange,
+            None => panic!(
+                "request #{} is not in the recording\n  actual: {:?}",
+                index, actual
+            ),
+        };
+        if exchange.request != actual {
+            let offset = exchange
+                .request
+                .iter()
+                .zip(actual.iter())
+                .position(|(a, b)| a != b)
+                .unwrap_or(exchange.request.len().min(actual.len()));
+            panic!(
+                "request #{} does not match the recording at byte {}\nexpected: {:?}\n  actual: {:?}",
+                index, offset, exchange.request, actual
+            );
+        }
+
+        if !exchange.responses.is_empty() {
+            let mut sender = request.sender;
+            // The responses of some requests, such as keepalives, are not awaited.
+            let _ = sender
+                .send(BackendMessages::new(BytesMut::from(
+                    &exchange.responses[..],
+                )))
+                .await;
+        }
+        index += 1;
+    }
+
+    if !exchanges.is_empty() {
+        panic!("{} recorded exchanges were not replayed", exchanges.len());
+    }
+}
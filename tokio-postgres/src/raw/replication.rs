@@ -0,0 +1,333 @@
+use crate::client::Responses;
+use crate::raw::identifier;
+use crate::raw::simple_query::{internal_simple_query, SimpleQueryRow};
+use crate::types::PgLsn;
+use crate::{Client, Error};
+use bytes::{Buf, Bytes};
+use futures_util::Stream;
+use pin_project_lite::pin_project;
+use postgres_protocol::message::backend::Message;
+use std::io;
+use std::marker::{PhantomData, PhantomPinned};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Runs a replication command and collects the rows it returns.
+async fn command_rows(client: &Client, command: &str) -> Result<Vec<SimpleQueryRow>, Error> {
+    let mut responses = internal_simple_query(client.inner(), command)?;
+    let mut rows = vec![];
+
+    loop {
+        match responses.next().await? {
+            Message::DataRow(body) => rows.push(SimpleQueryRow::new(body)?),
+            Message::RowDescription(_) | Message::CommandComplete(_) => {}
+            Message::ReadyForQuery(_) => return Ok(rows),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Returns the field at the given index, failing if it is null.
+fn required(row: &SimpleQueryRow, idx: usize) -> Result<&str, Error> {
+    row.try_get(idx)?.ok_or_else(|| {
+        Error::from_sql(
+            Box::new(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected null value",
+            )),
+            idx,
+        )
+    })
+}
+
+fn invalid_data(message: &str) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+/// The identification of a server, as reported by `IDENTIFY_SYSTEM`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemIdentification {
+    system_id: String,
+    timeline: u32,
+    xlog_pos: PgLsn,
+    dbname: Option<String>,
+}
+
+impl SystemIdentification {
+    /// Returns the unique system identifier of the cluster.
+    pub fn system_id(&self) -> &str {
+        &self.system_id
+    }
+
+    /// Returns the current timeline ID.
+    pub fn timeline(&self) -> u32 {
+        self.timeline
+    }
+
+    /// Returns the current WAL flush location.
+    pub fn xlog_pos(&self) -> PgLsn {
+        self.xlog_pos
+    }
+
+    /// Returns the database connected to, or `None` on physical replication connections.
+    pub fn dbname(&self) -> Option<&str> {
+        self.dbname.as_deref()
+    }
+}
+
+/// Identifies the server with the `IDENTIFY_SYSTEM` replication command.
+///
+/// The client must have been connected in replication mode.
+pub async fn identify_system<E>(client: &Client) -> Result<SystemIdentification, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = command_rows(client, "IDENTIFY_SYSTEM").await?;
+    let row = match rows.first() {
+        Some(row) if rows.len() == 1 => row,
+        _ => return Err(Error::row_count().into()),
+    };
+
+    let identification = SystemIdentification {
+        system_id: required(row, 0)?.to_string(),
+        timeline: required(row, 1)?
+            .parse()
+            .map_err(|_| invalid_data("invalid timeline"))?,
+        xlog_pos: required(row, 2)?
+            .parse()
+            .map_err(|_| invalid_data("invalid LSN"))?,
+        dbname: row.try_get(3)?.map(str::to_string),
+    };
+
+    Ok(identification)
+}
+
+/// The history file of a timeline, as returned by `TIMELINE_HISTORY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineHistory {
+    filename: String,
+    content: String,
+}
+
+impl TimelineHistory {
+    /// Returns the file name of the timeline history file, e.g. `00000002.history`.
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    /// Returns the contents of the timeline history file.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+/// Retrieves the history file of a timeline with the `TIMELINE_HISTORY` replication command.
+///
+/// The client must have been connected in replication mode. The server reports an error for timelines without a
+/// history file, such as the initial timeline 1.
+pub async fn timeline_history<E>(client: &Client, timeline: u32) -> Result<TimelineHistory, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = command_rows(client, &format!("TIMELINE_HISTORY {}", timeline)).await?;
+    let row = match rows.first() {
+        Some(row) if rows.len() == 1 => row,
+        _ => return Err(Error::row_count().into()),
+    };
+
+    Ok(TimelineHistory {
+        filename: required(row, 0)?.to_string(),
+        content: required(row, 1)?.to_string(),
+    })
+}
+
+/// Options of a `BASE_BACKUP` command.
+#[derive(Debug, Clone, Default)]
+pub struct BaseBackupOptions {
+    label: Option<String>,
+    progress: bool,
+    fast: bool,
+    wal: bool,
+    no_wait: bool,
+    max_rate: Option<u32>,
+    tablespace_map: bool,
+}
+
+impl BaseBackupOptions {
+    /// Creates a new set of options, with the server defaults.
+    pub fn new() -> BaseBackupOptions {
+        BaseBackupOptions::default()
+    }
+
+    /// Sets the label of the backup.
+    #[must_use]
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Requests the server to report the progress of the backup.
+    #[must_use]
+    pub fn progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// Requests an immediate checkpoint at the start of the backup, rather than a spread one.
+    #[must_use]
+    pub fn fast(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        self
+    }
+
+    /// Includes the WAL segments required to restore the backup.
+    #[must_use]
+    pub fn wal(mut self, wal: bool) -> Self {
+        self.wal = wal;
+        self
+    }
+
+    /// Does not wait for the WAL segments to be archived at the end of the backup.
+    #[must_use]
+    pub fn no_wait(mut self, no_wait: bool) -> Self {
+        self.no_wait = no_wait;
+        self
+    }
+
+    /// Limits the transfer rate of the backup, in kilobytes per second.
+    #[must_use]
+    pub fn max_rate(mut self, max_rate: u32) -> Self {
+        self.max_rate = Some(max_rate);
+        self
+    }
+
+    /// Includes a `tablespace_map` file in the backup.
+    #[must_use]
+    pub fn tablespace_map(mut self, tablespace_map: bool) -> Self {
+        self.tablespace_map = tablespace_map;
+        self
+    }
+
+    fn to_sql(&self) -> String {
+        let mut options = vec![];
+        if let Some(label) = &self.label {
+            options.push(format!("LABEL {}", identifier::literal(label)));
+        }
+        if self.progress {
+            options.push("PROGRESS".to_string());
+        }
+        if self.fast {
+            options.push("CHECKPOINT 'fast'".to_string());
+        }
+        if self.wal {
+            options.push("WAL".to_string());
+        }
+        if self.no_wait {
+            options.push("WAIT false".to_string());
+        }
+        if let Some(max_rate) = self.max_rate {
+            options.push(format!("MAX_RATE {}", max_rate));
+        }
+        if self.tablespace_map {
+            options.push("TABLESPACE_MAP".to_string());
+        }
+
+        if options.is_empty() {
+            "BASE_BACKUP".to_string()
+        } else {
+            format!("BASE_BACKUP ({})", options.join(", "))
+        }
+    }
+}
+
+pin_project! {
+    /// A stream of the archives of a base backup.
+    ///
+    /// Each item is a chunk of data along with the name of the archive it belongs to, e.g. `base.tar`. The chunks of
+    /// the backup manifest, which is not part of any archive, come with no name.
+    pub struct BaseBackupStream<E> {
+        responses: Responses,
+        archive: Option<String>,
+        #[pin]
+        _p: PhantomPinned,
+        _e: PhantomData<E>,
+    }
+}
+
+impl<E> Stream for BaseBackupStream<E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    type Item = Result<(Option<String>, Bytes), E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        loop {
+            let message = match this.responses.poll_next(cx) {
+                Poll::Ready(Ok(message)) => message,
+                Poll::Ready(Err(e)) if e.is_closed() => return Poll::Ready(None),
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match message {
+                Message::CopyData(body) => {
+                    let mut data = body.into_bytes();
+                    if data.is_empty() {
+                        return Poll::Ready(Some(Err(Error::unexpected_message().into())));
+                    }
+                    match data.get_u8() {
+                        b'n' => match read_cstr(&mut data) {
+                            Ok(name) => *this.archive = Some(name),
+                            Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                        },
+                        b'd' => return Poll::Ready(Some(Ok((this.archive.clone(), data)))),
+                        b'm' => *this.archive = None,
+                        b'p' => {}
+                        _ => return Poll::Ready(Some(Err(Error::unexpected_message().into()))),
+                    }
+                }
+                Message::RowDescription(_)
+                | Message::DataRow(_)
+                | Message::CommandComplete(_)
+                | Message::CopyOutResponse(_)
+                | Message::CopyDone => {}
+                Message::ReadyForQuery(_) => return Poll::Ready(None),
+                _ => return Poll::Ready(Some(Err(Error::unexpected_message().into()))),
+            }
+        }
+    }
+}
+
+fn read_cstr(buf: &mut Bytes) -> Result<String, Error> {
+    let end = buf
+        .iter()
+        .position(|b| *b == 0)
+        .ok_or_else(|| invalid_data("unterminated string"))?;
+    let value = String::from_utf8(buf.split_to(end).to_vec())
+        .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    buf.advance(1);
+    Ok(value)
+}
+
+/// Starts a base backup with the `BASE_BACKUP` replication command.
+///
+/// The client must have been connected in replication mode, and the server must run PostgreSQL 15 or later. The
+/// archives of the backup, one per tablespace, are streamed back as tar data.
+pub async fn base_backup<E>(
+    client: &Client,
+    options: BaseBackupOptions,
+) -> Result<BaseBackupStream<E>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let responses = internal_simple_query(client.inner(), &options.to_sql())?;
+
+    Ok(BaseBackupStream {
+        responses,
+        archive: None,
+        _p: PhantomPinned,
+        _e: PhantomData,
+    })
+}
@@ -0,0 +1,74 @@
+use crate::Error;
+use parking_lot::Mutex;
+use postgres_protocol::authentication::sasl::{ChannelBinding, ScramSha256};
+use std::fmt;
+
+/// The client side of a SCRAM-SHA-256 authentication exchange, for custom authentication middleware.
+///
+/// The exchange is made of three steps, each consuming the state of the previous one:
+///
+/// 1. `start` returns the `client-first` message, sent in a `SASLInitialResponse` message with the `SCRAM-SHA-256`
+///    mechanism;
+/// 2. `continue_` receives the `server-first` message of the `AuthenticationSASLContinue` reply, and returns the
+///    `client-final` message, sent in a `SASLResponse` message;
+/// 3. `finish` receives the `server-final` message of the `AuthenticationSASLFinal` reply, and verifies the proof of
+///    the server.
+///
+/// Channel binding is not supported. Each step can only be performed once, later calls return an error.
+pub struct ScramClient {
+    scram: Mutex<Option<ScramSha256>>,
+}
+
+impl ScramClient {
+    /// Starts an exchange, returning the client and the `client-first` message.
+    ///
+    /// PostgreSQL authenticates the user sent in the startup message and ignores the SCRAM user name, so `username`
+    /// is not included in the message, as done by libpq.
+    pub fn start(username: &str, password: &str) -> (ScramClient, String) {
+        let _ = username;
+        let scram = ScramSha256::new(password.as_bytes(), ChannelBinding::unsupported());
+        let message = String::from_utf8_lossy(scram.message()).into_owned();
+
+        (ScramClient::new(scram), message)
+    }
+
+    /// Processes the `server-first` message, returning the client for the last step and the `client-final` message.
+    pub fn continue_(&self, server_first: &str) -> Result<(ScramClient, String), Error> {
+        let mut scram = self.take()?;
+        scram
+            .update(server_first.as_bytes())
+            .map_err(|e| Error::authentication(e.into()))?;
+        let message = String::from_utf8_lossy(scram.message()).into_owned();
+
+        Ok((ScramClient::new(scram), message))
+    }
+
+    /// Processes the `server-final` message, verifying the proof of the server.
+    ///
+    /// Authentication has only succeeded if this method returns `Ok(())`.
+    pub fn finish(&self, server_final: &str) -> Result<(), Error> {
+        self.take()?
+            .finish(server_final.as_bytes())
+            .map_err(|e| Error::authentication(e.into()))
+    }
+
+    fn new(scram: ScramSha256) -> ScramClient {
+        ScramClient {
+            scram: Mutex::new(Some(scram)),
+        }
+    }
+
+    fn take(&self) -> Result<ScramSha256, Error> {
+        self.scram
+            .lock()
+            .take()
+            .ok_or_else(|| Error::authentication("SCRAM step already performed".into()))
+    }
+}
+
+impl fmt::Debug for ScramClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The state holds the password and the nonce, which are not displayed.
+        f.debug_struct("ScramClient").finish_non_exhaustive()
+    }
+}
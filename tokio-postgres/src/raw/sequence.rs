@@ -0,0 +1,69 @@
+use crate::raw::identifier;
+use crate::raw::typed_query;
+use crate::types::ToSql;
+use crate::{Client, Error};
+
+/// Executes a query returning a single `bigint` through the statement cache.
+async fn query_i64(client: &Client, query: &str, params: &[&dyn ToSql]) -> Result<i64, Error> {
+    let mut execution = typed_query::start(client.inner(), query, params.iter().copied()).await?;
+    let rows = execution.collect::<(i64,)>().await?;
+    match rows.as_slice() {
+        [(value,)] => Ok(*value),
+        _ => Err(Error::row_count()),
+    }
+}
+
+/// Advances a sequence and returns its new value, with `nextval`.
+///
+/// `sequence` may be schema-qualified (`schema.sequence`). The statement is kept in the statement cache of the
+/// connection, so repeated calls only take a single round trip.
+pub async fn sequence_nextval<E>(client: &Client, sequence: &str) -> Result<i64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(sequence, true)?;
+    Ok(query_i64(client, "SELECT nextval($1::text::regclass)", &[&sequence]).await?)
+}
+
+/// Returns the value most recently obtained by `nextval` for a sequence in the current session, with `currval`.
+///
+/// The server reports an error if `nextval` has not been called for the sequence in this session yet.
+pub async fn sequence_currval<E>(client: &Client, sequence: &str) -> Result<i64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(sequence, true)?;
+    Ok(query_i64(client, "SELECT currval($1::text::regclass)", &[&sequence]).await?)
+}
+
+/// Returns the value most recently obtained by `nextval` for any sequence in the current session, with `lastval`.
+///
+/// The server reports an error if `nextval` has not been called in this session yet.
+pub async fn sequence_lastval<E>(client: &Client) -> Result<i64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(query_i64(client, "SELECT lastval()", &[]).await?)
+}
+
+/// Sets the current value of a sequence, with `setval`.
+///
+/// If `is_called` is set, the next call to `nextval` advances the sequence before returning a value; otherwise it
+/// returns `value` itself. Returns `value`.
+pub async fn sequence_setval<E>(
+    client: &Client,
+    sequence: &str,
+    value: i64,
+    is_called: bool,
+) -> Result<i64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(sequence, true)?;
+    Ok(query_i64(
+        client,
+        "SELECT setval($1::text::regclass, $2, $3)",
+        &[&sequence, &value, &is_called],
+    )
+    .await?)
+}
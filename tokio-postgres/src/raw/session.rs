@@ -0,0 +1,175 @@
+use crate::raw::query::internal_query;
+#[cfg(feature = "with-chrono-0_4")]
+use crate::raw::simple_query::internal_simple_query;
+use crate::simple_query::batch_execute;
+use crate::types::{FromSql, Type};
+use crate::{Client, Error, IsolationLevel};
+#[cfg(feature = "with-chrono-0_4")]
+use chrono_04::{DateTime, Utc};
+#[cfg(feature = "with-chrono-0_4")]
+use postgres_protocol::message::backend::Message;
+use std::io;
+
+/// Runs a simple query and returns the first column of its first row as text.
+#[cfg(feature = "with-chrono-0_4")]
+async fn simple_scalar(client: &Client, query: &str) -> Result<Option<String>, Error> {
+    let mut responses = internal_simple_query(client.inner(), query)?;
+    let mut value = None;
+
+    loop {
+        match responses.next().await? {
+            Message::DataRow(body) if value.is_none() => {
+                let row = crate::raw::SimpleQueryRow::new(body)?;
+                value = Some(row.try_get(0)?.map(str::to_string));
+            }
+            Message::RowDescription(_) | Message::DataRow(_) | Message::CommandComplete(_) => {}
+            Message::ReadyForQuery(_) => return value.ok_or_else(Error::row_count),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Returns the current time according to the server.
+///
+/// The timestamp is parsed from the text output of `SELECT NOW()`, which requires the `ISO`
+/// `DateStyle` (the server default).
+///
+/// Requires the `with-chrono-0_4` Cargo feature.
+#[cfg(feature = "with-chrono-0_4")]
+pub async fn current_timestamp<E>(client: &Client) -> Result<DateTime<Utc>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let value = simple_scalar(client, "SELECT NOW()")
+        .await?
+        .ok_or_else(Error::row_count)?;
+
+    let timestamp = DateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S%.f%#z")
+        .map_err(|e| Error::parse(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+    Ok(timestamp.with_timezone(&Utc))
+}
+
+/// Checks whether the current transaction is read only.
+///
+/// Outside of an explicit transaction block, this reports the `default_transaction_read_only` setting of the
+/// session, which is always on for hot standby servers.
+pub async fn xact_is_read_only<E>(client: &Client) -> Result<bool, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = internal_query(
+        client.inner(),
+        "SELECT current_setting('transaction_read_only')::bool",
+        &[],
+    )
+    .await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+
+    Ok(bool::from_sql_nullable(&Type::BOOL, row.get(0)).map_err(|e| Error::from_sql(e, 0))?)
+}
+
+/// Returns the name of the schema first in the search path, where new objects are created by default.
+///
+/// Returns `None` if none of the schemas in the search path exists, such as when the search path is empty.
+///
+/// The result is cached in the client until a command that may change the search path completes, such as `SET`,
+/// `RESET`, `COMMIT` or `ROLLBACK`. Changes made through `set_config` within other statements are not detected.
+pub async fn current_schema<E>(client: &Client) -> Result<Option<String>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    if let Some(schema) = inner.current_schema() {
+        return Ok(schema);
+    }
+
+    let generation = inner.search_path_generation();
+    let rows = internal_query(inner, "SELECT current_schema()", &[]).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+    let schema = Option::<String>::from_sql_nullable(&Type::NAME, row.get(0))
+        .map_err(|e| Error::from_sql(e, 0))?;
+    inner.set_current_schema(generation, schema.as_deref());
+
+    Ok(schema)
+}
+
+/// Returns the names of the schemas in the effective search path, in their priority order.
+///
+/// If `include_implicit` is true, implicitly searched system schemas such as `pg_catalog` are included as well.
+/// The result is cached in the same way as for `current_schema`.
+pub async fn current_schemas<E>(client: &Client, include_implicit: bool) -> Result<Vec<String>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    if let Some(schemas) = inner.current_schemas(include_implicit) {
+        return Ok(schemas);
+    }
+
+    let generation = inner.search_path_generation();
+    let param = if include_implicit { "true" } else { "false" };
+    let rows = internal_query(inner, "SELECT current_schemas($1::bool)", &[Some(param)]).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+    let schemas = Vec::<String>::from_sql_nullable(&Type::NAME_ARRAY, row.get(0))
+        .map_err(|e| Error::from_sql(e, 0))?;
+    inner.set_current_schemas(generation, include_implicit, &schemas);
+
+    Ok(schemas)
+}
+
+/// Returns the isolation level of the current transaction.
+///
+/// Outside of an explicit transaction block, this reports the isolation level of the next transaction.
+pub async fn transaction_isolation<E>(client: &Client) -> Result<IsolationLevel, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let rows = internal_query(client.inner(), "SHOW transaction_isolation", &[]).await?;
+    let row = rows.first().ok_or_else(Error::row_count)?;
+    let level =
+        String::from_sql_nullable(&Type::TEXT, row.get(0)).map_err(|e| Error::from_sql(e, 0))?;
+
+    match level.as_str() {
+        "read uncommitted" => Ok(IsolationLevel::ReadUncommitted),
+        "read committed" => Ok(IsolationLevel::ReadCommitted),
+        "repeatable read" => Ok(IsolationLevel::RepeatableRead),
+        "serializable" => Ok(IsolationLevel::Serializable),
+        _ => Err(Error::parse(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unknown isolation level `{}`", level),
+        ))
+        .into()),
+    }
+}
+
+/// Sets the isolation level of transactions.
+///
+/// If `local` is set, only the current transaction is affected, with `SET TRANSACTION`: this must be called within a
+/// transaction block, before any query. Otherwise the default isolation level of the following transactions of the
+/// session is set, with `SET SESSION CHARACTERISTICS AS TRANSACTION`.
+pub async fn set_transaction_isolation<E>(
+    client: &Client,
+    level: IsolationLevel,
+    local: bool,
+) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    let level = match level {
+        IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+        IsolationLevel::ReadCommitted => "READ COMMITTED",
+        IsolationLevel::RepeatableRead => "REPEATABLE READ",
+        IsolationLevel::Serializable => "SERIALIZABLE",
+    };
+    let query = if local {
+        format!("SET TRANSACTION ISOLATION LEVEL {}", level)
+    } else {
+        format!(
+            "SET SESSION CHARACTERISTICS AS TRANSACTION ISOLATION LEVEL {}",
+            level
+        )
+    };
+
+    batch_execute(client.inner(), &query).await?;
+    Ok(())
+}
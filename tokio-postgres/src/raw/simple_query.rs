@@ -18,8 +18,14 @@ use pin_project_lite::pin_project;
 use postgres_protocol::message::backend::{DataRowBody, Message, RowDescriptionBody};
 use postgres_protocol::message::frontend;
 #[cfg(feature = "raw")]
+use postgres_protocol::Oid;
+#[cfg(feature = "raw")]
 use postgres_types::FromSql;
 #[cfg(feature = "raw")]
+use std::collections::HashMap;
+#[cfg(feature = "raw")]
+use std::io;
+#[cfg(feature = "raw")]
 use std::marker::PhantomData;
 #[cfg(feature = "raw")]
 use std::marker::PhantomPinned;
@@ -108,11 +114,13 @@ where
 /// in a more convenient way.
 ///
 /// Compared to the standard SimpleQueryRow, this has a simpler structure,
-/// no column data (meaning you can't get data by column name) and only a try_get method.
+/// no column data unless attached with `with_columns` (meaning you can't get data by column name)
+/// and only a try_get method.
 #[cfg(feature = "raw")]
 pub struct SimpleQueryRow {
     body: DataRowBody,
     ranges: Vec<Option<Range<usize>>>,
+    columns: Option<Arc<[SimpleColumn]>>,
 }
 
 #[cfg(feature = "raw")]
@@ -121,7 +129,22 @@ impl SimpleQueryRow {
     #[allow(clippy::new_ret_no_self)]
     pub fn new(body: DataRowBody) -> Result<SimpleQueryRow, Error> {
         let ranges = body.ranges().collect().map_err(Error::parse)?;
-        Ok(SimpleQueryRow { body, ranges })
+        Ok(SimpleQueryRow {
+            body,
+            ranges,
+            columns: None,
+        })
+    }
+
+    /// Attaches the description of the columns of the row, as returned by the `RowDescription` message.
+    pub fn with_columns(mut self, columns: Arc<[SimpleColumn]>) -> SimpleQueryRow {
+        self.columns = Some(columns);
+        self
+    }
+
+    /// Returns the columns attached with `with_columns`, if any.
+    pub fn columns(&self) -> Option<&[SimpleColumn]> {
+        self.columns.as_deref()
     }
 
     /// Determines if the row contains no values.
@@ -140,6 +163,126 @@ impl SimpleQueryRow {
         let buf = self.ranges[idx].clone().map(|r| &self.body.buffer()[r]);
         FromSql::from_sql_nullable(&Type::TEXT, buf).map_err(|e| Error::from_sql(e, idx))
     }
+
+    /// Returns the values of the row, keyed by the names of their columns.
+    ///
+    /// Returns an error if no columns are attached to the row, or if they do not match its values. If several columns
+    /// have the same name, the last one is kept.
+    pub fn as_hashmap(&self) -> Result<HashMap<&str, Option<&str>>, Error> {
+        let columns = match &self.columns {
+            Some(columns) if columns.len() == self.len() => columns,
+            Some(_) => return Err(columns_error("the columns do not match the row values")),
+            None => return Err(columns_error("no columns are attached to the row")),
+        };
+
+        columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| Ok((column.name(), self.try_get(idx)?)))
+            .collect()
+    }
+
+    /// Like `as_hashmap`, but returns owned names and values.
+    pub fn into_hashmap(self) -> Result<HashMap<String, Option<String>>, Error> {
+        let map = self
+            .as_hashmap()?
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.map(str::to_string)))
+            .collect();
+        Ok(map)
+    }
+
+    /// Formats the row as a CSV line, without the trailing line terminator.
+    ///
+    /// NULL values are written as empty unquoted fields, while empty strings are quoted to tell them apart. Values
+    /// containing the delimiter, the quoting character or a line break are quoted, doubling any quoting character.
+    pub fn to_csv(&self, delimiter: char, quote: char) -> String {
+        self.to_csv_with_null(delimiter, quote, "")
+    }
+
+    /// Like `to_csv`, but writes NULL values as `null` unquoted.
+    ///
+    /// Values equal to `null` are quoted to tell them apart from NULL values.
+    pub fn to_csv_with_null(&self, delimiter: char, quote: char, null: &str) -> String {
+        let mut line = String::new();
+        for (i, range) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                line.push(delimiter);
+            }
+
+            let range = match range {
+                Some(range) => range.clone(),
+                None => {
+                    line.push_str(null);
+                    continue;
+                }
+            };
+            let value = String::from_utf8_lossy(&self.body.buffer()[range]);
+
+            let needs_quotes = value == null
+                || value.is_empty()
+                || value
+                    .chars()
+                    .any(|c| c == delimiter || c == quote || c == '\r' || c == '\n');
+            if needs_quotes {
+                line.push(quote);
+                for c in value.chars() {
+                    if c == quote {
+                        line.push(quote);
+                    }
+                    line.push(c);
+                }
+                line.push(quote);
+            } else {
+                line.push_str(&value);
+            }
+        }
+
+        line
+    }
+}
+
+#[cfg(feature = "raw")]
+fn columns_error(message: &str) -> Error {
+    Error::parse(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+/// A row of data returned by a simple query, with its values decoded into owned strings.
+///
+/// Unlike `SimpleQueryRow`, the values are validated as UTF-8 once, when the row is created, and the row can be
+/// collected and processed later without keeping the message buffers around.
+#[cfg(feature = "raw")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedSimpleQueryRow(Vec<Option<String>>);
+
+#[cfg(feature = "raw")]
+impl OwnedSimpleQueryRow {
+    /// Creates an owned row by copying the values of a simple query row.
+    pub fn from_simple_row(row: &SimpleQueryRow) -> Result<OwnedSimpleQueryRow, Error> {
+        (0..row.len())
+            .map(|idx| Ok(row.try_get(idx)?.map(str::to_string)))
+            .collect::<Result<_, Error>>()
+            .map(OwnedSimpleQueryRow)
+    }
+
+    /// Determines if the row contains no values.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the number of values in the row.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns a value from the row, or `None` if it is NULL.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.0[idx].as_deref()
+    }
 }
 
 /// Information about a column of a single query row.
@@ -147,6 +290,7 @@ impl SimpleQueryRow {
 pub struct SimpleColumn {
     name: String,
     type_: Option<Type>,
+    type_oid: Oid,
     format: i16,
     table_oid: i32,
     column_id: i16,
@@ -157,7 +301,7 @@ pub struct SimpleColumn {
 impl SimpleColumn {
     pub(crate) fn new(
         name: String,
-        type_: Option<Type>,
+        type_oid: Oid,
         format: i16,
         table_oid: i32,
         column_id: i16,
@@ -165,7 +309,8 @@ impl SimpleColumn {
     ) -> SimpleColumn {
         SimpleColumn {
             name,
-            type_,
+            type_: Type::from_oid(type_oid),
+            type_oid,
             format,
             table_oid,
             column_id,
@@ -178,11 +323,28 @@ impl SimpleColumn {
         &self.name
     }
 
-    /// Returns the type of the column.
+    /// Returns the type of the column, or `None` if it is not a built-in type.
+    ///
+    /// The type is derived from `type_oid`, which is available for every type.
     pub fn type_(&self) -> &Option<Type> {
         &self.type_
     }
 
+    /// Returns the OID of the type of the column, even if the type is not known.
+    pub fn type_oid(&self) -> Oid {
+        self.type_oid
+    }
+
+    /// Returns the name of the type of the column, or `unknown(oid)` if the type is not known.
+    ///
+    /// Only built-in types are known; the names of other types can be looked up in `pg_type` from `type_oid`.
+    pub fn format_type(&self) -> String {
+        match &self.type_ {
+            Some(type_) => type_.name().to_string(),
+            None => format!("unknown({})", self.type_oid),
+        }
+    }
+
     /// Returns the format of the field.
     pub fn format(&self) -> i16 {
         self.format
@@ -210,7 +372,7 @@ impl SimpleColumn {
             .map(|f| {
                 Ok(SimpleColumn::new(
                     f.name().to_string(),
-                    Type::from_oid(f.type_oid()),
+                    f.type_oid(),
                     f.format(),
                     f.table_oid() as i32,
                     f.column_id(),
@@ -1,9 +1,14 @@
 use crate::client::InnerClient;
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
+use crate::Client;
+use bytes::BytesMut;
+use postgres_protocol::message::backend::Message;
 use postgres_protocol::message::frontend;
 use postgres_protocol::Oid;
+use std::ops::Deref;
 use std::sync::{Arc, Weak};
+use tokio::runtime::{Handle, RuntimeFlavor};
 
 struct StatementInner {
     client: Weak<InnerClient>,
@@ -50,6 +55,16 @@ impl Statement {
         }))
     }
 
+    /// Determines if the statement was prepared on the given client.
+    pub(crate) fn belongs_to(&self, client: &Arc<InnerClient>) -> bool {
+        self.0.client.as_ptr() == Arc::as_ptr(client)
+    }
+
+    /// Determines if the client the statement was prepared on is still alive.
+    pub(crate) fn is_orphaned(&self) -> bool {
+        self.0.client.strong_count() == 0
+    }
+
     /// Gets the name of the current statement.
     pub fn name(&self) -> &str {
         &self.0.name
@@ -60,3 +75,66 @@ impl Statement {
         &self.0.param_types
     }
 }
+
+/// A prepared statement which is closed on the server when dropped, waiting for the server to process the close.
+///
+/// Unlike `Statement`, whose destructor only queues a Close message, dropping a `WithStatement` blocks the current
+/// thread until the statement is closed, including while unwinding from a panic. Blocking relies on
+/// `tokio::task::block_in_place`, which is why the `raw` Cargo feature enables the `rt-multi-thread` feature of Tokio.
+/// The current runtime is checked when the guard is dropped: blocking only happens within a multi-threaded Tokio
+/// runtime, while elsewhere the Close message is sent without waiting for its completion. Clones of the statement
+/// obtained through `Deref` must not be used once the guard is dropped.
+pub struct WithStatement<'a> {
+    stmt: Statement,
+    client: &'a Client,
+}
+
+impl<'a> WithStatement<'a> {
+    pub(crate) fn new(stmt: Statement, client: &'a Client) -> WithStatement<'a> {
+        WithStatement { stmt, client }
+    }
+}
+
+impl Deref for WithStatement<'_> {
+    type Target = Statement;
+
+    fn deref(&self) -> &Statement {
+        &self.stmt
+    }
+}
+
+impl Drop for WithStatement<'_> {
+    fn drop(&mut self) {
+        if self.stmt.name().is_empty() {
+            return;
+        }
+
+        let mut buf = BytesMut::new();
+        if frontend::close(b'S', self.stmt.name(), &mut buf).is_err() {
+            return;
+        }
+        frontend::sync(&mut buf);
+        let mut responses = match self
+            .client
+            .inner()
+            .send(RequestMessages::Single(FrontendMessage::Raw(buf.freeze())))
+        {
+            Ok(responses) => responses,
+            Err(_) => return,
+        };
+
+        let handle = match Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => handle,
+            _ => return,
+        };
+        tokio::task::block_in_place(|| {
+            handle.block_on(async {
+                while let Ok(message) = responses.next().await {
+                    if let Message::ReadyForQuery(_) = message {
+                        break;
+                    }
+                }
+            })
+        });
+    }
+}
@@ -0,0 +1,159 @@
+use crate::client::InnerClient;
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::prepare::get_type;
+use crate::raw::simple_query::SimpleColumn;
+use crate::raw::statement::Statement;
+use crate::types::Type;
+use crate::Error;
+use fallible_iterator::FallibleIterator;
+use log::debug;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use postgres_protocol::Oid;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// The number of statements kept by a `StatementCache`.
+const CAPACITY: usize = 64;
+
+/// A prepared statement along with its description.
+#[derive(Clone)]
+pub(crate) struct CachedStatement {
+    pub statement: Statement,
+    pub param_types: Vec<Type>,
+    pub columns: Arc<[SimpleColumn]>,
+    /// The tables the result columns come from.
+    pub tables: Vec<Oid>,
+}
+
+/// A per-connection cache of the statements prepared by the high level raw helpers.
+///
+/// Statements are named automatically. When the cache is full, the least recently used statement is evicted, and
+/// closed once no longer in use.
+#[derive(Default)]
+pub(crate) struct StatementCache {
+    next_id: u64,
+    /// Cached statements by query, from the least to the most recently used.
+    entries: VecDeque<(String, CachedStatement)>,
+}
+
+impl StatementCache {
+    fn get(&mut self, query: &str) -> Option<CachedStatement> {
+        let idx = self.entries.iter().position(|(q, _)| q == query)?;
+        let entry = self.entries.remove(idx)?;
+        let statement = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(statement)
+    }
+
+    fn next_name(&mut self) -> String {
+        let id = self.next_id;
+        self.next_id += 1;
+        format!("raw{}", id)
+    }
+
+    /// Removes the statements which may be affected by a change to the object `oid`, or all of them if it is unknown.
+    ///
+    /// Statements are considered affected if one of their result columns comes from the object, or if none of them
+    /// comes from a table.
+    pub fn invalidate(&mut self, oid: Option<Oid>) {
+        match oid {
+            Some(oid) => self.entries.retain(|(_, statement)| {
+                !statement.tables.is_empty() && !statement.tables.contains(&oid)
+            }),
+            None => self.entries.clear(),
+        }
+    }
+
+    fn insert(&mut self, query: &str, statement: CachedStatement) {
+        if self.entries.len() >= CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((query.to_string(), statement));
+    }
+}
+
+/// Returns the cached statement for `query`, preparing and describing it in a single round trip if needed.
+///
+/// Commands buffered in the raw buffer are sent along with the preparation, and their responses are discarded.
+pub(crate) async fn get_or_prepare(
+    client: &Arc<InnerClient>,
+    query: &str,
+) -> Result<CachedStatement, Error> {
+    if let Some(statement) = client.raw_statements(|cache| cache.get(query)) {
+        return Ok(statement);
+    }
+
+    let name = client.raw_statements(|cache| cache.next_name());
+    debug!("preparing query {}: {}", name, query);
+
+    let bytes = client.with_buf(|buf| {
+        frontend::parse(&name, query, None, buf).map_err(Error::encode)?;
+        frontend::describe(b'S', &name, buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+
+    let mut responses = client.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+    let mut params: Option<Vec<Oid>> = None;
+    let mut columns = None;
+    loop {
+        match responses.next().await? {
+            Message::ParameterDescription(body) => {
+                params = Some(body.parameters().collect().map_err(Error::parse)?);
+            }
+            Message::RowDescription(body) => {
+                // The statement description reports the text format, while the rows are always requested in binary
+                // format by `encode_bind_tosql`.
+                let described = SimpleColumn::from_row_description_body(body)?;
+                columns = Some(
+                    described
+                        .iter()
+                        .map(|column| {
+                            SimpleColumn::new(
+                                column.name().to_string(),
+                                column.type_oid(),
+                                1,
+                                column.table_oid(),
+                                column.column_id(),
+                                column.type_modifier(),
+                            )
+                        })
+                        .collect(),
+                );
+            }
+            Message::NoData => columns = Some(Arc::from(vec![])),
+            Message::ReadyForQuery(_) => break,
+            _ => {}
+        }
+    }
+
+    let (params, columns) = match (params, columns) {
+        (Some(params), Some(columns)) => (params, columns),
+        _ => return Err(Error::unexpected_message()),
+    };
+    let statement = Statement::new(client, name, params);
+    let mut param_types = Vec::with_capacity(statement.param_types().len());
+    for &oid in statement.param_types() {
+        param_types.push(get_type(client, oid).await?);
+    }
+
+    let mut tables = columns
+        .iter()
+        .map(|column| column.table_oid() as Oid)
+        .filter(|&oid| oid != 0)
+        .collect::<Vec<_>>();
+    tables.sort_unstable();
+    tables.dedup();
+
+    let statement = CachedStatement {
+        statement,
+        param_types,
+        columns,
+        tables,
+    };
+    client.raw_statements(|cache| cache.insert(query, statement.clone()));
+
+    Ok(statement)
+}
@@ -0,0 +1,86 @@
+use crate::raw::query::internal_prepare;
+use crate::raw::statement::Statement;
+use crate::Client;
+use parking_lot::Mutex;
+use postgres_protocol::Oid;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A set of prepared statements shared between several connections.
+///
+/// Each distinct query is given a statement name, which is then prepared independently on every connection it is
+/// used with. Connections are identified by their backend process ID.
+pub struct SharedStatementPool {
+    prefix: String,
+    next_id: AtomicU64,
+    names: Mutex<HashMap<(String, Vec<Oid>), String>>,
+    statements: Mutex<HashMap<(i32, String), Statement>>,
+}
+
+impl fmt::Debug for SharedStatementPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedStatementPool")
+            .field("prefix", &self.prefix)
+            .field("names", &self.names.lock().len())
+            .field("statements", &self.statements.lock().len())
+            .finish()
+    }
+}
+
+impl Default for SharedStatementPool {
+    fn default() -> SharedStatementPool {
+        SharedStatementPool::new("pool")
+    }
+}
+
+impl SharedStatementPool {
+    /// Creates a new pool, naming its statements with the given prefix followed by a counter.
+    pub fn new(prefix: &str) -> SharedStatementPool {
+        SharedStatementPool {
+            prefix: prefix.to_string(),
+            next_id: AtomicU64::new(0),
+            names: Mutex::new(HashMap::new()),
+            statements: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the statement for `query` on the given client, preparing it if needed.
+    ///
+    /// When the statement has not been prepared on the client's connection yet, a Parse message is buffered and will
+    /// be fired on next sync. Otherwise the statement prepared earlier is returned and nothing is sent.
+    pub fn get_or_prepare<E>(
+        &self,
+        client: &Client,
+        query: &str,
+        types_oid: &[Oid],
+    ) -> Result<Statement, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        let name = self
+            .names
+            .lock()
+            .entry((query.to_string(), types_oid.to_vec()))
+            .or_insert_with(|| {
+                let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+                format!("{}{}", self.prefix, id)
+            })
+            .clone();
+
+        let mut statements = self.statements.lock();
+        let key = (client.backend_pid(), name);
+        if let Some(statement) = statements.get(&key) {
+            // Process IDs are reused, so make sure this is the very same connection.
+            if statement.belongs_to(client.inner()) {
+                return Ok(statement.clone());
+            }
+        }
+
+        let statement = internal_prepare(client.inner(), query, &key.1, types_oid)?;
+        statements.retain(|_, statement| !statement.is_orphaned());
+        statements.insert(key, statement.clone());
+
+        Ok(statement)
+    }
+}
@@ -0,0 +1,84 @@
+use crate::raw::message::split_messages;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Metrics of the queries made through a connection, returned by `Client::statistics`.
+///
+/// The counters are updated as raw messages are sent to and received from the server, so they cover both the raw
+/// and the high level APIs. The data sent by `COPY ... FROM STDIN` and the protocol-level keepalives are not
+/// counted.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    queries_executed: AtomicU64,
+    rows_fetched: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    parse_count: AtomicU64,
+    bind_count: AtomicU64,
+    sync_count: AtomicU64,
+}
+
+impl Statistics {
+    /// Returns the number of queries executed, counting both Execute and simple Query messages.
+    pub fn queries_executed(&self) -> u64 {
+        self.queries_executed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of rows received.
+    pub fn rows_fetched(&self) -> u64 {
+        self.rows_fetched.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes sent to the server.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes received from the server, excluding asynchronous messages.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of Parse messages sent.
+    pub fn parse_count(&self) -> u64 {
+        self.parse_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of Bind messages sent.
+    pub fn bind_count(&self) -> u64 {
+        self.bind_count.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of Sync messages sent.
+    pub fn sync_count(&self) -> u64 {
+        self.sync_count.load(Ordering::Relaxed)
+    }
+
+    /// Records a buffer of frontend messages about to be sent.
+    pub(crate) fn record_request(&self, buf: &[u8]) {
+        self.bytes_sent
+            .fetch_add(buf.len() as u64, Ordering::Relaxed);
+
+        for (tag, _) in split_messages(buf) {
+            let counter = match tag {
+                b'E' | b'Q' => Some(&self.queries_executed),
+                b'P' => Some(&self.parse_count),
+                b'B' => Some(&self.bind_count),
+                b'S' => Some(&self.sync_count),
+                _ => None,
+            };
+            if let Some(counter) = counter {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Records a buffer of backend messages received.
+    pub(crate) fn record_responses(&self, len: usize) {
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    /// Records a row received.
+    pub(crate) fn record_row(&self) {
+        self.rows_fetched.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,112 @@
+use crate::raw::builder::BoxedParam;
+use crate::raw::decode::FromRow;
+use crate::raw::identifier;
+use crate::raw::typed_query;
+use crate::types::ToSql;
+use crate::{Client, Error};
+
+/// Builds a `WHERE` clause matching every column of `conditions` against a parameter, numbered from `$1`.
+///
+/// Returns an empty string if there are no conditions.
+fn where_clause(conditions: &[(&str, &(dyn ToSql + Sync))]) -> Result<String, Error> {
+    if conditions.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut clause = " WHERE ".to_string();
+    for (i, (column, _)) in conditions.iter().enumerate() {
+        identifier::validate(column, false)?;
+        if i > 0 {
+            clause.push_str(" AND ");
+        }
+        clause.push_str(&format!("{} = ${}", column, i + 1));
+    }
+
+    Ok(clause)
+}
+
+/// Deletes the rows of a table whose columns are equal to the given values.
+///
+/// Builds a `DELETE FROM table WHERE column1 = $1 AND column2 = $2 ...` statement, executes it through the statement
+/// cache and returns the number of rows deleted. `table` may be schema-qualified (`schema.table`). Other predicates,
+/// such as `OR` or comparisons with NULL, must be written in SQL.
+///
+/// Every row of the table is deleted if `conditions` is empty.
+pub async fn delete_where<E>(
+    client: &Client,
+    table: &str,
+    conditions: &[(&str, &(dyn ToSql + Sync))],
+) -> Result<u64, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(table, true)?;
+    let query = format!("DELETE FROM {}{}", table, where_clause(conditions)?);
+
+    let params = conditions.iter().map(|(_, value)| *value as &dyn ToSql);
+    let mut execution = typed_query::start(client.inner(), &query, params).await?;
+    Ok(execution.rows_affected().await?)
+}
+
+/// Selects columns from the rows of a table whose columns are equal to the given values.
+///
+/// Builds a `SELECT column1, column2, ... FROM table WHERE column1 = $1 AND ...` statement, executes it through the
+/// statement cache and decodes every row with `FromRow`. All the columns of the table are selected if `columns` is
+/// empty, and all its rows if `conditions` is empty. `table` may be schema-qualified (`schema.table`).
+pub async fn select_where<T, E>(
+    client: &Client,
+    table: &str,
+    columns: &[&str],
+    conditions: &[(&str, &(dyn ToSql + Sync))],
+) -> Result<Vec<T>, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(table, true)?;
+    for column in columns {
+        identifier::validate(column, false)?;
+    }
+
+    let select_list = if columns.is_empty() {
+        "*".to_string()
+    } else {
+        columns.join(", ")
+    };
+    let query = format!(
+        "SELECT {} FROM {}{}",
+        select_list,
+        table,
+        where_clause(conditions)?
+    );
+
+    let params = conditions.iter().map(|(_, value)| *value as &dyn ToSql);
+    let mut execution = typed_query::start(client.inner(), &query, params).await?;
+    Ok(execution.collect().await?)
+}
+
+/// Executes a `SELECT` query locking the selected rows with `FOR UPDATE`, and decodes every row with `FromRow`.
+///
+/// `query` is a statement and its parameters, as built by `QueryBuilder::build`. The rows are locked until the end of
+/// the current transaction, so this is only useful within a transaction block. If `no_wait` is set, `NOWAIT` is
+/// appended and an error is returned immediately if a row is already locked; `Error::is_lock_not_available` detects
+/// it.
+pub async fn select_for_update<T, E>(
+    client: &Client,
+    query: (String, Vec<BoxedParam>),
+    no_wait: bool,
+) -> Result<Vec<T>, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    let (query, params) = query;
+    let mut query = format!("{} FOR UPDATE", query);
+    if no_wait {
+        query.push_str(" NOWAIT");
+    }
+
+    let params = params.iter().map(|param| &**param as &dyn ToSql);
+    let mut execution = typed_query::start(client.inner(), &query, params).await?;
+    Ok(execution.collect().await?)
+}
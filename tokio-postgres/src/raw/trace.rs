@@ -0,0 +1,241 @@
+use crate::client::InnerClient;
+use crate::codec::FrontendMessage;
+use crate::config::SslMode;
+use crate::connection::{Request, RequestMessages};
+use crate::raw::message::split_messages;
+use crate::Client;
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// A client logging the messages it exchanges with the server, returned by `trace_session`.
+///
+/// The trace client dereferences to a `Client`, so it can be passed to the `raw` functions.
+pub struct TraceClient {
+    client: Client,
+}
+
+impl Deref for TraceClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+/// Traces the messages exchanged with the server through `client`.
+///
+/// The requests made through the returned client are forwarded to `client`, and a line is written to `writer` for
+/// each message sent and received, with a timestamp (in seconds since the Unix epoch), the direction (`>` for sent,
+/// `<` for received), the type of the message and its key fields. Requests are forwarded one at a time, after the
+/// responses of the previous one have been received. The asynchronous messages delivered through the `Connection`,
+/// such as notifications, are not traced.
+///
+/// Like the connection of a client, the requests are forwarded by a future returned alongside the trace client, which
+/// should be spawned onto an executor. The future completes once the trace client and its clones are dropped and the
+/// log of the pending requests is written, returning the writer. The connection of `client` is kept open until then.
+pub fn trace_session<W>(client: &Client, writer: W) -> (TraceClient, impl Future<Output = W>)
+where
+    W: AsyncWrite + Unpin,
+{
+    let (sender, receiver) = mpsc::unbounded();
+    let traced = Client::new(sender, SslMode::Disable, 0, 0);
+
+    (
+        TraceClient { client: traced },
+        trace(receiver, client.inner().clone(), writer),
+    )
+}
+
+async fn trace<W>(
+    mut receiver: mpsc::UnboundedReceiver<Request>,
+    client: Arc<InnerClient>,
+    mut writer: W,
+) -> W
+where
+    W: AsyncWrite + Unpin,
+{
+    while let Some(request) = receiver.next().await {
+        match &request.messages {
+            RequestMessages::Single(FrontendMessage::Raw(bytes)) => {
+                log(
+                    &mut writer,
+                    '>',
+                    describe_messages(bytes, describe_frontend),
+                )
+                .await
+            }
+            RequestMessages::Single(FrontendMessage::CopyData(_)) => {
+                log(&mut writer, '>', vec!["CopyData".to_string()]).await
+            }
+            RequestMessages::CopyIn(_) => {
+                log(&mut writer, '>', vec!["CopyIn stream".to_string()]).await
+            }
+        }
+
+        let (sender, mut responses) = mpsc::channel(1);
+        let mut original = request.sender;
+        if client
+            .forward(Request {
+                messages: request.messages,
+                sender,
+            })
+            .is_err()
+        {
+            break;
+        }
+
+        while let Some(messages) = responses.next().await {
+            log(
+                &mut writer,
+                '<',
+                describe_messages(messages.as_bytes(), describe_backend),
+            )
+            .await;
+            // The responses of some requests, such as keepalives, are not awaited.
+            let _ = original.send(messages).await;
+        }
+    }
+
+    let _ = writer.flush().await;
+    writer
+}
+
+async fn log<W>(writer: &mut W, direction: char, messages: Vec<String>)
+where
+    W: AsyncWrite + Unpin,
+{
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64();
+
+    let mut lines = String::new();
+    for message in messages {
+        lines.push_str(&format!("{:.6} {} {}\n", timestamp, direction, message));
+    }
+    let _ = writer.write_all(lines.as_bytes()).await;
+}
+
+/// Splits a buffer into messages, describing each of them with `describe`.
+fn describe_messages(buf: &[u8], describe: fn(u8, &[u8]) -> String) -> Vec<String> {
+    split_messages(buf)
+        .map(|(tag, body)| describe(tag, body))
+        .collect()
+}
+
+fn describe_frontend(tag: u8, body: &[u8]) -> String {
+    let mut fields = body.split(|b| *b == 0).map(String::from_utf8_lossy);
+    match tag {
+        b'P' => format!(
+            "Parse name={:?} query={:?}",
+            fields.next().unwrap_or_default(),
+            fields.next().unwrap_or_default()
+        ),
+        b'B' => format!(
+            "Bind portal={:?} statement={:?}",
+            fields.next().unwrap_or_default(),
+            fields.next().unwrap_or_default()
+        ),
+        b'E' => format!(
+            "Execute portal={:?} max_rows={}",
+            fields.next().unwrap_or_default(),
+            body.len().checked_sub(4).map_or(0, |i| i32::from_be_bytes([
+                body[i],
+                body[i + 1],
+                body[i + 2],
+                body[i + 3]
+            ]))
+        ),
+        b'D' | b'C' => format!(
+            "{} {} name={:?}",
+            if tag == b'D' { "Describe" } else { "Close" },
+            if body.first() == Some(&b'S') {
+                "statement"
+            } else {
+                "portal"
+            },
+            body.get(1..)
+                .map(|name| String::from_utf8_lossy(name)
+                    .trim_end_matches('\0')
+                    .to_string())
+                .unwrap_or_default()
+        ),
+        b'Q' => format!("Query query={:?}", fields.next().unwrap_or_default()),
+        b'S' => "Sync".to_string(),
+        b'H' => "Flush".to_string(),
+        b'd' => format!("CopyData len={}", body.len()),
+        b'c' => "CopyDone".to_string(),
+        b'f' => format!("CopyFail message={:?}", fields.next().unwrap_or_default()),
+        b'X' => "Terminate".to_string(),
+        _ => format!("Unknown tag={:?} len={}", tag as char, body.len()),
+    }
+}
+
+fn describe_backend(tag: u8, body: &[u8]) -> String {
+    let count = || {
+        body.get(..2)
+            .map(|b| i16::from_be_bytes([b[0], b[1]]))
+            .unwrap_or_default()
+    };
+    let text = || {
+        String::from_utf8_lossy(body)
+            .trim_end_matches('\0')
+            .to_string()
+    };
+
+    match tag {
+        b'1' => "ParseComplete".to_string(),
+        b'2' => "BindComplete".to_string(),
+        b'3' => "CloseComplete".to_string(),
+        b'n' => "NoData".to_string(),
+        b's' => "PortalSuspended".to_string(),
+        b'I' => "EmptyQueryResponse".to_string(),
+        b'T' => format!("RowDescription fields={}", count()),
+        b't' => format!("ParameterDescription params={}", count()),
+        b'D' => format!("DataRow columns={}", count()),
+        b'C' => format!("CommandComplete tag={:?}", text()),
+        b'Z' => format!(
+            "ReadyForQuery status={}",
+            body.first().map_or('?', |b| *b as char)
+        ),
+        b'E' | b'N' => {
+            let mut code = String::new();
+            let mut message = String::new();
+            for field in body.split(|b| *b == 0).filter(|f| !f.is_empty()) {
+                match field[0] {
+                    b'C' => code = String::from_utf8_lossy(&field[1..]).to_string(),
+                    b'M' => message = String::from_utf8_lossy(&field[1..]).to_string(),
+                    _ => {}
+                }
+            }
+            format!(
+                "{} code={} message={:?}",
+                if tag == b'E' {
+                    "ErrorResponse"
+                } else {
+                    "NoticeResponse"
+                },
+                code,
+                message
+            )
+        }
+        b'G' | b'H' => format!(
+            "{} columns={}",
+            if tag == b'G' {
+                "CopyInResponse"
+            } else {
+                "CopyOutResponse"
+            },
+            body.get(1..3)
+                .map_or(0, |b| i16::from_be_bytes([b[0], b[1]]))
+        ),
+        b'd' => format!("CopyData len={}", body.len()),
+        b'c' => "CopyDone".to_string(),
+        _ => format!("Unknown tag={:?} len={}", tag as char, body.len()),
+    }
+}
@@ -0,0 +1,147 @@
+use crate::raw::identifier;
+use crate::{simple_query, Client, Error};
+use log::error;
+
+/// A transaction block started with `begin`.
+///
+/// The transaction must be ended explicitly with `commit` or `rollback`. Unlike `Transaction`, dropping it does not
+/// roll it back: the session is left inside the transaction block and an error is logged.
+pub struct RawTransaction<'a> {
+    client: &'a Client,
+    done: bool,
+}
+
+/// Starts a transaction block with `BEGIN`.
+pub async fn begin<'a, E>(client: &'a Client) -> Result<RawTransaction<'a>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    simple_query::batch_execute(client.inner(), "BEGIN").await?;
+    Ok(RawTransaction {
+        client,
+        done: false,
+    })
+}
+
+impl<'a> RawTransaction<'a> {
+    /// Returns the client the transaction was started on.
+    pub fn client(&self) -> &'a Client {
+        self.client
+    }
+
+    /// Consumes the transaction, committing all changes made within it.
+    pub async fn commit<E>(mut self) -> Result<(), E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.done = true;
+        simple_query::batch_execute(self.client.inner(), "COMMIT").await?;
+        Ok(())
+    }
+
+    /// Consumes the transaction, discarding all changes made within it.
+    pub async fn rollback<E>(mut self) -> Result<(), E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.done = true;
+        simple_query::batch_execute(self.client.inner(), "ROLLBACK").await?;
+        Ok(())
+    }
+}
+
+impl Drop for RawTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            error!("transaction dropped without being committed or rolled back");
+        }
+    }
+}
+
+/// A savepoint inside a transaction block, started with `savepoint`.
+///
+/// Like `RawTransaction`, it must be ended explicitly with `commit` or `rollback`, and an error is logged if it is
+/// dropped instead.
+pub struct NestedTransaction<'a> {
+    client: &'a Client,
+    name: String,
+    done: bool,
+}
+
+async fn start_savepoint<'a>(
+    client: &'a Client,
+    name: &str,
+) -> Result<NestedTransaction<'a>, Error> {
+    identifier::validate(name, false)?;
+    let query = format!("SAVEPOINT {}", name);
+    simple_query::batch_execute(client.inner(), &query).await?;
+    Ok(NestedTransaction {
+        client,
+        name: name.to_string(),
+        done: false,
+    })
+}
+
+/// Creates a savepoint named `name` inside a transaction block.
+///
+/// The name must be a plain identifier.
+pub async fn savepoint<'a, E>(
+    tx: &'a RawTransaction<'_>,
+    name: &str,
+) -> Result<NestedTransaction<'a>, E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    Ok(start_savepoint(tx.client, name).await?)
+}
+
+impl<'a> NestedTransaction<'a> {
+    /// Returns the client the transaction was started on.
+    pub fn client(&self) -> &'a Client {
+        self.client
+    }
+
+    /// Creates a savepoint named `name` nested inside this one.
+    pub async fn savepoint<E>(&self, name: &str) -> Result<NestedTransaction<'_>, E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        Ok(start_savepoint(self.client, name).await?)
+    }
+
+    /// Consumes the savepoint, releasing it and keeping the changes made since it was created.
+    pub async fn commit<E>(mut self) -> Result<(), E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.done = true;
+        let query = format!("RELEASE SAVEPOINT {}", self.name);
+        simple_query::batch_execute(self.client.inner(), &query).await?;
+        Ok(())
+    }
+
+    /// Consumes the savepoint, discarding the changes made since it was created.
+    pub async fn rollback<E>(mut self) -> Result<(), E>
+    where
+        E: std::convert::From<crate::error::Error>,
+    {
+        self.done = true;
+        let query = format!(
+            "ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}",
+            self.name
+        );
+        simple_query::batch_execute(self.client.inner(), &query).await?;
+        Ok(())
+    }
+}
+
+impl Drop for NestedTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            error!(
+                "savepoint `{}` dropped without being committed or rolled back",
+                self.name
+            );
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use crate::client::{InnerClient, Responses};
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::query::extract_row_affected;
+use crate::raw::builder::BoxedParam;
+use crate::raw::decode::FromRow;
+use crate::raw::query::{encode_bind_tosql, Row};
+use crate::raw::simple_query::SimpleColumn;
+use crate::raw::statement::Statement;
+use crate::raw::statement_cache;
+use crate::types::ToSql;
+use crate::{Client, Error};
+use futures_util::{stream, Stream, TryStreamExt};
+use log::debug;
+use postgres_protocol::message::backend::Message;
+use postgres_protocol::message::frontend;
+use std::sync::Arc;
+
+/// The responses to a query, along with what is needed to decode its rows.
+pub(crate) struct Execution {
+    responses: Responses,
+    columns: Arc<[SimpleColumn]>,
+    /// Keeps the statement open even if it is evicted from the cache in the meantime.
+    _statement: Statement,
+}
+
+impl Execution {
+    /// Returns the next row of the result set, or `None` once the query has completed.
+    pub(crate) async fn next_row(&mut self) -> Result<Option<Row>, Error> {
+        loop {
+            match self.responses.next().await? {
+                Message::BindComplete
+                | Message::CommandComplete(_)
+                | Message::EmptyQueryResponse
+                | Message::PortalSuspended => {}
+                Message::DataRow(body) => return Row::new(body).map(Some),
+                Message::ReadyForQuery(_) => return Ok(None),
+                _ => return Err(Error::unexpected_message()),
+            }
+        }
+    }
+
+    /// Decodes all the remaining rows of the result set.
+    pub(crate) async fn collect<T>(&mut self) -> Result<Vec<T>, Error>
+    where
+        T: FromRow,
+    {
+        let mut values = vec![];
+        while let Some(row) = self.next_row().await? {
+            values.push(T::from_row(&row, &self.columns)?);
+        }
+        Ok(values)
+    }
+
+    /// Waits for the query to complete, returning the number of rows it affected.
+    pub(crate) async fn rows_affected(&mut self) -> Result<u64, Error> {
+        let mut rows_affected = 0;
+        loop {
+            match self.responses.next().await? {
+                Message::BindComplete | Message::DataRow(_) | Message::EmptyQueryResponse => {}
+                Message::CommandComplete(body) => rows_affected = extract_row_affected(&body)?,
+                Message::ReadyForQuery(_) => return Ok(rows_affected),
+                _ => return Err(Error::unexpected_message()),
+            }
+        }
+    }
+}
+
+/// Decodes the next row of the result set, handing the execution back for the next call.
+async fn next_value<T>(mut execution: Execution) -> Result<Option<(T, Execution)>, Error>
+where
+    T: FromRow,
+{
+    match execution.next_row().await? {
+        Some(row) => {
+            let value = T::from_row(&row, &execution.columns)?;
+            Ok(Some((value, execution)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Prepares `query` through the connection's statement cache, then binds and executes it with no row limit.
+pub(crate) async fn start<'a, I>(
+    client: &Arc<InnerClient>,
+    query: &str,
+    params: I,
+) -> Result<Execution, Error>
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    start_limited(client, query, params, 0).await
+}
+
+/// Like `start`, but fetches at most `max_rows` rows, or all of them if it is 0.
+pub(crate) async fn start_limited<'a, I>(
+    client: &Arc<InnerClient>,
+    query: &str,
+    params: I,
+    max_rows: i32,
+) -> Result<Execution, Error>
+where
+    I: IntoIterator<Item = &'a dyn ToSql>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let cached = statement_cache::get_or_prepare(client, query).await?;
+
+    debug!("executing statement {}", cached.statement.name());
+    let bytes = client.with_buf(|buf| {
+        encode_bind_tosql(&cached.statement, &cached.param_types, params, "", buf)?;
+        frontend::execute("", max_rows, buf).map_err(Error::encode)?;
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+    let responses = client.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+
+    Ok(Execution {
+        responses,
+        columns: cached.columns,
+        _statement: cached.statement,
+    })
+}
+
+/// Executes a query, returning a stream of its rows decoded into values of type `T`.
+///
+/// The statement is prepared, described, bound, executed and synced without any further setup. It is named
+/// automatically and kept in a per-connection cache of recently used statements, so that running the same query
+/// again skips the preparation round trip. Parameters are serialized with `ToSql` and rows are requested in binary
+/// format. The raw buffer is expected to be empty.
+pub fn stream_query<'a, T, E>(
+    client: &'a Client,
+    query: &'a str,
+    params: &'a [&'a (dyn ToSql + Sync)],
+) -> impl Stream<Item = Result<T, E>> + 'a
+where
+    T: FromRow + 'static,
+    E: std::convert::From<crate::error::Error> + 'a,
+{
+    let params = params.iter().map(|param| *param as &dyn ToSql);
+    stream::once(start(client.inner(), query, params))
+        .map_ok(|execution| stream::try_unfold(execution, next_value::<T>))
+        .try_flatten()
+        .map_err(E::from)
+}
+
+/// Executes a query returning at most one row, decoded into a value of type `T`.
+///
+/// Returns `None` if the query returns no rows, and an error if it returns more than one. The query is executed
+/// through the statement cache like `stream_query`, with a limit of 2 rows on the portal, so that no more rows than
+/// needed to detect the error are fetched.
+pub async fn query_opt<T, E>(
+    client: &Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<Option<T>, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    let params = params.iter().map(|param| *param as &dyn ToSql);
+    let mut execution = start_limited(client.inner(), query, params, 2).await?;
+    let mut values = execution.collect::<T>().await?;
+    if values.len() > 1 {
+        return Err(Error::too_many_rows().into());
+    }
+    Ok(values.pop())
+}
+
+/// Executes a query returning exactly one row, decoded into a value of type `T`.
+///
+/// Unlike `query_opt`, an error is returned if the query returns no rows. The rows are fetched the same way, with a
+/// limit of 2 rows on the portal.
+pub async fn query_one<T, E>(
+    client: &Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+) -> Result<T, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    match query_opt::<T, Error>(client, query, params).await? {
+        Some(value) => Ok(value),
+        None => Err(Error::no_rows().into()),
+    }
+}
+
+/// A page of the rows returned by a query, returned by `query_page`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    items: Vec<T>,
+    total_count: Option<u64>,
+    page: u32,
+    page_size: u32,
+}
+
+impl<T> Page<T> {
+    /// Returns the rows of the page.
+    pub fn items(&self) -> &[T] {
+        &self.items
+    }
+
+    /// Consumes the page, returning its rows.
+    pub fn into_items(self) -> Vec<T> {
+        self.items
+    }
+
+    /// Returns the total number of rows returned by the query, if it was requested.
+    pub fn total_count(&self) -> Option<u64> {
+        self.total_count
+    }
+
+    /// Returns the index of the page, starting at 0.
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// Returns the maximum number of rows of the page.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+}
+
+/// Executes a query, returning the rows of the page with the given index, decoded into values of type `T`.
+///
+/// The query is wrapped as `SELECT * FROM (query) t LIMIT $n OFFSET $m`, the limit and the offset being passed as
+/// additional parameters so that the same statement is used for every page. It should have an `ORDER BY` clause for
+/// the pages to be consistent. If `include_total_count` is set, `SELECT COUNT(*) FROM (query) t` is executed as well,
+/// in the same pipeline.
+///
+/// Both statements are prepared through the statement cache, like `stream_query`.
+pub async fn query_page<T, E>(
+    client: &Client,
+    query: &str,
+    params: &[&(dyn ToSql + Sync)],
+    page: u32,
+    page_size: u32,
+    include_total_count: bool,
+) -> Result<Page<T>, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    let items_query = format!(
+        "SELECT * FROM ({}) t LIMIT ${} OFFSET ${}",
+        query,
+        params.len() + 1,
+        params.len() + 2
+    );
+    let items = statement_cache::get_or_prepare(inner, &items_query).await?;
+    let count = if include_total_count {
+        let count_query = format!("SELECT COUNT(*) FROM ({}) t", query);
+        Some(statement_cache::get_or_prepare(inner, &count_query).await?)
+    } else {
+        None
+    };
+
+    let limit = i64::from(page_size);
+    let offset = i64::from(page) * i64::from(page_size);
+    let mut items_params = params
+        .iter()
+        .map(|param| *param as &dyn ToSql)
+        .collect::<Vec<_>>();
+    items_params.push(&limit);
+    items_params.push(&offset);
+
+    debug!("executing statement {}", items.statement.name());
+    let bytes = inner.with_buf(|buf| {
+        encode_bind_tosql(
+            &items.statement,
+            &items.param_types,
+            items_params.iter().copied(),
+            "",
+            buf,
+        )?;
+        frontend::execute("", 0, buf).map_err(Error::encode)?;
+        if let Some(count) = &count {
+            let params = params.iter().map(|param| *param as &dyn ToSql);
+            encode_bind_tosql(&count.statement, &count.param_types, params, "", buf)?;
+            frontend::execute("", 0, buf).map_err(Error::encode)?;
+        }
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+
+    let mut values = vec![];
+    let mut total_count = None;
+    let mut counting = false;
+    loop {
+        match responses.next().await? {
+            Message::BindComplete | Message::EmptyQueryResponse => {}
+            Message::CommandComplete(_) => counting = true,
+            Message::DataRow(body) => {
+                let row = Row::new(body)?;
+                match &count {
+                    Some(count) if counting => {
+                        let (n,) = <(i64,)>::from_row(&row, &count.columns)?;
+                        total_count = Some(n as u64);
+                    }
+                    _ => values.push(T::from_row(&row, &items.columns)?),
+                }
+            }
+            Message::ReadyForQuery(_) => break,
+            _ => return Err(Error::unexpected_message().into()),
+        }
+    }
+
+    Ok(Page {
+        items: values,
+        total_count,
+        page,
+        page_size,
+    })
+}
+
+/// Executes several queries in a single round trip, returning the rows of each one decoded into values of type `T`.
+///
+/// Each query is prepared through the statement cache, like `stream_query`, then all of them are bound and executed
+/// in a pipeline synced once. The result sets are returned in the order of `queries`, which are typically built with
+/// `QueryBuilder::build`. If a query fails, the following ones are not executed and its error is returned.
+pub async fn batch_select<T, E>(
+    client: &Client,
+    queries: Vec<(String, Vec<BoxedParam>)>,
+) -> Result<Vec<Vec<T>>, E>
+where
+    T: FromRow,
+    E: std::convert::From<crate::error::Error>,
+{
+    let inner = client.inner();
+    let mut statements = Vec::with_capacity(queries.len());
+    for (query, _) in &queries {
+        statements.push(statement_cache::get_or_prepare(inner, query).await?);
+    }
+
+    let bytes = inner.with_buf(|buf| {
+        for (cached, (_, params)) in statements.iter().zip(&queries) {
+            debug!("executing statement {}", cached.statement.name());
+            let params = params.iter().map(|param| &**param as &dyn ToSql);
+            encode_bind_tosql(&cached.statement, &cached.param_types, params, "", buf)?;
+            frontend::execute("", 0, buf).map_err(Error::encode)?;
+        }
+        frontend::sync(buf);
+        Ok::<_, Error>(buf.split().freeze())
+    })?;
+    let mut responses = inner.send(RequestMessages::Single(FrontendMessage::Raw(bytes)))?;
+
+    let mut results = Vec::with_capacity(queries.len());
+    let mut values = vec![];
+    loop {
+        match responses.next().await? {
+            Message::BindComplete => {}
+            Message::CommandComplete(_) | Message::EmptyQueryResponse => {
+                results.push(values);
+                values = vec![];
+            }
+            Message::DataRow(body) => {
+                let cached = statements
+                    .get(results.len())
+                    .ok_or_else(Error::unexpected_message)?;
+                values.push(T::from_row(&Row::new(body)?, &cached.columns)?);
+            }
+            Message::ReadyForQuery(_) => break,
+            _ => return Err(Error::unexpected_message().into()),
+        }
+    }
+
+    if results.len() != queries.len() {
+        return Err(Error::unexpected_message().into());
+    }
+    Ok(results)
+}
@@ -0,0 +1,258 @@
+use crate::raw::identifier;
+use crate::raw::simple_query::internal_simple_query;
+use crate::{Client, Error};
+use postgres_protocol::message::backend::Message;
+
+/// The lock modes of a `LOCK TABLE` command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LockMode {
+    /// `ACCESS SHARE` mode.
+    AccessShare,
+    /// `ROW SHARE` mode.
+    RowShare,
+    /// `ROW EXCLUSIVE` mode.
+    RowExclusive,
+    /// `SHARE UPDATE EXCLUSIVE` mode.
+    ShareUpdateExclusive,
+    /// `SHARE` mode.
+    Share,
+    /// `SHARE ROW EXCLUSIVE` mode.
+    ShareRowExclusive,
+    /// `EXCLUSIVE` mode.
+    Exclusive,
+    /// `ACCESS EXCLUSIVE` mode.
+    AccessExclusive,
+}
+
+impl LockMode {
+    fn as_sql(self) -> &'static str {
+        match self {
+            LockMode::AccessShare => "ACCESS SHARE",
+            LockMode::RowShare => "ROW SHARE",
+            LockMode::RowExclusive => "ROW EXCLUSIVE",
+            LockMode::ShareUpdateExclusive => "SHARE UPDATE EXCLUSIVE",
+            LockMode::Share => "SHARE",
+            LockMode::ShareRowExclusive => "SHARE ROW EXCLUSIVE",
+            LockMode::Exclusive => "EXCLUSIVE",
+            LockMode::AccessExclusive => "ACCESS EXCLUSIVE",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ImportRestriction {
+    LimitTo(Vec<String>),
+    Except(Vec<String>),
+}
+
+/// Options of an `IMPORT FOREIGN SCHEMA` command.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    restriction: Option<ImportRestriction>,
+    options: Vec<(String, String)>,
+}
+
+impl ImportOptions {
+    /// Creates a new set of options, importing all the tables of the remote schema.
+    pub fn new() -> ImportOptions {
+        ImportOptions::default()
+    }
+
+    /// Imports only the given tables.
+    ///
+    /// This replaces any table list set with `except`.
+    #[must_use]
+    pub fn limit_to<I, S>(mut self, tables: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.restriction = Some(ImportRestriction::LimitTo(
+            tables.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Imports all the tables but the given ones.
+    ///
+    /// This replaces any table list set with `limit_to`.
+    #[must_use]
+    pub fn except<I, S>(mut self, tables: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.restriction = Some(ImportRestriction::Except(
+            tables.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Adds an option specific to the foreign data wrapper.
+    #[must_use]
+    pub fn option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.push((key.into(), value.into()));
+        self
+    }
+}
+
+/// Executes a single utility command, checking that the server reported its completion.
+async fn command(client: &Client, query: &str) -> Result<(), Error> {
+    let mut responses = internal_simple_query(client.inner(), query)?;
+    let mut completed = false;
+
+    loop {
+        match responses.next().await? {
+            Message::CommandComplete(_) => completed = true,
+            Message::ReadyForQuery(_) if completed => return Ok(()),
+            _ => return Err(Error::unexpected_message()),
+        }
+    }
+}
+
+/// Refreshes the contents of a materialized view.
+///
+/// `view_name` may be schema-qualified (`schema.view`). If `concurrently` is set, the view is
+/// refreshed without locking out concurrent selects; this requires the view to have a unique index.
+pub async fn refresh_materialized_view<E>(
+    client: &Client,
+    view_name: &str,
+    concurrently: bool,
+) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(view_name, true)?;
+
+    let query = if concurrently {
+        format!("REFRESH MATERIALIZED VIEW CONCURRENTLY {}", view_name)
+    } else {
+        format!("REFRESH MATERIALIZED VIEW {}", view_name)
+    };
+
+    command(client, &query).await?;
+    Ok(())
+}
+
+/// Locks a table in the given mode.
+///
+/// `table` may be schema-qualified (`schema.table`). The lock is held until the end of the current
+/// transaction, so this is only useful within a transaction block. If `no_wait` is set, an error is
+/// returned instead of waiting for conflicting locks to be released.
+pub async fn lock_table<E>(
+    client: &Client,
+    table: &str,
+    mode: LockMode,
+    no_wait: bool,
+) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(table, true)?;
+
+    let mut query = format!("LOCK TABLE {} IN {} MODE", table, mode.as_sql());
+    if no_wait {
+        query.push_str(" NOWAIT");
+    }
+
+    command(client, &query).await?;
+    Ok(())
+}
+
+/// Empties one or more tables.
+///
+/// Table names may be schema-qualified (`schema.table`). If `restart_identity` is set, the
+/// sequences owned by the truncated columns are restarted; if `cascade` is set, tables referencing
+/// the truncated ones through foreign keys are truncated as well.
+pub async fn truncate<E>(
+    client: &Client,
+    tables: &[&str],
+    cascade: bool,
+    restart_identity: bool,
+) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    for table in tables {
+        identifier::validate(table, true)?;
+    }
+
+    let mut query = format!("TRUNCATE {}", tables.join(", "));
+    if restart_identity {
+        query.push_str(" RESTART IDENTITY");
+    }
+    if cascade {
+        query.push_str(" CASCADE");
+    }
+
+    command(client, &query).await?;
+    Ok(())
+}
+
+/// Changes the current user identifier of the session to `role`.
+///
+/// The session user must be a member of `role`. If called within a transaction that is later
+/// rolled back, the change is reverted too.
+pub async fn set_role<E>(client: &Client, role: &str) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(role, false)?;
+
+    command(client, &format!("SET ROLE {}", role)).await?;
+    Ok(())
+}
+
+/// Resets the current user identifier of the session to the session user.
+pub async fn reset_role<E>(client: &Client) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    command(client, "SET ROLE NONE").await?;
+    Ok(())
+}
+
+/// Creates foreign tables in `local_schema` for the tables of `remote_schema` on a foreign server.
+pub async fn import_foreign_schema<E>(
+    client: &Client,
+    remote_schema: &str,
+    local_server: &str,
+    local_schema: &str,
+    options: ImportOptions,
+) -> Result<(), E>
+where
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(remote_schema, false)?;
+    identifier::validate(local_server, false)?;
+    identifier::validate(local_schema, false)?;
+
+    let mut query = format!("IMPORT FOREIGN SCHEMA {}", remote_schema);
+    let (clause, tables) = match &options.restriction {
+        Some(ImportRestriction::LimitTo(tables)) => (" LIMIT TO", Some(tables)),
+        Some(ImportRestriction::Except(tables)) => (" EXCEPT", Some(tables)),
+        None => ("", None),
+    };
+    if let Some(tables) = tables {
+        for table in tables {
+            identifier::validate(table, false)?;
+        }
+        query.push_str(&format!("{} ({})", clause, tables.join(", ")));
+    }
+    query.push_str(&format!(
+        " FROM SERVER {} INTO {}",
+        local_server, local_schema
+    ));
+
+    if !options.options.is_empty() {
+        let mut fdw_options = vec![];
+        for (key, value) in &options.options {
+            identifier::validate(key, false)?;
+            fdw_options.push(format!("{} {}", key, identifier::literal(value)));
+        }
+        query.push_str(&format!(" OPTIONS ({})", fdw_options.join(", ")));
+    }
+
+    command(client, &query).await?;
+    Ok(())
+}
@@ -0,0 +1,180 @@
+use crate::client::InnerClient;
+use crate::codec::FrontendMessage;
+use crate::connection::RequestMessages;
+use crate::raw::identifier;
+use crate::simple_query::batch_execute;
+use crate::{AsyncMessage, Client};
+use bytes::BytesMut;
+use futures_util::{ready, Stream};
+use pin_project_lite::pin_project;
+use postgres_protocol::message::frontend;
+use serde_json_1::Value;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+
+const WATCH_TABLE_FUNCTION: &str = "\
+CREATE OR REPLACE FUNCTION public.watch_table_notify() RETURNS trigger
+LANGUAGE plpgsql AS $fn$
+DECLARE
+    r record;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        r := OLD;
+    ELSE
+        r := NEW;
+    END IF;
+    PERFORM pg_notify(TG_ARGV[0], json_build_object('op', TG_OP, 'id', to_json(r) -> TG_ARGV[1])::text);
+    RETURN NULL;
+END
+$fn$";
+
+/// The kind of change made to a row of a watched table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// The row has been inserted.
+    Insert,
+    /// The row has been updated.
+    Update,
+    /// The row has been deleted.
+    Delete,
+}
+
+/// A change to a row of a table watched with `watch_table`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableChange {
+    operation: Operation,
+    pk: Value,
+}
+
+impl TableChange {
+    fn parse(payload: &str) -> Option<TableChange> {
+        let mut payload: Value = serde_json_1::from_str(payload).ok()?;
+        let operation = match payload.get("op")?.as_str()? {
+            "INSERT" => Operation::Insert,
+            "UPDATE" => Operation::Update,
+            "DELETE" => Operation::Delete,
+            _ => return None,
+        };
+        let pk = payload.get_mut("id")?.take();
+
+        Some(TableChange { operation, pk })
+    }
+
+    /// Returns the kind of change.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Returns the primary key of the changed row, as serialized by `to_json`.
+    ///
+    /// For updates, this is the primary key after the update.
+    pub fn pk(&self) -> &Value {
+        &self.pk
+    }
+}
+
+/// Removes the trigger and stops listening when the watch is dropped.
+struct WatchGuard {
+    client: Weak<InnerClient>,
+    cleanup: String,
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        if let Some(client) = self.client.upgrade() {
+            let mut buf = BytesMut::new();
+            if frontend::query(&self.cleanup, &mut buf).is_ok() {
+                let _ = client.send(RequestMessages::Single(FrontendMessage::Raw(buf.freeze())));
+            }
+        }
+    }
+}
+
+pin_project! {
+    /// A stream of the changes to a table, returned by `watch_table`.
+    pub struct TableWatch<S> {
+        #[pin]
+        messages: S,
+        channel: String,
+        guard: WatchGuard,
+    }
+}
+
+impl<S> Stream for TableWatch<S>
+where
+    S: Stream<Item = AsyncMessage>,
+{
+    type Item = TableChange;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<TableChange>> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.messages.as_mut().poll_next(cx)) {
+                Some(AsyncMessage::Notification(n)) if n.channel() == this.channel.as_str() => {
+                    if let Some(change) = TableChange::parse(n.payload()) {
+                        return Poll::Ready(Some(change));
+                    }
+                }
+                Some(_) => {}
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+/// Watches the rows inserted, updated or deleted in a table.
+///
+/// A row-level trigger is created on `table`, notifying the `watch_<table>` channel with the operation and the value
+/// of `pk_column` of every changed row, and the client starts listening on the channel. The trigger function,
+/// `public.watch_table_notify`, is created or replaced as needed. As `table` and `pk_column` are not quoted, they are
+/// folded to lower case, and so is the name of the channel.
+///
+/// Notifications are delivered through the `Connection` rather than the `Client`, so the asynchronous messages polled
+/// from the connection must be passed as `messages`. The returned stream yields the changes among them, ignoring any
+/// other message. When the stream is dropped, the trigger is removed and the client stops listening on the channel.
+///
+/// Requires the `with-serde_json-1` Cargo feature.
+pub async fn watch_table<S, E>(
+    client: &Client,
+    table: &str,
+    pk_column: &str,
+    messages: S,
+) -> Result<TableWatch<S>, E>
+where
+    S: Stream<Item = AsyncMessage>,
+    E: std::convert::From<crate::error::Error>,
+{
+    identifier::validate(table, true)?;
+    identifier::validate(pk_column, false)?;
+
+    // The trigger passes the channel and the column name as string literals, which are not folded like identifiers.
+    let channel = format!("watch_{}", table.replace('.', "_")).to_ascii_lowercase();
+    let pk_column = pk_column.to_ascii_lowercase();
+    identifier::validate_channel(&channel)?;
+    let trigger = format!("{}_{}", channel, client.backend_pid());
+
+    batch_execute(client.inner(), WATCH_TABLE_FUNCTION).await?;
+    batch_execute(
+        client.inner(),
+        &format!(
+            "CREATE TRIGGER {} AFTER INSERT OR UPDATE OR DELETE ON {} \
+             FOR EACH ROW EXECUTE FUNCTION public.watch_table_notify('{}', '{}'); \
+             LISTEN {}",
+            trigger, table, channel, pk_column, channel
+        ),
+    )
+    .await?;
+
+    Ok(TableWatch {
+        messages,
+        guard: WatchGuard {
+            client: Arc::downgrade(client.inner()),
+            cleanup: format!(
+                "DROP TRIGGER IF EXISTS {} ON {}; UNLISTEN {}",
+                trigger, table, channel
+            ),
+        },
+        channel,
+    })
+}
@@ -1,8 +1,8 @@
 use crate::client::{InnerClient, Responses};
 use crate::codec::FrontendMessage;
 use crate::connection::RequestMessages;
-use crate::raw::simple_query::{encode, internal_simple_query};
 use crate::query::extract_row_affected;
+use crate::raw::simple_query::{encode, internal_simple_query};
 use crate::{Error, SimpleQueryMessage, SimpleQueryRow};
 use fallible_iterator::FallibleIterator;
 use futures_util::{ready, Stream};
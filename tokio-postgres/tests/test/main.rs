@@ -8,15 +8,19 @@ use futures_util::{
 use pin_project_lite::pin_project;
 use std::fmt::Write;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time;
-use tokio_postgres::error::SqlState;
+use tokio_postgres::config::ReplicationMode;
+use tokio_postgres::error::{ErrorPosition, SqlState};
 use tokio_postgres::tls::{NoTls, NoTlsStream};
 use tokio_postgres::types::{Kind, Type};
 use tokio_postgres::{
-    AsyncMessage, Client, Config, Connection, Error, IsolationLevel, SimpleQueryMessage,
+    AsyncMessage, Client, Config, Connection, ConnectionLifecycle, DisconnectReason, Error,
+    IsolationLevel, PasswordProvider, SimpleQueryMessage,
 };
 
 mod binary_copy;
@@ -127,6 +131,33 @@ async fn md5_password_ok() {
     connect("user=md5_user password=password dbname=postgres").await;
 }
 
+#[tokio::test]
+async fn md5_password_provider() {
+    struct Provider(std::sync::Mutex<Vec<String>>);
+
+    impl PasswordProvider for Provider {
+        fn get_password(
+            &self,
+            username: &str,
+            database: &str,
+        ) -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send>> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}@{}", username, database));
+            Box::pin(future::ready(Ok("password".to_string())))
+        }
+    }
+
+    let provider = Arc::new(Provider(Default::default()));
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=md5_user dbname=postgres".parse::<Config>().unwrap();
+    config.password_provider(provider.clone());
+    let (_client, _connection) = config.connect_raw(socket, NoTls).await.unwrap();
+
+    assert_eq!(*provider.0.lock().unwrap(), ["md5_user@postgres"]);
+}
+
 #[tokio::test]
 async fn scram_password_missing() {
     connect_raw("user=scram_user dbname=postgres")
@@ -165,6 +196,83 @@ async fn pipelined_prepare() {
     assert_eq!(statement2.columns()[0].type_(), &Type::INT8);
 }
 
+#[tokio::test]
+async fn pending_request_count() {
+    let client = connect("user=postgres").await;
+    assert_eq!(client.pending_request_count(), 0);
+
+    let first = client
+        .query_raw("SELECT 1", std::iter::empty::<i32>())
+        .await
+        .unwrap();
+    let second = client
+        .query_raw("SELECT 2", std::iter::empty::<i32>())
+        .await
+        .unwrap();
+    assert_eq!(client.pending_request_count(), 2);
+
+    let rows = first.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(client.pending_request_count(), 1);
+
+    drop(second);
+    assert_eq!(client.pending_request_count(), 0);
+}
+
+#[tokio::test]
+async fn connection_id() {
+    let first = connect("user=postgres").await;
+    let second = connect("user=postgres").await;
+
+    assert_ne!(first.connection_id(), 0);
+    assert!(second.connection_id() > first.connection_id());
+}
+
+/// Answers the startup message with a `NegotiateProtocolVersion` message rejecting `_pq_.unknown`.
+async fn negotiating_server(mut stream: tokio::io::DuplexStream) -> tokio::io::DuplexStream {
+    let mut len = [0; 4];
+    stream.read_exact(&mut len).await.unwrap();
+    let mut startup = vec![0; u32::from_be_bytes(len) as usize - 4];
+    stream.read_exact(&mut startup).await.unwrap();
+
+    let mut buf = vec![];
+    buf.extend_from_slice(b"v\0\0\0\x19\0\0\0\0\0\0\0\x01_pq_.unknown\0");
+    buf.extend_from_slice(b"R\0\0\0\x08\0\0\0\0");
+    buf.extend_from_slice(b"K\0\0\0\x0c\0\0\0\x01\0\0\0\x02");
+    buf.extend_from_slice(b"Z\0\0\0\x05I");
+    stream.write_all(&buf).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn negotiate_protocol_version() {
+    let (client_stream, server_stream) = tokio::io::duplex(1024);
+
+    let config = "user=postgres sslmode=disable".parse::<Config>().unwrap();
+    let (result, _server_stream) = join!(
+        config.connect_raw(client_stream, NoTls),
+        negotiating_server(server_stream)
+    );
+    let (client, _connection) = result.unwrap();
+
+    assert_eq!(client.negotiated_protocol_options(), ["_pq_.unknown"]);
+}
+
+#[tokio::test]
+async fn negotiate_protocol_version_rejected() {
+    let (client_stream, server_stream) = tokio::io::duplex(1024);
+
+    let mut config = "user=postgres sslmode=disable".parse::<Config>().unwrap();
+    config.reject_unsupported_protocol_options(true);
+    let (result, _server_stream) = join!(
+        config.connect_raw(client_stream, NoTls),
+        negotiating_server(server_stream)
+    );
+
+    let error = result.err().unwrap();
+    assert!(error.to_string().contains("_pq_.unknown"));
+}
+
 #[tokio::test]
 async fn insert_select() {
     let client = connect("user=postgres").await;
@@ -665,6 +773,32 @@ async fn copy_in_error() {
     assert_eq!(rows.len(), 0);
 }
 
+#[tokio::test]
+async fn copy_in_abort() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (
+                id INTEGER,
+                name TEXT
+            )",
+        )
+        .await
+        .unwrap();
+
+    let sink = client.copy_in("COPY foo FROM STDIN").await.unwrap();
+    pin_mut!(sink);
+    sink.feed(Bytes::from_static(b"1\tsteven\n")).await.unwrap();
+    sink.as_mut().abort::<Error>("invalid input").await.unwrap();
+
+    let rows = client
+        .query("SELECT id, name FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 0);
+}
+
 #[tokio::test]
 async fn copy_out() {
     let client = connect("user=postgres").await;
@@ -831,6 +965,118 @@ async fn disable_channel_binding() {
     connect("user=postgres channel_binding=disable").await;
 }
 
+#[tokio::test]
+async fn replication_mode() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres dbname=postgres".parse::<Config>().unwrap();
+    config
+        .replication_mode(ReplicationMode::Database)
+        .statement_timeout(30_000);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let messages = client.simple_query("IDENTIFY_SYSTEM").await.unwrap();
+    match &messages[0] {
+        SimpleQueryMessage::Row(row) => assert_eq!(row.get("dbname"), Some("postgres")),
+        _ => panic!("unexpected message"),
+    }
+}
+
+#[tokio::test]
+async fn lifecycle() {
+    #[derive(Default)]
+    struct Recorder(std::sync::Mutex<Vec<String>>);
+
+    impl ConnectionLifecycle for Recorder {
+        fn on_connect(&self, client: &Client) {
+            let event = format!("connect {}", client.connection_id());
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_disconnect(&self, _: &Client, reason: DisconnectReason) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("disconnect {:?}", reason));
+        }
+
+        fn on_query_start(&self, _: &Client, query: &str) {
+            self.0.lock().unwrap().push(format!("start {}", query));
+        }
+
+        fn on_query_end(&self, _: &Client, _: Duration, result: &Result<(), Error>) {
+            let event = format!("end {}", result.is_ok());
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    let recorder = Arc::new(Recorder::default());
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.lifecycle(recorder.clone());
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+    let connection_id = client.connection_id();
+
+    client.batch_execute("SELECT 1").await.unwrap();
+    client
+        .simple_query("SELECT * FROM t_lifecycle_missing")
+        .await
+        .unwrap_err();
+    client.query("SELECT 1", &[]).await.unwrap();
+    drop(client);
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![
+            format!("connect {}", connection_id),
+            "start SELECT 1".to_string(),
+            "end true".to_string(),
+            "start SELECT * FROM t_lifecycle_missing".to_string(),
+            "end false".to_string(),
+            "disconnect ClientDropped".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn session_timeouts() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config
+        .idle_in_transaction_session_timeout(60_000)
+        .statement_timeout(30_000)
+        .lock_timeout(1_500);
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let rows = client
+        .query(
+            "SELECT current_setting('idle_in_transaction_session_timeout'), \
+             current_setting('statement_timeout'), current_setting('lock_timeout')",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(rows[0].get::<_, &str>(0), "1min");
+    assert_eq!(rows[0].get::<_, &str>(1), "30s");
+    assert_eq!(rows[0].get::<_, &str>(2), "1500ms");
+}
+
+#[tokio::test]
+async fn keepalive_interval() {
+    let socket = TcpStream::connect("127.0.0.1:5433").await.unwrap();
+    let mut config = "user=postgres".parse::<Config>().unwrap();
+    config.keepalive_interval(Duration::from_millis(10));
+    let (client, connection) = config.connect_raw(socket, NoTls).await.unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    time::sleep(Duration::from_millis(50)).await;
+
+    let rows = client.query("SELECT 1", &[]).await.unwrap();
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
 #[tokio::test]
 async fn check_send() {
     fn is_send<T: Send>(_: &T) {}
@@ -954,3 +1200,22 @@ async fn deferred_constraint() {
         .await
         .unwrap_err();
 }
+
+#[tokio::test]
+async fn error_position() {
+    let client = connect("user=postgres").await;
+
+    let query = "SELECT 'é', 1 1";
+    let error = client.batch_execute(query).await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::SYNTAX_ERROR));
+    assert_eq!(error.position(), Some(&ErrorPosition::Original(15)));
+    assert_eq!(
+        error.annotated_query(query).as_deref(),
+        Some("SELECT 'é', 1 ^1")
+    );
+    assert_eq!(error.annotated_query(""), None);
+
+    let error = client.batch_execute("SELECT 1 / 0").await.unwrap_err();
+    assert_eq!(error.position(), None);
+    assert_eq!(error.annotated_query("SELECT 1 / 0"), None);
+}
@@ -1,5 +1,5 @@
 use std::time::Duration;
-use tokio_postgres::config::{Config, TargetSessionAttrs};
+use tokio_postgres::config::{Config, ReplicationMode, TargetSessionAttrs};
 
 fn check(s: &str, config: &Config) {
     assert_eq!(s.parse::<Config>().expect(s), *config, "`{}`", s);
@@ -44,6 +44,20 @@ fn settings() {
     );
 }
 
+#[test]
+fn replication() {
+    check(
+        "replication=database",
+        Config::new().replication_mode(ReplicationMode::Database),
+    );
+    check(
+        "replication=true",
+        Config::new().replication_mode(ReplicationMode::Physical),
+    );
+    check("replication=database replication=off", &Config::new());
+    assert!("replication=logical".parse::<Config>().is_err());
+}
+
 #[test]
 fn keepalive_settings() {
     check(
@@ -1,13 +1,38 @@
-use crate::connect;
+use crate::{connect, connect_raw};
 use bytes::BytesMut;
-use futures::TryStreamExt;
+use fallible_iterator::FallibleIterator;
+use futures_channel::mpsc;
+use futures_util::{future, pin_mut, stream, FutureExt, StreamExt, TryStreamExt};
 use postgres_protocol::message::backend::Message;
+use std::collections::HashMap;
 use std::convert::TryInto;
+use std::io::SeekFrom;
+use std::time::Duration;
+use tokio_postgres::error::SqlState;
 use tokio_postgres::raw::{
-    bind, execute, prepare, simple_query, sync, Row, SimpleColumn, SimpleQueryRow,
+    advisory_xact_lock, advisory_xact_lock_pair, base_backup, batch_select, begin, bind,
+    bind_with_generator, call_procedure, column_exists, copy_in_csv, copy_in_rows,
+    copy_in_with_transform, copy_out_csv, current_schema, current_schemas, declare_cursor,
+    decode_array, decode_range, decode_recording, delete_where, describe, describe_many,
+    encode_array, encode_composite, encode_range, encode_recording, execute, execute_function,
+    execute_if_not_exists, flush_raw_buf, function_exists, get_parameter_types, identify_system,
+    import_foreign_schema, index_exists, insert_many, invalidate_on_schema_change, keepalive,
+    lo_create, lo_unlink, lock_table, pg_blocking_pids, pg_cancel_backend, pg_dump_query,
+    pg_notify, pg_read_binary_file, pg_stat_activity, pg_terminate_backend, prepare,
+    prepare_and_describe, prepare_with_guard, query_one, query_opt, query_page, raw_buf_is_empty,
+    refresh_materialized_view, reset_role, row_count, savepoint, select_for_update, select_where,
+    sequence_currval, sequence_lastval, sequence_nextval, sequence_setval, set_role,
+    set_transaction_isolation, simple_query, stream_query, subscribe_schema_changes, sync,
+    sync_with_timeout, table_exists, timeline_history, trace_session, transaction_isolation,
+    truncate, try_advisory_xact_lock, try_advisory_xact_lock_pair, upsert_many, xact_is_read_only,
+    AtomicPortalNameGenerator, BaseBackupOptions, CsvOptions, DescribeTarget, FromRow, GenericRow,
+    ImportOptions, InsertBuilder, LargeObject, LargeObjectMode, LockMode, Order,
+    OwnedSimpleQueryRow, PgError, PgRange, PipelineBuilder, PortalNameGenerator, QueryBuilder,
+    RangeFlags, RecordedExchange, ReplayClient, Row, RowDecoder, ScramClient, ServerMessage,
+    SharedStatementPool, SimpleColumn, SimpleQueryRow, TypedPortal, UpdateBuilder,
 };
-use tokio_postgres::types::Type;
-use tokio_postgres::Error;
+use tokio_postgres::types::{ToSql, Type};
+use tokio_postgres::{AsyncMessage, Error, IsolationLevel, NoTls};
 
 #[cfg(feature = "raw")]
 #[tokio::test]
@@ -358,3 +383,3642 @@ async fn query_execute_with_data_result_text_format() {
         _ => panic!("unexpected message"),
     }
 }
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_refresh_materialized_view() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "BEGIN;
+            CREATE TABLE refresh_foo (id INT PRIMARY KEY);
+            CREATE MATERIALIZED VIEW refresh_foo_view AS SELECT id FROM refresh_foo;
+            CREATE UNIQUE INDEX ON refresh_foo_view (id);
+            INSERT INTO refresh_foo (id) VALUES (1), (2);",
+        )
+        .await
+        .unwrap();
+
+    refresh_materialized_view::<Error>(&client, "public.refresh_foo_view", false)
+        .await
+        .unwrap();
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM public.refresh_foo_view", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 2);
+
+    client
+        .batch_execute("INSERT INTO refresh_foo (id) VALUES (3)")
+        .await
+        .unwrap();
+    refresh_materialized_view::<Error>(&client, "public.refresh_foo_view", true)
+        .await
+        .unwrap();
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM public.refresh_foo_view", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 3);
+
+    let err = refresh_materialized_view::<Error>(
+        &client,
+        "refresh_foo_view; DROP TABLE refresh_foo",
+        false,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+
+    client.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_exists() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "BEGIN;
+            CREATE TABLE exists_foo (id INT PRIMARY KEY, name TEXT);
+            CREATE INDEX exists_foo_name ON exists_foo (name);
+            CREATE FUNCTION exists_fn() RETURNS INT AS 'SELECT 1' LANGUAGE SQL;",
+        )
+        .await
+        .unwrap();
+
+    assert!(table_exists::<Error>(&client, "public", "exists_foo")
+        .await
+        .unwrap());
+    assert!(!table_exists::<Error>(&client, "public", "exists_bar")
+        .await
+        .unwrap());
+    assert!(
+        column_exists::<Error>(&client, "public", "exists_foo", "name")
+            .await
+            .unwrap()
+    );
+    assert!(
+        !column_exists::<Error>(&client, "public", "exists_foo", "age")
+            .await
+            .unwrap()
+    );
+    assert!(index_exists::<Error>(&client, "public", "exists_foo_name")
+        .await
+        .unwrap());
+    assert!(!index_exists::<Error>(&client, "public", "exists_foo_age")
+        .await
+        .unwrap());
+    assert!(function_exists::<Error>(&client, "public", "exists_fn")
+        .await
+        .unwrap());
+    assert!(!function_exists::<Error>(&client, "public", "exists_nofn")
+        .await
+        .unwrap());
+
+    client.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_execute_if_not_exists() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS _migrations;
+            CREATE TEMPORARY TABLE migrated (id INT)",
+        )
+        .await
+        .unwrap();
+
+    let applied = execute_if_not_exists::<Error>(
+        &client,
+        "0001_insert",
+        "INSERT INTO migrated (id) VALUES (1)",
+    )
+    .await
+    .unwrap();
+    assert!(applied);
+
+    let applied = execute_if_not_exists::<Error>(
+        &client,
+        "0001_insert",
+        "INSERT INTO migrated (id) VALUES (1)",
+    )
+    .await
+    .unwrap();
+    assert!(!applied);
+
+    let err = execute_if_not_exists::<Error>(&client, "0002_broken", "INSERT INTO nope VALUES (1)")
+        .await
+        .unwrap_err();
+    assert!(err.as_db_error().is_some());
+
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM migrated", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 1);
+
+    let ids = client
+        .query("SELECT id FROM _migrations ORDER BY id", &[])
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.get::<_, String>(0))
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec!["0001_insert".to_string()]);
+
+    client
+        .batch_execute("DROP TABLE _migrations")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_row_count() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT);
+            INSERT INTO foo (name) VALUES ('steven'), ('joe'), ('bob');",
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        row_count::<Error>(&client, "pg_temp", "foo").await.unwrap(),
+        3
+    );
+
+    let err = row_count::<Error>(&client, "pg_temp", "foo WHERE false")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+}
+
+#[cfg(all(feature = "raw", feature = "with-chrono-0_4"))]
+#[tokio::test]
+async fn t_current_timestamp() {
+    use chrono_04::{DateTime, Utc};
+    use tokio_postgres::raw::current_timestamp;
+
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("BEGIN; SET LOCAL TimeZone TO 'Asia/Kolkata'")
+        .await
+        .unwrap();
+
+    let timestamp = current_timestamp::<Error>(&client).await.unwrap();
+    let expected: DateTime<Utc> = client.query_one("SELECT NOW()", &[]).await.unwrap().get(0);
+    assert_eq!(timestamp, expected);
+
+    client.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_advisory_xact_lock() {
+    let client = connect("user=postgres").await;
+    let other = connect("user=postgres").await;
+
+    client.batch_execute("BEGIN").await.unwrap();
+    advisory_xact_lock::<Error>(&client, 382).await.unwrap();
+    advisory_xact_lock_pair::<Error>(&client, 382, 1)
+        .await
+        .unwrap();
+
+    other.batch_execute("BEGIN").await.unwrap();
+    assert!(!try_advisory_xact_lock::<Error>(&other, 382).await.unwrap());
+    assert!(!try_advisory_xact_lock_pair::<Error>(&other, 382, 1)
+        .await
+        .unwrap());
+    assert!(try_advisory_xact_lock_pair::<Error>(&other, 382, 2)
+        .await
+        .unwrap());
+    other.batch_execute("ROLLBACK").await.unwrap();
+
+    client.batch_execute("COMMIT").await.unwrap();
+
+    other.batch_execute("BEGIN").await.unwrap();
+    assert!(try_advisory_xact_lock::<Error>(&other, 382).await.unwrap());
+    other.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_lock_table() {
+    let client = connect("user=postgres").await;
+    let other = connect("user=postgres").await;
+
+    client
+        .batch_execute("DROP TABLE IF EXISTS lock_foo; CREATE TABLE lock_foo (id INT);")
+        .await
+        .unwrap();
+
+    client.batch_execute("BEGIN").await.unwrap();
+    lock_table::<Error>(&client, "public.lock_foo", LockMode::AccessExclusive, false)
+        .await
+        .unwrap();
+
+    other.batch_execute("BEGIN").await.unwrap();
+    let err = lock_table::<Error>(&other, "lock_foo", LockMode::AccessShare, true)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::LOCK_NOT_AVAILABLE));
+    other.batch_execute("ROLLBACK").await.unwrap();
+
+    client.batch_execute("COMMIT").await.unwrap();
+
+    other.batch_execute("BEGIN").await.unwrap();
+    lock_table::<Error>(&other, "lock_foo", LockMode::ShareRowExclusive, true)
+        .await
+        .unwrap();
+    other.batch_execute("ROLLBACK").await.unwrap();
+
+    let err = lock_table::<Error>(&client, "lock_foo IN SHARE MODE;", LockMode::Share, false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+
+    client.batch_execute("DROP TABLE lock_foo").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_truncate() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id SERIAL PRIMARY KEY);
+            CREATE TEMPORARY TABLE bar (id SERIAL, foo_id INT REFERENCES foo (id));
+            INSERT INTO foo DEFAULT VALUES;
+            INSERT INTO bar (foo_id) VALUES (1);",
+        )
+        .await
+        .unwrap();
+
+    let err = truncate::<Error>(&client, &["foo"], false, false)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::FEATURE_NOT_SUPPORTED));
+
+    truncate::<Error>(&client, &["pg_temp.foo"], true, true)
+        .await
+        .unwrap();
+    assert_eq!(
+        row_count::<Error>(&client, "pg_temp", "bar").await.unwrap(),
+        0
+    );
+
+    client
+        .batch_execute("INSERT INTO foo DEFAULT VALUES")
+        .await
+        .unwrap();
+    let id: i32 = client
+        .query_one("SELECT id FROM foo", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(id, 1);
+
+    truncate::<Error>(&client, &["foo", "bar"], false, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        row_count::<Error>(&client, "pg_temp", "foo").await.unwrap(),
+        0
+    );
+
+    let err = truncate::<Error>(&client, &["foo", "bar, baz"], false, false)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_set_role() {
+    let client = connect("user=postgres").await;
+
+    set_role::<Error>(&client, "pass_user").await.unwrap();
+    let user: String = client
+        .query_one("SELECT current_user::TEXT", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(user, "pass_user");
+
+    reset_role::<Error>(&client).await.unwrap();
+    let user: String = client
+        .query_one("SELECT current_user::TEXT", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(user, "postgres");
+
+    let err = set_role::<Error>(&client, "pass_user; RESET ALL")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_prepare_and_describe() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT)")
+        .await
+        .unwrap();
+
+    let (select, description) = prepare_and_describe::<Error>(
+        &client,
+        "SELECT id, name FROM foo WHERE id = $1 AND name = $2",
+        "describe_select",
+        &[Type::INT8.oid()],
+    )
+    .await
+    .unwrap();
+    assert_eq!(select.name(), "describe_select");
+    assert_eq!(select.param_types(), &[Type::INT8.oid(), Type::TEXT.oid()]);
+    assert_eq!(description.params(), &[Type::INT8.oid(), Type::TEXT.oid()]);
+    assert_eq!(description.columns().len(), 2);
+    assert_eq!(description.columns()[0].name(), "id");
+    assert_eq!(description.columns()[0].type_(), &Some(Type::INT4));
+    assert_eq!(description.columns()[1].name(), "name");
+    assert_eq!(description.columns()[1].type_(), &Some(Type::TEXT));
+
+    let (_, description) = prepare_and_describe::<Error>(
+        &client,
+        "INSERT INTO foo (name) VALUES ($1)",
+        "describe_insert",
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(description.params(), &[Type::TEXT.oid()]);
+    assert!(description.columns().is_empty());
+
+    let err = prepare_and_describe::<Error>(&client, "SELECT * FROM bar", "", &[])
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_TABLE));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_copy_in_csv() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT, note TEXT)")
+        .await
+        .unwrap();
+
+    let data: &[u8] = b"name;id\n'steven; jr';1\nNULL;2\n";
+    let rows = copy_in_csv::<_, Error>(
+        &client,
+        "foo",
+        &["name", "id"],
+        CsvOptions::new()
+            .delimiter(';')
+            .quote('\'')
+            .null("NULL")
+            .header(true),
+        data,
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, 2);
+
+    let rows = client
+        .query("SELECT id, name FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[0].get::<_, Option<&str>>(1), Some("steven; jr"));
+    assert_eq!(rows[1].get::<_, i32>(0), 2);
+    assert_eq!(rows[1].get::<_, Option<&str>>(1), None);
+
+    let data: &[u8] = b"3,joe,\"a \"\"quoted\"\" note\"\n";
+    let rows = copy_in_csv::<_, Error>(&client, "foo", &[], CsvOptions::new(), data)
+        .await
+        .unwrap();
+    assert_eq!(rows, 1);
+    let note: String = client
+        .query_one("SELECT note FROM foo WHERE id = 3", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(note, "a \"quoted\" note");
+
+    let data: &[u8] = b"";
+    let err = copy_in_csv::<_, Error>(&client, "foo", &["id)"], CsvOptions::new(), data)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_copy_out_csv() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT);
+            INSERT INTO foo (name) VALUES ('steven'), ('joe; jr'), (NULL);",
+        )
+        .await
+        .unwrap();
+
+    let data = copy_out_csv::<Error>(
+        &client,
+        "SELECT id, name FROM foo ORDER BY id",
+        CsvOptions::new().delimiter(';').null("NULL").header(true),
+    )
+    .await
+    .unwrap()
+    .try_fold(BytesMut::new(), |mut buf, chunk| async move {
+        buf.extend_from_slice(&chunk);
+        Ok(buf)
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(&data[..], b"id;name\n1;steven\n2;\"joe; jr\"\n3;NULL\n");
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_notify() {
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream =
+        stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!("{}", e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    client.batch_execute("LISTEN test_pg_notify").await.unwrap();
+    pg_notify::<Error>(&client, "test_pg_notify", "it's 'quoted'")
+        .await
+        .unwrap();
+
+    let err = pg_notify::<Error>(&client, "test_pg_notify, 'oops'", "")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("invalid identifier"));
+    let err = pg_notify::<Error>(&client, &"c".repeat(64), "")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("too long"));
+
+    drop(client);
+
+    let notifications = rx
+        .filter_map(|m| match m {
+            AsyncMessage::Notification(n) => future::ready(Some(n)),
+            _ => future::ready(None),
+        })
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(notifications.len(), 1);
+    assert_eq!(notifications[0].channel(), "test_pg_notify");
+    assert_eq!(notifications[0].payload(), "it's 'quoted'");
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_subscribe_schema_changes() {
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream =
+        stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!("{}", e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    let events = subscribe_schema_changes::<_, Error>(&client, rx)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS public.schema_changes_foo;
+             CREATE TABLE public.schema_changes_foo (id INT);
+             DROP TABLE public.schema_changes_foo;
+             DROP EVENT TRIGGER schema_changes_notify;
+             DROP FUNCTION public.schema_changes_notify();",
+        )
+        .await
+        .unwrap();
+    drop(client);
+
+    let events = events
+        .filter(|e| future::ready(e.object_identity() == "public.schema_changes_foo"))
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].command_tag(), "CREATE TABLE");
+    assert_eq!(events[0].object_type(), "table");
+    assert_eq!(events[0].schema_name(), Some("public"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_large_object() {
+    let client = connect("user=postgres").await;
+
+    client.batch_execute("BEGIN").await.unwrap();
+    let oid: u32 = client
+        .query_one("SELECT lo_create(0)", &[])
+        .await
+        .unwrap()
+        .get(0);
+
+    let object = LargeObject::open::<Error>(&client, oid, LargeObjectMode::ReadWrite)
+        .await
+        .unwrap();
+    assert_eq!(object.oid(), oid);
+    assert_eq!(object.write::<Error>(b"hello world").await.unwrap(), 11);
+    assert_eq!(object.seek::<Error>(SeekFrom::Start(6)).await.unwrap(), 6);
+    assert_eq!(&object.read::<Error>(5).await.unwrap()[..], b"world");
+    assert!(object.read::<Error>(5).await.unwrap().is_empty());
+    assert_eq!(
+        object.seek::<Error>(SeekFrom::Current(-5)).await.unwrap(),
+        6
+    );
+    assert_eq!(object.seek::<Error>(SeekFrom::End(0)).await.unwrap(), 11);
+    object.close::<Error>().await.unwrap();
+
+    let object = LargeObject::open::<Error>(&client, oid, LargeObjectMode::Read)
+        .await
+        .unwrap();
+    assert_eq!(
+        &object.read::<Error>(100).await.unwrap()[..],
+        b"hello world"
+    );
+    let err = object.write::<Error>(b"!").await.unwrap_err();
+    assert_eq!(
+        err.code(),
+        Some(&SqlState::OBJECT_NOT_IN_PREREQUISITE_STATE)
+    );
+
+    client.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_lo_create_unlink() {
+    let client = connect("user=postgres").await;
+
+    let exists = |oid: u32| {
+        let client = &client;
+        async move {
+            client
+                .query_one(
+                    "SELECT EXISTS(SELECT 1 FROM pg_largeobject_metadata WHERE oid = $1)",
+                    &[&oid],
+                )
+                .await
+                .unwrap()
+                .get::<_, bool>(0)
+        }
+    };
+
+    let oid = lo_create::<Error>(&client).await.unwrap();
+    assert!(exists(oid).await);
+    lo_unlink::<Error>(&client, oid).await.unwrap();
+    assert!(!exists(oid).await);
+
+    let oid = lo_create::<Error>(&client).await.unwrap();
+    client.batch_execute("BEGIN").await.unwrap();
+    let mut object = LargeObject::open::<Error>(&client, oid, LargeObjectMode::Write)
+        .await
+        .unwrap();
+    object.set_unlink_on_drop(true);
+    object.write::<Error>(b"temporary").await.unwrap();
+    object.close::<Error>().await.unwrap();
+    client.batch_execute("COMMIT").await.unwrap();
+    assert!(!exists(oid).await);
+
+    // The unlink on drop does not interfere with the commands pending in the raw buffer.
+    let oid = lo_create::<Error>(&client).await.unwrap();
+    client.batch_execute("BEGIN").await.unwrap();
+    let mut object = LargeObject::open::<Error>(&client, oid, LargeObjectMode::Read)
+        .await
+        .unwrap();
+    object.set_unlink_on_drop(true);
+    let statement = prepare::<Error>(&client, "SELECT 1::INT4", "", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    drop(object);
+    let messages = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(matches!(
+        messages.as_slice(),
+        [
+            Message::ParseComplete,
+            Message::BindComplete,
+            Message::DataRow(_),
+            Message::CommandComplete(_),
+            Message::ReadyForQuery(_),
+        ]
+    ));
+    client.batch_execute("COMMIT").await.unwrap();
+    assert!(!exists(oid).await);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_import_foreign_schema() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "BEGIN;
+            CREATE EXTENSION postgres_fdw;
+            CREATE SERVER import_loopback FOREIGN DATA WRAPPER postgres_fdw
+                OPTIONS (host '127.0.0.1', port '5433', dbname 'postgres');
+            CREATE USER MAPPING FOR postgres SERVER import_loopback OPTIONS (user 'postgres');
+            CREATE SCHEMA import_limit;
+            CREATE SCHEMA import_except;",
+        )
+        .await
+        .unwrap();
+
+    import_foreign_schema::<Error>(
+        &client,
+        "information_schema",
+        "import_loopback",
+        "import_limit",
+        ImportOptions::new()
+            .limit_to(vec!["tables", "columns"])
+            .option("import_default", "true"),
+    )
+    .await
+    .unwrap();
+    let tables = client
+        .query(
+            "SELECT table_name::TEXT FROM information_schema.tables \
+             WHERE table_schema = 'import_limit' ORDER BY table_name",
+            &[],
+        )
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.get::<_, String>(0))
+        .collect::<Vec<_>>();
+    assert_eq!(tables, vec!["columns".to_string(), "tables".to_string()]);
+
+    import_foreign_schema::<Error>(
+        &client,
+        "information_schema",
+        "import_loopback",
+        "import_except",
+        ImportOptions::new().except(vec!["tables"]),
+    )
+    .await
+    .unwrap();
+    assert!(!table_exists::<Error>(&client, "import_except", "tables")
+        .await
+        .unwrap());
+    assert!(table_exists::<Error>(&client, "import_except", "columns")
+        .await
+        .unwrap());
+
+    let err = import_foreign_schema::<Error>(
+        &client,
+        "information_schema",
+        "import_loopback",
+        "import_limit",
+        ImportOptions::new().option("import_default", "true'); DROP TABLE foo; --"),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.as_db_error().is_some());
+
+    client.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_get_parameter_types() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id SERIAL, name TEXT)")
+        .await
+        .unwrap();
+
+    let types = get_parameter_types::<Error>(
+        &client,
+        "SELECT * FROM foo WHERE id = $1 AND name = $2 LIMIT $3",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        types,
+        vec![Type::INT4.oid(), Type::TEXT.oid(), Type::INT8.oid()]
+    );
+
+    let types = get_parameter_types::<Error>(&client, "SELECT 1")
+        .await
+        .unwrap();
+    assert!(types.is_empty());
+
+    let err = get_parameter_types::<Error>(&client, "SELECT * FROM bar WHERE id = $1")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_TABLE));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_bind_with_generator() {
+    let client = connect("user=postgres").await;
+
+    let generator = AtomicPortalNameGenerator::new("gen_");
+    assert_eq!(generator.next_name(), "gen_0");
+    assert_eq!(generator.next_name(), "gen_1");
+
+    let select = prepare::<Error>(&client, "SELECT $1::TEXT", "", &[]).unwrap();
+    let first = bind_with_generator::<&[Option<BytesMut>; 1], Error>(
+        &client,
+        &select,
+        &generator,
+        &[0],
+        &[Some(BytesMut::from("foo"))],
+        &[0],
+    )
+    .unwrap();
+    let second = bind_with_generator::<&[Option<BytesMut>; 1], Error>(
+        &client,
+        &select,
+        &generator,
+        &[0],
+        &[Some(BytesMut::from("bar"))],
+        &[0],
+    )
+    .unwrap();
+    assert_eq!(first.name(), "gen_2");
+    assert_eq!(second.name(), "gen_3");
+
+    execute::<Error>(&client, &second, 0).unwrap();
+    execute::<Error>(&client, &first, 0).unwrap();
+    let values = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|m| async move {
+            match m {
+                Message::DataRow(body) => Ok(Some(
+                    String::from_utf8(Row::new(body).unwrap().get(0).unwrap().to_vec()).unwrap(),
+                )),
+                _ => Ok(None),
+            }
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(values, vec!["bar".to_string(), "foo".to_string()]);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_sync_with_timeout() {
+    let (client, connection) =
+        tokio_postgres::connect("host=localhost port=5433 user=postgres", NoTls)
+            .await
+            .unwrap();
+    tokio::spawn(connection.map(|r| r.unwrap()));
+
+    let select = prepare::<Error>(&client, "SELECT 1::INT4", "", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let messages = sync_with_timeout::<Error>(&client, Duration::from_secs(5))
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(matches!(messages.last(), Some(Message::ReadyForQuery(_))));
+    assert_eq!(
+        messages
+            .iter()
+            .filter(|m| matches!(m, Message::DataRow(_)))
+            .count(),
+        1
+    );
+
+    let sleep = prepare::<Error>(&client, "SELECT pg_sleep(10)", "", &[]).unwrap();
+    let portal = bind::<&[Option<BytesMut>; 0], Error>(&client, &sleep, "", &[], &[], &[]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let err = sync_with_timeout::<Error>(&client, Duration::from_millis(100))
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(err.to_string(), "timeout waiting for server");
+
+    let rows = client.simple_query("SELECT 1").await.unwrap();
+    assert_eq!(rows.len(), 2);
+}
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+async fn t_collect_arrow() {
+    use arrow_array::{Array, Int32Array, StringArray, TimestampMicrosecondArray};
+    use arrow_schema::{DataType, Field, Schema, TimeUnit};
+    use std::sync::Arc;
+
+    let client = connect("user=postgres").await;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, true),
+        Field::new(
+            "created",
+            DataType::Timestamp(TimeUnit::Microsecond, None),
+            false,
+        ),
+    ]));
+
+    let select = prepare::<Error>(
+        &client,
+        "SELECT * FROM (VALUES \
+            (1, 'alice', '1970-01-01 00:00:01'::TIMESTAMP), \
+            (2, NULL, '2000-01-01 00:00:00'::TIMESTAMP)) AS t (id, name, created)",
+        "",
+        &[],
+    )
+    .unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let batch = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .collect_arrow(schema.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(batch.num_rows(), 2);
+    let ids = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int32Array>()
+        .unwrap();
+    assert_eq!(ids.values(), &[1, 2]);
+    let names = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(names.value(0), "alice");
+    assert!(names.is_null(1));
+    let created = batch
+        .column(2)
+        .as_any()
+        .downcast_ref::<TimestampMicrosecondArray>()
+        .unwrap();
+    assert_eq!(created.values(), &[1_000_000, 946_684_800_000_000]);
+
+    let select =
+        prepare::<Error>(&client, "SELECT NULL::INT4, NULL::TEXT, NOW()", "", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let err = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .collect_arrow(schema)
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("unexpected NULL"));
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn t_rows_to_parquet() {
+    use arrow_array::{Array, Int64Array, StringArray};
+    use parquet_50::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use tokio_postgres::raw::parquet::{rows_to_parquet, DefaultTypeMapper};
+
+    let client = connect("user=postgres").await;
+
+    let (select, description) = prepare_and_describe::<Error>(
+        &client,
+        "SELECT i::INT8 AS id, CASE WHEN i % 2 = 0 THEN i::TEXT END AS label \
+         FROM generate_series(1, 10000) AS s (i)",
+        "",
+        &[],
+    )
+    .await
+    .unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let stream = sync::<Error>(&client).await.unwrap();
+
+    let mut buf = vec![];
+    rows_to_parquet::<_, Error>(stream, description.columns(), &DefaultTypeMapper, &mut buf)
+        .await
+        .unwrap();
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(buf))
+        .unwrap()
+        .build()
+        .unwrap();
+    let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10000);
+
+    let batch = &batches[0];
+    assert_eq!(batch.schema().field(0).name(), "id");
+    let ids = batch
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(ids.value(0), 1);
+    let labels = batch
+        .column(1)
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert!(labels.is_null(0));
+    assert_eq!(labels.value(1), "2");
+}
+
+#[cfg(feature = "arrow")]
+#[tokio::test]
+async fn t_copy_out_arrow() {
+    use arrow_array::{Array, Float64Array, Int64Array};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::sync::Arc;
+    use tokio_postgres::raw::copy_out_arrow;
+
+    let client = connect("user=postgres").await;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("half", DataType::Float64, true),
+    ]));
+
+    let batches = copy_out_arrow::<Error>(
+        &client,
+        "SELECT i::INT8, CASE WHEN i % 3 <> 0 THEN i / 2.0 END::FLOAT8 \
+         FROM generate_series(1, 10000) AS s (i)",
+        schema,
+    )
+    .await
+    .unwrap()
+    .try_collect::<Vec<_>>()
+    .await
+    .unwrap();
+
+    assert_eq!(
+        batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+        vec![8192, 1808]
+    );
+    let ids = batches[1]
+        .column(0)
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(ids.value(0), 8193);
+    let halves = batches[0]
+        .column(1)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert_eq!(halves.value(0), 0.5);
+    assert!(halves.is_null(2));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_row_decoder() {
+    struct Person {
+        id: i32,
+        name: Option<String>,
+    }
+
+    impl FromRow for Person {
+        fn from_row(row: &Row, columns: &[SimpleColumn]) -> Result<Self, Error> {
+            Ok(Person {
+                id: row.try_get(columns, 0)?,
+                name: row.get_str(1)?.map(str::to_string),
+            })
+        }
+    }
+
+    let client = connect("user=postgres").await;
+
+    let query = "SELECT * FROM (VALUES (1, 'alice'), (2, NULL)) AS t (id, name)";
+    let (select, description) = prepare_and_describe::<Error>(&client, query, "row_decoder", &[])
+        .await
+        .unwrap();
+
+    // Describing the portal reports the binary format of its results.
+    client.batch_execute("BEGIN").await.unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "p", &[], &[], &[1]).unwrap();
+    describe::<Error>(&client, DescribeTarget::Portal("p".to_string())).unwrap();
+    let columns = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|m| async move {
+            match m {
+                Message::RowDescription(body) => {
+                    Ok(Some(SimpleColumn::from_row_description_body(body).unwrap()))
+                }
+                _ => Ok(None),
+            }
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .remove(0);
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let stream = sync::<Error>(&client).await.unwrap();
+    let people = RowDecoder::<Person>::new()
+        .decode_batch(stream, &columns)
+        .await
+        .unwrap();
+    drop(portal);
+    client.batch_execute("COMMIT").await.unwrap();
+    assert_eq!(people.len(), 2);
+    assert_eq!(people[0].id, 1);
+    assert_eq!(people[0].name.as_deref(), Some("alice"));
+    assert_eq!(people[1].id, 2);
+    assert_eq!(people[1].name, None);
+
+    // Without a bind, results come back in the text format described by `prepare_and_describe`.
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let stream = sync::<Error>(&client).await.unwrap();
+    let rows = RowDecoder::<(String, Option<String>)>::default()
+        .decode_batch(stream, description.columns())
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("1".to_string(), Some("alice".to_string())),
+            ("2".to_string(), None)
+        ]
+    );
+
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    let stream = sync::<Error>(&client).await.unwrap();
+    let err = RowDecoder::<(i32,)>::new()
+        .decode_batch(stream, description.columns())
+        .await
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("error deserializing column 0"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_shared_statement_pool() {
+    let first = connect("user=postgres").await;
+    let second = connect("user=postgres").await;
+    assert_ne!(first.backend_pid(), second.backend_pid());
+
+    let pool = SharedStatementPool::new("shared_");
+    let query = "SELECT $1::INT4";
+
+    let a = pool.get_or_prepare::<Error>(&first, query, &[]).unwrap();
+    sync::<Error>(&first)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    // No Parse is buffered the second time around: syncing would otherwise fail on the duplicate name.
+    let b = pool.get_or_prepare::<Error>(&first, query, &[]).unwrap();
+    sync::<Error>(&first)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(a.name(), "shared_0");
+    assert_eq!(b.name(), "shared_0");
+
+    let c = pool.get_or_prepare::<Error>(&second, query, &[]).unwrap();
+    sync::<Error>(&second)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(c.name(), "shared_0");
+
+    let other = pool
+        .get_or_prepare::<Error>(&second, "SELECT 1", &[])
+        .unwrap();
+    assert_eq!(other.name(), "shared_1");
+    sync::<Error>(&second)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    for client in [&first, &second] {
+        let rows = client
+            .query(
+                "SELECT 1 FROM pg_prepared_statements WHERE name = 'shared_0'",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_identify_system() {
+    let client = connect("user=postgres dbname=postgres replication=database").await;
+
+    let identification = identify_system::<Error>(&client).await.unwrap();
+    assert!(!identification.system_id().is_empty());
+    assert!(identification.timeline() >= 1);
+    assert!(u64::from(identification.xlog_pos()) > 0);
+    assert_eq!(identification.dbname(), Some("postgres"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_timeline_history() {
+    let client = connect("user=postgres dbname=postgres replication=database").await;
+
+    let identification = identify_system::<Error>(&client).await.unwrap();
+    assert_eq!(identification.timeline(), 1);
+
+    // The initial timeline has no history file.
+    let err = timeline_history::<Error>(&client, 1).await.unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_FILE));
+
+    // The connection is still usable afterwards.
+    identify_system::<Error>(&client).await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_base_backup() {
+    let client = connect("user=postgres dbname=postgres replication=database").await;
+
+    let options = BaseBackupOptions::new()
+        .label("it's a test")
+        .fast(true)
+        .progress(true)
+        .no_wait(true);
+    let chunks = base_backup::<Error>(&client, options)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(chunks[0].0.as_deref(), Some("base.tar"));
+    let base = chunks
+        .iter()
+        .filter(|(archive, _)| archive.as_deref() == Some("base.tar"))
+        .flat_map(|(_, data)| data.iter().copied())
+        .collect::<Vec<u8>>();
+    // tar archives are made of 512-byte blocks, starting with the header of the first entry.
+    assert_eq!(base.len() % 512, 0);
+    assert!(base
+        .windows(b"PG_VERSION".len())
+        .any(|w| w == b"PG_VERSION"));
+
+    // The connection is still usable afterwards.
+    identify_system::<Error>(&client).await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_read_binary_file() {
+    let client = connect("user=postgres").await;
+
+    let version = client
+        .query_one("SHOW server_version_num", &[])
+        .await
+        .unwrap()
+        .get::<_, String>(0);
+    let contents = pg_read_binary_file::<Error>(&client, "PG_VERSION")
+        .await
+        .unwrap();
+    assert_eq!(
+        &contents[..],
+        format!("{}\n", version.parse::<u32>().unwrap() / 10000).as_bytes()
+    );
+
+    let err = pg_read_binary_file::<Error>(&client, "does_not_exist")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_FILE));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_xact_is_read_only() {
+    let client = connect("user=postgres").await;
+
+    assert!(!xact_is_read_only::<Error>(&client).await.unwrap());
+
+    client
+        .batch_execute("BEGIN TRANSACTION READ ONLY")
+        .await
+        .unwrap();
+    assert!(xact_is_read_only::<Error>(&client).await.unwrap());
+    client.batch_execute("ROLLBACK").await.unwrap();
+
+    assert!(!xact_is_read_only::<Error>(&client).await.unwrap());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_current_schema() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE SCHEMA IF NOT EXISTS t_current_schema")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        current_schema::<Error>(&client).await.unwrap().as_deref(),
+        Some("public")
+    );
+    assert_eq!(
+        current_schemas::<Error>(&client, false).await.unwrap(),
+        vec!["public".to_string()]
+    );
+    assert_eq!(
+        current_schemas::<Error>(&client, true).await.unwrap(),
+        vec!["pg_catalog".to_string(), "public".to_string()]
+    );
+
+    client
+        .batch_execute("SET search_path TO t_current_schema, public")
+        .await
+        .unwrap();
+    assert_eq!(
+        current_schema::<Error>(&client).await.unwrap().as_deref(),
+        Some("t_current_schema")
+    );
+    assert_eq!(
+        current_schemas::<Error>(&client, false).await.unwrap(),
+        vec!["t_current_schema".to_string(), "public".to_string()]
+    );
+
+    client.batch_execute("SET search_path TO ''").await.unwrap();
+    assert_eq!(current_schema::<Error>(&client).await.unwrap(), None);
+
+    client.batch_execute("RESET search_path").await.unwrap();
+    assert_eq!(
+        current_schema::<Error>(&client).await.unwrap().as_deref(),
+        Some("public")
+    );
+
+    client
+        .batch_execute("DROP SCHEMA t_current_schema")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_cancel_backend() {
+    let client = connect("user=postgres").await;
+    let other = connect("user=postgres").await;
+
+    let pid = other.backend_pid() as u32;
+    assert!(pg_cancel_backend::<Error>(&client, pid).await.unwrap());
+    assert!(pg_terminate_backend::<Error>(&client, pid).await.unwrap());
+
+    let err = other.batch_execute("SELECT 1").await.unwrap_err();
+    assert!(err.is_closed() || err.code() == Some(&SqlState::ADMIN_SHUTDOWN));
+}
+
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+#[tokio::test]
+async fn t_query_as_json() {
+    use serde_json_1::json;
+    use tokio_postgres::raw::query_as_json;
+
+    let client = connect("user=postgres").await;
+
+    let values = query_as_json::<Error>(
+        &client,
+        "SELECT * FROM (VALUES (1, 'foo', NULL::bool), (2, 'bar', true)) v(id, name, flag)",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        values,
+        vec![
+            json!({"id": 1, "name": "foo", "flag": null}),
+            json!({"id": 2, "name": "bar", "flag": true}),
+        ]
+    );
+
+    let values = query_as_json::<Error>(&client, "SELECT 1 AS id WHERE false")
+        .await
+        .unwrap();
+    assert!(values.is_empty());
+}
+
+#[cfg(feature = "raw")]
+#[test]
+fn t_encode_array() {
+    use bytes::BufMut;
+    use postgres_protocol::IsNull;
+    use tokio_postgres::types::FromSql;
+
+    let buf = encode_array(
+        vec![Some(1i32), None, Some(3)],
+        Type::INT4.oid(),
+        |value, buf| match value {
+            Some(value) => {
+                buf.put_i32(value);
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        },
+    )
+    .unwrap();
+    let values = Vec::<Option<i32>>::from_sql(&Type::INT4_ARRAY, &buf).unwrap();
+    assert_eq!(values, vec![Some(1), None, Some(3)]);
+
+    let buf = encode_array(Vec::<&str>::new(), Type::TEXT.oid(), |value, buf| {
+        buf.put_slice(value.as_bytes());
+        Ok(IsNull::No)
+    })
+    .unwrap();
+    let values = Vec::<String>::from_sql(&Type::TEXT_ARRAY, &buf).unwrap();
+    assert!(values.is_empty());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_decode_array() {
+    use tokio_postgres::types::FromSql;
+
+    struct RawValue<'a>(&'a [u8]);
+
+    impl<'a> FromSql<'a> for RawValue<'a> {
+        fn from_sql(
+            _: &Type,
+            raw: &'a [u8],
+        ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+            Ok(RawValue(raw))
+        }
+
+        fn accepts(_: &Type) -> bool {
+            true
+        }
+    }
+
+    let client = connect("user=postgres").await;
+
+    let stmt = client
+        .prepare("SELECT ARRAY[1, NULL, 3]::int4[], '{{a,b},{c,NULL}}'::text[]")
+        .await
+        .unwrap();
+    let row = client.query_one(&stmt, &[]).await.unwrap();
+    let ints: &[u8] = row.get::<_, RawValue<'_>>(0).0;
+    let texts: &[u8] = row.get::<_, RawValue<'_>>(1).0;
+
+    let values = decode_array(ints, |value| {
+        Ok(i32::from_be_bytes(value.try_into().unwrap()))
+    })
+    .unwrap();
+    assert_eq!(values, vec![Some(1), None, Some(3)]);
+
+    let values = decode_array(texts, |value| {
+        Ok(std::str::from_utf8(value).unwrap().to_string())
+    })
+    .unwrap();
+    assert_eq!(
+        values,
+        vec![
+            Some("a".to_string()),
+            Some("b".to_string()),
+            Some("c".to_string()),
+            None
+        ]
+    );
+
+    assert!(decode_array(&[0, 0, 0, 1], |_| Ok(())).is_err());
+}
+
+#[cfg(feature = "raw")]
+#[test]
+fn t_encode_composite() {
+    use bytes::BufMut;
+    use postgres_protocol::IsNull;
+
+    type EncodeField = Box<dyn Fn(&mut BytesMut) -> Result<IsNull, Error>>;
+
+    fn field<F>(f: F) -> Option<EncodeField>
+    where
+        F: Fn(&mut BytesMut) -> Result<IsNull, Error> + 'static,
+    {
+        Some(Box::new(f))
+    }
+
+    let fields = vec![
+        (
+            Type::INT4.oid(),
+            field(|buf| {
+                buf.put_i32(7);
+                Ok(IsNull::No)
+            }),
+        ),
+        (Type::TEXT.oid(), None),
+        (
+            Type::TEXT.oid(),
+            field(|buf| {
+                buf.put_slice(b"ignored");
+                Ok(IsNull::Yes)
+            }),
+        ),
+        (
+            Type::TEXT.oid(),
+            field(|buf| {
+                buf.put_slice(b"hi");
+                Ok(IsNull::No)
+            }),
+        ),
+    ];
+
+    let mut buf = BytesMut::new();
+    encode_composite(&fields, &mut buf).unwrap();
+
+    let mut expected = BytesMut::new();
+    expected.put_i32(4);
+    expected.put_u32(Type::INT4.oid());
+    expected.put_i32(4);
+    expected.put_i32(7);
+    expected.put_u32(Type::TEXT.oid());
+    expected.put_i32(-1);
+    expected.put_u32(Type::TEXT.oid());
+    expected.put_i32(-1);
+    expected.put_u32(Type::TEXT.oid());
+    expected.put_i32(2);
+    expected.put_slice(b"hi");
+    assert_eq!(buf, expected);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_simple_query_row_to_csv() {
+    let client = connect("user=postgres").await;
+
+    let messages: Vec<Message> = simple_query::<Error>(
+        &client,
+        "SELECT 1, NULL, '', 'a,b', 'say \"hi\"', E'two\\nlines', 'N'",
+    )
+    .unwrap()
+    .try_collect()
+    .await
+    .unwrap();
+
+    let row = messages
+        .into_iter()
+        .find_map(|m| match m {
+            Message::DataRow(body) => Some(SimpleQueryRow::new(body).unwrap()),
+            _ => None,
+        })
+        .unwrap();
+
+    assert_eq!(
+        row.to_csv(',', '"'),
+        "1,,\"\",\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",N"
+    );
+    assert_eq!(row.to_csv(';', '\''), "1;;'';a,b;say \"hi\";'two\nlines';N");
+    assert_eq!(
+        row.to_csv_with_null(',', '"', "N"),
+        "1,N,\"\",\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\",\"N\""
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_raw_transaction() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    let transaction = begin::<Error>(&client).await.unwrap();
+    transaction
+        .client()
+        .batch_execute("INSERT INTO foo VALUES (1)")
+        .await
+        .unwrap();
+    transaction.commit::<Error>().await.unwrap();
+
+    let transaction = begin::<Error>(&client).await.unwrap();
+    transaction
+        .client()
+        .batch_execute("INSERT INTO foo VALUES (2)")
+        .await
+        .unwrap();
+    transaction.rollback::<Error>().await.unwrap();
+
+    let rows = client.query("SELECT id FROM foo", &[]).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_savepoint() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT)")
+        .await
+        .unwrap();
+
+    let transaction = begin::<Error>(&client).await.unwrap();
+    let outer = savepoint::<Error>(&transaction, "sp_outer").await.unwrap();
+    outer
+        .client()
+        .batch_execute("INSERT INTO foo VALUES (1)")
+        .await
+        .unwrap();
+
+    let inner = outer.savepoint::<Error>("sp_inner").await.unwrap();
+    inner
+        .client()
+        .batch_execute("INSERT INTO foo VALUES (2)")
+        .await
+        .unwrap();
+    inner.rollback::<Error>().await.unwrap();
+
+    let inner = outer.savepoint::<Error>("sp_inner").await.unwrap();
+    inner
+        .client()
+        .batch_execute("INSERT INTO foo VALUES (3)")
+        .await
+        .unwrap();
+    inner.commit::<Error>().await.unwrap();
+    outer.commit::<Error>().await.unwrap();
+
+    assert!(savepoint::<Error>(&transaction, "bad name").await.is_err());
+    transaction.commit::<Error>().await.unwrap();
+
+    let rows = client
+        .query("SELECT id FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    let ids = rows.iter().map(|r| r.get(0)).collect::<Vec<i32>>();
+    assert_eq!(ids, vec![1, 3]);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_stream_query() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'alice'), (2, 'bob'), (3, NULL);",
+        )
+        .await
+        .unwrap();
+
+    let query = "SELECT id, name FROM foo WHERE id >= $1 ORDER BY id";
+    for _ in 0..2 {
+        let rows = stream_query::<(i32, Option<String>), Error>(&client, query, &[&2i32])
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(rows, vec![(2, Some("bob".to_string())), (3, None)]);
+    }
+
+    let err = stream_query::<(i32,), Error>(&client, query, &[&"2"])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("error serializing parameter 0"));
+
+    let err = stream_query::<(i32,), Error>(&client, "SELECT * FROM missing", &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Some(&SqlState::UNDEFINED_TABLE));
+
+    // The types of the parameters which are not built in are looked up.
+    let mut map = HashMap::new();
+    map.insert("a".to_string(), Some("1".to_string()));
+    let rows = stream_query::<(String,), Error>(&client, "SELECT $1::hstore -> 'a'", &[&map])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![("1".to_string(),)]);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_insert_many() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT, score FLOAT8 DEFAULT 0)")
+        .await
+        .unwrap();
+
+    let rows = (0..1000).map(|i| {
+        (
+            i,
+            if i % 2 == 0 {
+                Some(i.to_string())
+            } else {
+                None
+            },
+        )
+    });
+    let count = insert_many::<_, _, Error>(&client, "foo", &["id", "name"], rows)
+        .await
+        .unwrap();
+    assert_eq!(count, 1000);
+
+    let count = insert_many::<_, _, Error>(&client, "foo", &[], vec![(1000, "last", 1.5f64)])
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let row = client
+        .query_one("SELECT count(*), count(name), sum(score) FROM foo", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1001);
+    assert_eq!(row.get::<_, i64>(1), 501);
+    assert_eq!(row.get::<_, f64>(2), 1.5);
+
+    let err = insert_many::<_, _, Error>(&client, "foo", &["id"], vec![("x",)])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("error serializing parameter 0"));
+    assert!(
+        insert_many::<_, _, Error>(&client, "foo; DROP TABLE foo", &[], vec![(1,)])
+            .await
+            .is_err()
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_upsert_many() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY, name TEXT, hits INT);
+             INSERT INTO foo VALUES (1, 'alice', 1), (2, 'bob', 1);",
+        )
+        .await
+        .unwrap();
+
+    let rows = vec![(2, "robert", 5), (3, "carol", 1)];
+    let count = upsert_many::<_, _, Error>(
+        &client,
+        "foo",
+        &["id", "name", "hits"],
+        &["id"],
+        &["name"],
+        rows,
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 2);
+
+    let count = upsert_many::<_, _, Error>(
+        &client,
+        "foo",
+        &["id", "name", "hits"],
+        &["id"],
+        &[],
+        vec![(1, "ignored", 0), (4, "dave", 1)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(count, 1);
+
+    let rows = client
+        .query("SELECT id, name, hits FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    let rows = rows
+        .iter()
+        .map(|r| (r.get(0), r.get(1), r.get(2)))
+        .collect::<Vec<(i32, String, i32)>>();
+    assert_eq!(
+        rows,
+        vec![
+            (1, "alice".to_string(), 1),
+            (2, "robert".to_string(), 1),
+            (3, "carol".to_string(), 1),
+            (4, "dave".to_string(), 1),
+        ]
+    );
+
+    let err = upsert_many::<(i32,), _, Error>(&client, "foo", &["id"], &[], &[], vec![(5,)])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no conflict column"));
+    let err = upsert_many::<(i32,), _, Error>(&client, "foo", &[], &["id"], &[], vec![(5,)])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("no column to insert"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_delete_where() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'a'), (2, 'a'), (2, 'b'), (3, 'c');",
+        )
+        .await
+        .unwrap();
+
+    let count = delete_where::<Error>(&client, "foo", &[("id", &2i32), ("name", &"a")])
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let count = delete_where::<Error>(&client, "foo", &[("id", &2i32)])
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    assert!(
+        delete_where::<Error>(&client, "foo", &[("id = id OR true", &1i32)])
+            .await
+            .is_err()
+    );
+
+    let count = delete_where::<Error>(&client, "foo", &[]).await.unwrap();
+    assert_eq!(count, 2);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_select_where() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT, active BOOL);
+             INSERT INTO foo VALUES (1, 'a', true), (2, 'b', false), (3, 'c', true);",
+        )
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        let rows = select_where::<(i32, String), Error>(
+            &client,
+            "foo",
+            &["id", "name"],
+            &[("active", &true)],
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows, vec![(1, "a".to_string()), (3, "c".to_string())]);
+    }
+
+    let rows = select_where::<(i32, String, bool), Error>(&client, "foo", &[], &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+
+    assert!(
+        select_where::<(i32,), Error>(&client, "foo", &["id FROM foo; --"], &[])
+            .await
+            .is_err()
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_builder() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'a'), (2, 'b'), (3, 'b'), (4, 'b'), (5, 'c');",
+        )
+        .await
+        .unwrap();
+
+    let (sql, params) = QueryBuilder::new()
+        .select(&["id", "name"])
+        .from("foo")
+        .where_eq("name", "b")
+        .where_in("id", vec![1i32, 3, 4])
+        .order_by("id", Order::Desc)
+        .limit(1)
+        .offset(1)
+        .build()
+        .unwrap();
+    assert_eq!(
+        sql,
+        "SELECT id, name FROM foo WHERE name = $1 AND id IN ($2, $3, $4) \
+         ORDER BY id DESC LIMIT 1 OFFSET 1"
+    );
+
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    let rows = client.query(sql.as_str(), &params).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 3);
+
+    let (sql, params) = QueryBuilder::new()
+        .from("foo")
+        .where_in("id", Vec::<i32>::new())
+        .build()
+        .unwrap();
+    assert_eq!(sql, "SELECT * FROM foo WHERE FALSE");
+    assert!(params.is_empty());
+
+    assert!(QueryBuilder::new().select(&["id"]).build().is_err());
+    assert!(QueryBuilder::new()
+        .from("foo")
+        .order_by("id; DROP TABLE foo", Order::Asc)
+        .build()
+        .is_err());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_insert_builder() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY, name TEXT, hits INT DEFAULT 0)",
+        )
+        .await
+        .unwrap();
+
+    let insert = |name: &'static str| {
+        InsertBuilder::new()
+            .into_table("foo")
+            .column("id", 1i32)
+            .column("name", name)
+            .on_conflict(&["id"])
+            .on_conflict_do_update("name", name)
+            .on_conflict_do_update("hits", 1i32)
+            .returning(&["hits"])
+            .build()
+            .unwrap()
+    };
+
+    let (sql, params) = insert("a");
+    assert_eq!(
+        sql,
+        "INSERT INTO foo (id, name) VALUES ($1, $2) \
+         ON CONFLICT (id) DO UPDATE SET name = $3, hits = $4 RETURNING hits"
+    );
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    let row = client.query_one(sql.as_str(), &params).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 0);
+
+    let (sql, params) = insert("b");
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    let row = client.query_one(sql.as_str(), &params).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 1);
+
+    let (sql, params) = InsertBuilder::new()
+        .into_table("foo")
+        .column("id", 1i32)
+        .on_conflict_do_nothing()
+        .build()
+        .unwrap();
+    assert_eq!(
+        sql,
+        "INSERT INTO foo (id) VALUES ($1) ON CONFLICT DO NOTHING"
+    );
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    assert_eq!(client.execute(sql.as_str(), &params).await.unwrap(), 0);
+
+    let name = client
+        .query_one("SELECT name FROM foo WHERE id = 1", &[])
+        .await
+        .unwrap()
+        .get::<_, String>(0);
+    assert_eq!(name, "b");
+
+    assert!(InsertBuilder::new()
+        .into_table("foo")
+        .column("id", 2i32)
+        .on_conflict_do_update("hits", 1i32)
+        .build()
+        .is_err());
+    assert!(InsertBuilder::new()
+        .into_table("foo")
+        .column("id) VALUES (1); --", 2i32)
+        .build()
+        .is_err());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_update_builder() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT, hits INT);
+             INSERT INTO foo VALUES (1, 'a', 0), (2, 'b', 0), (3, 'b', 0);",
+        )
+        .await
+        .unwrap();
+
+    let (sql, params) = UpdateBuilder::new()
+        .table("foo")
+        .set("hits", 5i32)
+        .where_eq("name", "b")
+        .where_eq("id", 3i32)
+        .returning(&["id"])
+        .build()
+        .unwrap();
+    assert_eq!(
+        sql,
+        "UPDATE foo SET hits = $1 WHERE name = $2 AND id = $3 RETURNING id"
+    );
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    let rows = client.query(sql.as_str(), &params).await.unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].get::<_, i32>(0), 3);
+
+    assert!(UpdateBuilder::new()
+        .table("foo")
+        .set("hits", 1i32)
+        .build()
+        .is_err());
+    assert!(UpdateBuilder::new()
+        .table("foo")
+        .allow_update_all()
+        .build()
+        .is_err());
+
+    let (sql, params) = UpdateBuilder::new()
+        .table("foo")
+        .set("hits", 1i32)
+        .allow_update_all()
+        .build()
+        .unwrap();
+    assert_eq!(sql, "UPDATE foo SET hits = $1");
+    let params = params
+        .iter()
+        .map(|p| &**p as &(dyn ToSql + Sync))
+        .collect::<Vec<_>>();
+    assert_eq!(client.execute(sql.as_str(), &params).await.unwrap(), 3);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_invalidate_on_schema_change() {
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream =
+        stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!("{}", e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    let invalidator = invalidate_on_schema_change::<_, Error>(&client, rx)
+        .await
+        .unwrap();
+    tokio::spawn(invalidator);
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT); INSERT INTO foo VALUES (1)")
+        .await
+        .unwrap();
+
+    let query = "SELECT * FROM foo";
+    let rows = stream_query::<(i32,), Error>(&client, query, &[])
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1,)]);
+
+    client
+        .batch_execute("ALTER TABLE foo ADD COLUMN name TEXT DEFAULT 'a'")
+        .await
+        .unwrap();
+
+    let mut rows = None;
+    for _ in 0..100 {
+        match stream_query::<(i32, String), Error>(&client, query, &[])
+            .try_collect::<Vec<_>>()
+            .await
+        {
+            Ok(r) => {
+                rows = Some(r);
+                break;
+            }
+            Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+        }
+    }
+    assert_eq!(rows, Some(vec![(1, "a".to_string())]));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_keepalive() {
+    let client = connect("user=postgres").await;
+
+    keepalive::<Error>(&client).await.unwrap();
+
+    let select = prepare::<Error>(&client, "SELECT 1", "", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    // the buffered commands are not sent by the keepalive
+    keepalive::<Error>(&client).await.unwrap();
+
+    let messages: Vec<Message> = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 5);
+    assert!(matches!(messages[0], Message::ParseComplete));
+    assert!(matches!(messages[4], Message::ReadyForQuery(_)));
+}
+
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+#[tokio::test]
+async fn t_watch_table() {
+    use serde_json_1::json;
+    use tokio_postgres::raw::{watch_table, Operation};
+
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream =
+        stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!("{}", e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY, name TEXT)")
+        .await
+        .unwrap();
+
+    let mut changes = watch_table::<_, Error>(&client, "foo", "id", rx)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute(
+            "INSERT INTO foo VALUES (1, 'a'); \
+             UPDATE foo SET name = 'b' WHERE id = 1; \
+             DELETE FROM foo WHERE id = 1",
+        )
+        .await
+        .unwrap();
+
+    for operation in [Operation::Insert, Operation::Update, Operation::Delete] {
+        let change = changes.next().await.unwrap();
+        assert_eq!(change.operation(), operation);
+        assert_eq!(change.pk(), &json!(1));
+    }
+
+    drop(changes);
+    let rows = client
+        .query(
+            "SELECT 1 FROM pg_trigger WHERE tgrelid = 'foo'::regclass AND NOT tgisinternal",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(rows.is_empty());
+}
+
+#[cfg(all(feature = "raw", feature = "with-serde_json-1"))]
+#[tokio::test]
+async fn t_watch_table_mixed_case() {
+    use serde_json_1::json;
+    use tokio_postgres::raw::{watch_table, Operation};
+
+    let (client, mut connection) = connect_raw("user=postgres").await.unwrap();
+
+    let (tx, rx) = mpsc::unbounded();
+    let stream =
+        stream::poll_fn(move |cx| connection.poll_message(cx)).map_err(|e| panic!("{}", e));
+    let connection = stream.forward(tx).map(|r| r.unwrap());
+    tokio::spawn(connection);
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT PRIMARY KEY)")
+        .await
+        .unwrap();
+
+    let mut changes = watch_table::<_, Error>(&client, "Foo", "Id", rx)
+        .await
+        .unwrap();
+
+    client
+        .batch_execute("INSERT INTO foo VALUES (1)")
+        .await
+        .unwrap();
+
+    let change = changes.next().await.unwrap();
+    assert_eq!(change.operation(), Operation::Insert);
+    assert_eq!(change.pk(), &json!(1));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_dump_query() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT NOT NULL, name VARCHAR(10), created TIMESTAMP)",
+        )
+        .await
+        .unwrap();
+
+    let ddl = pg_dump_query::<Error>(&client, "SELECT id, name, 1::BIGINT AS \"Count\" FROM foo")
+        .await
+        .unwrap();
+    assert_eq!(
+        ddl,
+        "CREATE TABLE tmp (\n    \"id\" integer NOT NULL,\n    \"name\" character varying(10),\n    \"Count\" bigint\n)"
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_sequence() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY SEQUENCE foo_seq START 10")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        sequence_nextval::<Error>(&client, "foo_seq").await.unwrap(),
+        10
+    );
+    assert_eq!(
+        sequence_nextval::<Error>(&client, "foo_seq").await.unwrap(),
+        11
+    );
+    assert_eq!(
+        sequence_currval::<Error>(&client, "foo_seq").await.unwrap(),
+        11
+    );
+    assert_eq!(sequence_lastval::<Error>(&client).await.unwrap(), 11);
+
+    assert_eq!(
+        sequence_setval::<Error>(&client, "foo_seq", 20, false)
+            .await
+            .unwrap(),
+        20
+    );
+    assert_eq!(
+        sequence_nextval::<Error>(&client, "foo_seq").await.unwrap(),
+        20
+    );
+
+    sequence_nextval::<Error>(&client, "foo_seq; DROP TABLE foo")
+        .await
+        .unwrap_err();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_stat_activity() {
+    let client = connect("user=postgres application_name=t_pg_stat_activity").await;
+
+    let activity = pg_stat_activity::<Error>(&client).await.unwrap();
+    let own = activity
+        .iter()
+        .find(|a| a.pid() == client.backend_pid())
+        .unwrap();
+    assert_eq!(own.usename(), Some("postgres"));
+    assert_eq!(own.application_name(), Some("t_pg_stat_activity"));
+    assert_eq!(own.state(), Some("active"));
+    assert!(own.query().unwrap().contains("pg_stat_activity"));
+    assert!(own.query_start().is_some());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_select_for_update() {
+    let client = connect("user=postgres").await;
+    let other = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "DROP TABLE IF EXISTS t_select_for_update;
+             CREATE TABLE t_select_for_update (id INT, name TEXT);
+             INSERT INTO t_select_for_update VALUES (1, 'a'), (2, 'b');",
+        )
+        .await
+        .unwrap();
+
+    let query = || {
+        QueryBuilder::new()
+            .select(&["id", "name"])
+            .from("t_select_for_update")
+            .where_eq("name", "a")
+            .build()
+            .unwrap()
+    };
+
+    client.batch_execute("BEGIN").await.unwrap();
+    let rows = select_for_update::<(i32, String), Error>(&client, query(), false)
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1, "a".to_string())]);
+
+    other.batch_execute("BEGIN").await.unwrap();
+    let err = select_for_update::<(i32, String), Error>(&other, query(), true)
+        .await
+        .unwrap_err();
+    assert!(err.is_lock_not_available());
+    other.batch_execute("ROLLBACK").await.unwrap();
+
+    client
+        .batch_execute("COMMIT; DROP TABLE t_select_for_update")
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_blocking_pids() {
+    let client = connect("user=postgres").await;
+    let other = connect("user=postgres").await;
+
+    let other_pid = other.backend_pid() as u32;
+    assert_eq!(
+        pg_blocking_pids::<Error>(&client, other_pid).await.unwrap(),
+        Vec::<u32>::new()
+    );
+
+    client
+        .batch_execute("BEGIN; SELECT pg_advisory_xact_lock(4380)")
+        .await
+        .unwrap();
+    let blocked = tokio::spawn(async move {
+        other
+            .batch_execute("SELECT pg_advisory_lock(4380); SELECT pg_advisory_unlock(4380)")
+            .await
+            .unwrap();
+    });
+
+    let mut pids = vec![];
+    for _ in 0..100 {
+        pids = pg_blocking_pids::<Error>(&client, other_pid).await.unwrap();
+        if !pids.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!(pids, vec![client.backend_pid() as u32]);
+
+    client.batch_execute("COMMIT").await.unwrap();
+    blocked.await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_generic_row() {
+    let client = connect("user=postgres").await;
+
+    let select = prepare::<Error>(&client, "SELECT 'foo', NULL::TEXT, 42", "", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let rows: Vec<GenericRow> = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|message| {
+            future::ready(match message {
+                Message::DataRow(body) => Row::new(body).map(|row| Some(GenericRow::from_row(row))),
+                _ => Ok(None),
+            })
+        })
+        .try_collect()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 1);
+
+    let row = rows[0].clone();
+    drop(rows);
+    assert_eq!(row.len(), 3);
+    assert_eq!(row.get_text(0).unwrap(), Some("foo"));
+    assert_eq!(row.get_text(1).unwrap(), None);
+    assert_eq!(row.get(2), Some(&b"42"[..]));
+    assert_eq!(row[2].as_deref(), Some(&b"42"[..]));
+    assert!(row[1].is_none());
+    row.get_text(3).unwrap_err();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_owned_simple_query_row() {
+    let client = connect("user=postgres").await;
+
+    let messages: Vec<Message> =
+        simple_query::<Error>(&client, "SELECT 'a', NULL UNION ALL SELECT 'b', 'c'")
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+
+    let rows = messages
+        .into_iter()
+        .filter_map(|m| match m {
+            Message::DataRow(body) => Some(SimpleQueryRow::new(body).unwrap()),
+            _ => None,
+        })
+        .map(|row| OwnedSimpleQueryRow::from_simple_row(&row).unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].len(), 2);
+    assert_eq!(rows[0].get(0), Some("a"));
+    assert_eq!(rows[0].get(1), None);
+    assert_eq!(rows[1].get(0), Some("b"));
+    assert_eq!(rows[1].get(1), Some("c"));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_opt() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'a'), (2, 'b'), (3, 'b');",
+        )
+        .await
+        .unwrap();
+
+    let query = "SELECT id FROM foo WHERE name = $1";
+    assert_eq!(
+        query_opt::<(i32,), Error>(&client, query, &[&"a"])
+            .await
+            .unwrap(),
+        Some((1,))
+    );
+    assert_eq!(
+        query_opt::<(i32,), Error>(&client, query, &[&"c"])
+            .await
+            .unwrap(),
+        None
+    );
+    query_opt::<(i32,), Error>(&client, query, &[&"b"])
+        .await
+        .unwrap_err();
+
+    // the connection is still usable after the portal is suspended
+    assert_eq!(
+        query_opt::<(i32,), Error>(&client, query, &[&"a"])
+            .await
+            .unwrap(),
+        Some((1,))
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_one() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'a'), (2, 'b'), (3, 'b');",
+        )
+        .await
+        .unwrap();
+
+    let query = "SELECT id FROM foo WHERE name = $1";
+    assert_eq!(
+        query_one::<(i32,), Error>(&client, query, &[&"a"])
+            .await
+            .unwrap(),
+        (1,)
+    );
+
+    let err = query_one::<(i32,), Error>(&client, query, &[&"c"])
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "query returned no rows");
+
+    let err = query_one::<(i32,), Error>(&client, query, &[&"b"])
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "query returned more than one row");
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_page() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo SELECT i, 'a' FROM generate_series(1, 5) i;
+             INSERT INTO foo VALUES (6, 'b');",
+        )
+        .await
+        .unwrap();
+
+    let query = "SELECT id FROM foo WHERE name = $1 ORDER BY id";
+    let page = query_page::<(i32,), Error>(&client, query, &[&"a"], 1, 2, true)
+        .await
+        .unwrap();
+    assert_eq!(page.items(), &[(3,), (4,)]);
+    assert_eq!(page.total_count(), Some(5));
+    assert_eq!(page.page(), 1);
+    assert_eq!(page.page_size(), 2);
+
+    let page = query_page::<(i32,), Error>(&client, query, &[&"a"], 2, 2, false)
+        .await
+        .unwrap();
+    assert_eq!(page.into_items(), vec![(5,)]);
+
+    let page = query_page::<(i32,), Error>(&client, query, &[&"a"], 3, 2, true)
+        .await
+        .unwrap();
+    assert!(page.items().is_empty());
+    assert_eq!(page.total_count(), Some(5));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_batch_select() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute(
+            "CREATE TEMPORARY TABLE foo (id INT, name TEXT);
+             INSERT INTO foo VALUES (1, 'a'), (2, 'b'), (3, 'b');",
+        )
+        .await
+        .unwrap();
+
+    let by_name = |name: &str| {
+        QueryBuilder::new()
+            .select(&["id", "name"])
+            .from("foo")
+            .where_eq("name", name.to_string())
+            .order_by("id", Order::Asc)
+            .build()
+            .unwrap()
+    };
+
+    let results = batch_select::<(i32, String), Error>(
+        &client,
+        vec![by_name("b"), by_name("c"), by_name("a")],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        results,
+        vec![
+            vec![(2, "b".to_string()), (3, "b".to_string())],
+            vec![],
+            vec![(1, "a".to_string())],
+        ]
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_simple_column_format_type() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TYPE pg_temp.mood AS ENUM ('sad', 'happy')")
+        .await
+        .unwrap();
+    let oid = client
+        .query_one("SELECT 'pg_temp.mood'::regtype::oid", &[])
+        .await
+        .unwrap()
+        .get::<_, u32>(0);
+
+    let (_, description) =
+        prepare_and_describe::<Error>(&client, "SELECT 1::INT4, 'happy'::pg_temp.mood", "", &[])
+            .await
+            .unwrap();
+
+    let columns = description.columns();
+    assert_eq!(columns[0].type_oid(), Type::INT4.oid());
+    assert_eq!(columns[0].format_type(), "int4");
+    assert_eq!(columns[1].type_oid(), oid);
+    assert_eq!(columns[1].type_(), &None);
+    assert_eq!(columns[1].format_type(), format!("unknown({})", oid));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_describe_many() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    let (_select, _) = prepare_and_describe::<Error>(
+        &client,
+        "SELECT id, name FROM foo WHERE id = $1",
+        "describe_many_select",
+        &[],
+    )
+    .await
+    .unwrap();
+    let (_insert, _) = prepare_and_describe::<Error>(
+        &client,
+        "INSERT INTO foo (name) VALUES ($1)",
+        "describe_many_insert",
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let descriptions =
+        describe_many::<Error>(&client, &["describe_many_insert", "describe_many_select"])
+            .await
+            .unwrap();
+    assert_eq!(descriptions.len(), 2);
+    assert_eq!(descriptions[0].params(), &[Type::TEXT.oid()]);
+    assert!(descriptions[0].columns().is_empty());
+    assert_eq!(descriptions[1].params(), &[Type::INT4.oid()]);
+    assert_eq!(descriptions[1].columns().len(), 2);
+    assert_eq!(descriptions[1].columns()[1].name(), "name");
+
+    assert!(describe_many::<Error>(&client, &["describe_many_missing"])
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_encode_range() {
+    use bytes::BufMut;
+
+    let encode_int4 = |value: i32, buf: &mut BytesMut| {
+        buf.put_i32(value);
+        Ok(())
+    };
+
+    let mut buf = BytesMut::new();
+    encode_range(
+        Some(1),
+        Some(10),
+        RangeFlags::LOWER_INCLUSIVE,
+        encode_int4,
+        &mut buf,
+    )
+    .unwrap();
+    let mut expected = BytesMut::new();
+    expected.put_u8(0x02);
+    expected.put_i32(4);
+    expected.put_i32(1);
+    expected.put_i32(4);
+    expected.put_i32(10);
+    assert_eq!(buf, expected);
+
+    let mut empty = BytesMut::new();
+    encode_range(
+        Some(1),
+        Some(10),
+        RangeFlags::EMPTY,
+        encode_int4,
+        &mut empty,
+    )
+    .unwrap();
+    assert_eq!(&empty[..], &[0x01]);
+
+    let client = connect("user=postgres").await;
+    let ranges = vec![buf, empty, {
+        let mut buf = BytesMut::new();
+        encode_range(
+            None,
+            Some(5),
+            RangeFlags::UPPER_INCLUSIVE,
+            encode_int4,
+            &mut buf,
+        )
+        .unwrap();
+        buf
+    }];
+
+    let select = prepare::<Error>(
+        &client,
+        "SELECT $1::int4range::text",
+        "",
+        &[Type::INT4_RANGE.oid()],
+    )
+    .unwrap();
+    for range in &ranges {
+        let portal =
+            bind::<_, Error>(&client, &select, "", &[1], &[Some(range.clone())], &[0]).unwrap();
+        execute::<Error>(&client, &portal, 0).unwrap();
+    }
+
+    let values = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|message| {
+            future::ready(match message {
+                Message::DataRow(body) => {
+                    Row::new(body).and_then(|row| Ok(row.get_str(0)?.map(str::to_string)))
+                }
+                _ => Ok(None),
+            })
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(values, vec!["[1,10)", "empty", "(,6)"]);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_decode_range() {
+    use std::ops::Bound;
+
+    let client = connect("user=postgres").await;
+
+    let select = prepare::<Error>(
+        &client,
+        "SELECT '[1,10)'::int4range, 'empty'::int4range, '(,5]'::int4range",
+        "",
+        &[],
+    )
+    .unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let rows = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|message| {
+            future::ready(match message {
+                Message::DataRow(body) => Row::new(body).map(Some),
+                _ => Ok(None),
+            })
+        });
+    pin_mut!(rows);
+    let row = rows.try_next().await.unwrap().unwrap();
+
+    let decode_int4 = |buf: &[u8]| Ok(i32::from_be_bytes(buf.try_into().unwrap()));
+    assert_eq!(
+        decode_range(row.get(0).unwrap(), decode_int4).unwrap(),
+        PgRange::Range {
+            lower: Bound::Included(1),
+            upper: Bound::Excluded(10),
+        }
+    );
+    assert_eq!(
+        decode_range(row.get(1).unwrap(), decode_int4).unwrap(),
+        PgRange::Empty
+    );
+    assert_eq!(
+        decode_range(row.get(2).unwrap(), decode_int4).unwrap(),
+        PgRange::Range {
+            lower: Bound::Unbounded,
+            upper: Bound::Excluded(6),
+        }
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test(flavor = "multi_thread")]
+async fn t_prepare_with_guard() {
+    let client = connect("user=postgres").await;
+
+    let count_prepared = || async {
+        client
+            .query_one(
+                "SELECT COUNT(*) FROM pg_prepared_statements WHERE name = 'guarded'",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get::<_, i64>(0)
+    };
+
+    {
+        let statement = prepare_with_guard::<Error>(&client, "SELECT 1", "guarded", &[]).unwrap();
+        assert_eq!(statement.name(), "guarded");
+        sync::<Error>(&client)
+            .await
+            .unwrap()
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(count_prepared().await, 1);
+    }
+
+    assert_eq!(count_prepared().await, 0);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_portal_fetched_count() {
+    let client = connect("user=postgres").await;
+    client.batch_execute("BEGIN").await.unwrap();
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 5)", "fetched", &[]).unwrap();
+    let portal = bind::<&[Option<BytesMut>; 0], Error>(
+        &client,
+        &statement,
+        "fetched_portal",
+        &[],
+        &[],
+        &[1],
+    )
+    .unwrap();
+    assert_eq!(portal.fetched_count(), 0);
+
+    execute::<Error>(&client, &portal, 2).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(portal.max_rows(), 2);
+    assert_eq!(portal.fetched_count(), 2);
+
+    execute::<Error>(&client, &portal, 0).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(portal.max_rows(), 0);
+    assert_eq!(portal.fetched_count(), 5);
+
+    client.batch_execute("COMMIT").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_execute_flushed_by_prepare() {
+    let client = connect("user=postgres").await;
+    client.batch_execute("BEGIN").await.unwrap();
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 3)", "flushed", &[]).unwrap();
+    let flushed = bind::<&[Option<BytesMut>; 0], Error>(
+        &client,
+        &statement,
+        "flushed_portal",
+        &[],
+        &[],
+        &[1],
+    )
+    .unwrap();
+    execute::<Error>(&client, &flushed, 2).unwrap();
+
+    // The buffered commands are sent along with the preparation of the cached statement.
+    let (value,) = query_one::<(i32,), Error>(&client, "SELECT 42 AS flushed_by_prepare", &[])
+        .await
+        .unwrap();
+    assert_eq!(value, 42);
+    assert!(raw_buf_is_empty(&client));
+
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "synced_portal", &[], &[], &[1])
+            .unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    assert_eq!(flushed.fetched_count(), 0);
+    assert!(!flushed.is_suspended());
+    assert_eq!(portal.fetched_count(), 3);
+    assert!(!portal.is_suspended());
+
+    client.batch_execute("COMMIT").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_execute_function() {
+    let client = connect("user=postgres").await;
+
+    let value = (-5_i32).to_be_bytes();
+    let result = execute_function::<Error>(&client, "abs", &[(&Type::INT4, Some(&value[..]))])
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(result.as_ref(), 5_i32.to_be_bytes());
+
+    let result = execute_function::<Error>(&client, "pg_catalog.abs", &[(&Type::INT4, None)])
+        .await
+        .unwrap();
+    assert_eq!(result, None);
+
+    assert!(execute_function::<Error>(&client, "abs; DROP", &[])
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_call_procedure() {
+    let client = connect("user=postgres").await;
+    client
+        .batch_execute(
+            "CREATE PROCEDURE pg_temp.double_it(INOUT x INT4) LANGUAGE plpgsql AS $$ BEGIN x := x * 2; END $$;
+             CREATE PROCEDURE pg_temp.noop(x INT4) LANGUAGE plpgsql AS $$ BEGIN END $$;",
+        )
+        .await
+        .unwrap();
+
+    let value = 21_i32.to_be_bytes();
+    let out = call_procedure::<Error>(
+        &client,
+        "pg_temp.double_it",
+        &[(&Type::INT4, Some(&value[..]))],
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].as_deref(), Some(&42_i32.to_be_bytes()[..]));
+
+    let out = call_procedure::<Error>(&client, "pg_temp.noop", &[(&Type::INT4, Some(&value[..]))])
+        .await
+        .unwrap();
+    assert_eq!(out, None);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_describe_function() {
+    let client = connect("user=postgres").await;
+
+    describe::<Error>(
+        &client,
+        DescribeTarget::with_schema("pg_catalog", "abs", vec![Type::INT4.oid()]),
+    )
+    .unwrap();
+    let messages = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let mut itr = messages.into_iter();
+    assert!(matches!(itr.next(), Some(Message::ParseComplete)));
+    match itr.next() {
+        Some(Message::ParameterDescription(body)) => {
+            let params = body.parameters().collect::<Vec<_>>().unwrap();
+            assert_eq!(params, [Type::INT4.oid()]);
+        }
+        _ => panic!("unexpected message"),
+    }
+    match itr.next() {
+        Some(Message::RowDescription(body)) => {
+            let columns = SimpleColumn::from_row_description_body(body).unwrap();
+            assert_eq!(columns[0].type_(), &Some(Type::INT4));
+        }
+        _ => panic!("unexpected message"),
+    }
+    assert!(matches!(itr.next(), Some(Message::ReadyForQuery(_))));
+
+    assert!(describe::<Error>(
+        &client,
+        DescribeTarget::with_schema("pg_catalog", "abs()", vec![])
+    )
+    .is_err());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_typed_portal() {
+    let client = connect("user=postgres").await;
+    client.batch_execute("BEGIN").await.unwrap();
+
+    let (select, _) =
+        prepare_and_describe::<Error>(&client, "SELECT generate_series(1, 3)", "typed", &[])
+            .await
+            .unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &select, "typed_portal", &[], &[], &[1])
+            .unwrap();
+    describe::<Error>(&client, DescribeTarget::Portal("typed_portal".to_string())).unwrap();
+    let columns = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_filter_map(|m| async move {
+            match m {
+                Message::RowDescription(body) => {
+                    Ok(Some(SimpleColumn::from_row_description_body(body).unwrap()))
+                }
+                _ => Ok(None),
+            }
+        })
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap()
+        .remove(0);
+
+    let portal = TypedPortal::<(i32,)>::new(portal, columns);
+    let rows = portal
+        .execute::<Error>(&client, 2)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(1,), (2,)]);
+
+    let rows = portal
+        .execute::<Error>(&client, 0)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(rows, [(3,)]);
+
+    drop(portal);
+    client.batch_execute("COMMIT").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_transaction_isolation() {
+    let client = connect("user=postgres").await;
+
+    set_transaction_isolation::<Error>(&client, IsolationLevel::Serializable, false)
+        .await
+        .unwrap();
+    assert!(matches!(
+        transaction_isolation::<Error>(&client).await.unwrap(),
+        IsolationLevel::Serializable
+    ));
+
+    client.batch_execute("BEGIN").await.unwrap();
+    set_transaction_isolation::<Error>(&client, IsolationLevel::RepeatableRead, true)
+        .await
+        .unwrap();
+    assert!(matches!(
+        transaction_isolation::<Error>(&client).await.unwrap(),
+        IsolationLevel::RepeatableRead
+    ));
+    client.batch_execute("COMMIT").await.unwrap();
+
+    assert!(matches!(
+        transaction_isolation::<Error>(&client).await.unwrap(),
+        IsolationLevel::Serializable
+    ));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_cursor_fetch_stream() {
+    let client = connect("user=postgres").await;
+    client.batch_execute("BEGIN").await.unwrap();
+
+    let mut cursor = declare_cursor::<Error>(&client, "numbers", "SELECT generate_series(1, 10)")
+        .await
+        .unwrap();
+    cursor.set_fetch_size(4);
+    assert_eq!(cursor.name(), "numbers");
+    assert_eq!(cursor.fetch_size(), 4);
+
+    let values = cursor
+        .fetch_stream::<Error>(&client)
+        .map_ok(|row| row.try_get(0).unwrap().unwrap().parse::<i32>().unwrap())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(values, (1..=10).collect::<Vec<_>>());
+
+    let rest = cursor
+        .fetch_stream::<Error>(&client)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(rest.is_empty());
+
+    client.batch_execute("COMMIT").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_copy_in_with_transform() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    let people = stream::iter(vec![(1, "alice"), (2, "bob")]);
+    let rows = copy_in_with_transform::<_, _, _, _, Error>(
+        &client,
+        "COPY foo (id, name) FROM STDIN WITH (FORMAT CSV)",
+        people,
+        |(id, name), buf| {
+            buf.extend_from_slice(format!("{},{}\n", id, name).as_bytes());
+            future::ready(Ok(()))
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, 2);
+
+    let rows = client
+        .query("SELECT id, name FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<_, i32>(0), 1);
+    assert_eq!(rows[0].get::<_, &str>(1), "alice");
+    assert_eq!(rows[1].get::<_, i32>(0), 2);
+    assert_eq!(rows[1].get::<_, &str>(1), "bob");
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pipeline_builder() {
+    let client = connect("user=postgres").await;
+
+    let mut pipeline = PipelineBuilder::new(&client);
+    let statement = pipeline
+        .prepare::<Error>("SELECT $1::INT4 + 1", "pipelined", &[])
+        .unwrap();
+    let value = Some(BytesMut::from(&41_i32.to_be_bytes()[..]));
+    let portal = pipeline
+        .bind::<_, Error>(&statement, "pipelined_portal", &[1], &[value], &[1])
+        .unwrap();
+    pipeline
+        .describe::<Error>(DescribeTarget::Portal("pipelined_portal".to_string()))
+        .unwrap()
+        .execute::<Error>(&portal, 0)
+        .unwrap()
+        .close_portal::<Error>("pipelined_portal")
+        .unwrap()
+        .close_statement::<Error>("pipelined")
+        .unwrap();
+    assert_eq!(pipeline.len(), 6);
+
+    let messages = pipeline
+        .sync::<Error>()
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    let mut itr = messages.into_iter();
+    assert!(matches!(itr.next(), Some(Message::ParseComplete)));
+    assert!(matches!(itr.next(), Some(Message::BindComplete)));
+    assert!(matches!(itr.next(), Some(Message::RowDescription(_))));
+    match itr.next() {
+        Some(Message::DataRow(body)) => {
+            let row = Row::new(body).unwrap();
+            assert_eq!(row.get(0), Some(&42_i32.to_be_bytes()[..]));
+        }
+        _ => panic!("unexpected message"),
+    }
+    assert!(matches!(itr.next(), Some(Message::CommandComplete(_))));
+    assert!(matches!(itr.next(), Some(Message::CloseComplete)));
+    assert!(matches!(itr.next(), Some(Message::CloseComplete)));
+    assert!(matches!(itr.next(), Some(Message::ReadyForQuery(_))));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_peekable_query_stream() {
+    let client = connect("user=postgres").await;
+
+    let _statement = prepare::<Error>(&client, "SELECT 1", "peeked", &[]).unwrap();
+    describe::<Error>(&client, DescribeTarget::Statement("peeked".to_string())).unwrap();
+    let mut stream = sync::<Error>(&client).await.unwrap().peekable();
+
+    assert!(matches!(
+        stream.peek().await,
+        Some(Ok(Message::ParseComplete))
+    ));
+    assert!(matches!(
+        stream.peek().await,
+        Some(Ok(Message::ParseComplete))
+    ));
+    assert!(matches!(
+        stream.next().await,
+        Some(Ok(Message::ParseComplete))
+    ));
+    assert!(matches!(
+        stream.next().await,
+        Some(Ok(Message::ParameterDescription(_)))
+    ));
+    assert!(matches!(
+        stream.peek().await,
+        Some(Ok(Message::RowDescription(_)))
+    ));
+    let rest = stream.try_collect::<Vec<_>>().await.unwrap();
+    assert_eq!(rest.len(), 2);
+    assert!(matches!(rest[0], Message::RowDescription(_)));
+    assert!(matches!(rest[1], Message::ReadyForQuery(_)));
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_replay_client() {
+    let exchanges = vec![RecordedExchange::new(
+        &b"S\0\0\0\x04"[..],
+        &b"Z\0\0\0\x05I"[..],
+    )];
+    let recording = encode_recording(&exchanges);
+    assert_eq!(decode_recording(&recording).unwrap(), exchanges);
+    assert!(decode_recording(&recording[..recording.len() - 1]).is_err());
+
+    let (client, replay) = ReplayClient::from_recording(&recording).unwrap();
+    let replay = tokio::spawn(replay);
+    keepalive::<Error>(&client).await.unwrap();
+    drop(client);
+    replay.await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+#[should_panic(expected = "request #0 does not match the recording at byte 0")]
+async fn t_replay_client_mismatch() {
+    let (client, replay) =
+        ReplayClient::new(vec![RecordedExchange::new(&b"H\0\0\0\x04"[..], &b""[..])]);
+    let replay = tokio::spawn(replay);
+    let _ = keepalive::<Error>(&client).await;
+    drop(client);
+    std::panic::resume_unwind(replay.await.unwrap_err().into_panic());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_trace_session() {
+    let client = connect("user=postgres").await;
+    let (traced, trace) = trace_session(&client, Vec::new());
+    let trace = tokio::spawn(trace);
+
+    let statement = prepare::<Error>(&traced, "SELECT 1, 2", "traced", &[]).unwrap();
+    describe::<Error>(&traced, DescribeTarget::Statement("traced".to_string())).unwrap();
+    sync::<Error>(&traced)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    drop(statement);
+
+    drop(traced);
+    let log = String::from_utf8(trace.await.unwrap()).unwrap();
+    let lines = log
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        &lines[..7],
+        [
+            "> Parse name=\"traced\" query=\"SELECT 1, 2\"",
+            "> Describe statement name=\"traced\"",
+            "> Sync",
+            "< ParseComplete",
+            "< ParameterDescription params=0",
+            "< RowDescription fields=2",
+            "< ReadyForQuery status=I",
+        ]
+    );
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_stream_with_row_limit() {
+    let client = connect("user=postgres").await;
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 10)", "limited", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let mut stream = Box::pin(sync::<Error>(&client).await.unwrap().with_row_limit(3));
+    let mut rows = 0;
+    let mut error = None;
+    while let Some(message) = stream.next().await {
+        match message {
+            Ok(Message::DataRow(_)) => rows += 1,
+            Ok(_) => {}
+            Err(e) => error = Some(e),
+        }
+    }
+    assert_eq!(rows, 3);
+    assert_eq!(stream.rows(), 3);
+    assert_eq!(
+        error.unwrap().to_string(),
+        "query returned more than 3 rows"
+    );
+
+    keepalive::<Error>(&client).await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_query_stream_with_row_limit_then_query() {
+    let client = connect("user=postgres").await;
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 10)", "limited", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let mut stream = Box::pin(sync::<Error>(&client).await.unwrap().with_row_limit(2));
+    loop {
+        if stream.next().await.unwrap().is_err() {
+            break;
+        }
+    }
+    // The rest of the batch is drained silently.
+    assert!(stream.next().await.is_none());
+    assert_eq!(stream.rows(), 2);
+
+    let (value,) = query_one::<(i32,), Error>(&client, "SELECT 42", &[])
+        .await
+        .unwrap();
+    assert_eq!(value, 42);
+    let row = client.query_one("SELECT 'after'", &[]).await.unwrap();
+    assert_eq!(row.get::<_, &str>(0), "after");
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_flush_raw_buf() {
+    let client = connect("user=postgres").await;
+
+    assert!(raw_buf_is_empty(&client));
+    let statement = prepare::<Error>(&client, "SELECT 1", "discarded", &[]).unwrap();
+    assert!(!raw_buf_is_empty(&client));
+
+    flush_raw_buf::<Error>(&client).unwrap();
+    assert!(raw_buf_is_empty(&client));
+    // Closing a statement which was never prepared is harmless.
+    drop(statement);
+
+    let count = client
+        .query_one(
+            "SELECT COUNT(*) FROM pg_prepared_statements WHERE name = 'discarded'",
+            &[],
+        )
+        .await
+        .unwrap()
+        .get::<_, i64>(0);
+    assert_eq!(count, 0);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_server_message() {
+    let client = connect("user=postgres").await;
+
+    let statement = prepare::<Error>(&client, "SELECT $1::INT4", "server_message", &[]).unwrap();
+    describe::<Error>(
+        &client,
+        DescribeTarget::Statement("server_message".to_string()),
+    )
+    .unwrap();
+    let value = Some(BytesMut::from(&7_i32.to_be_bytes()[..]));
+    let portal = bind::<_, Error>(&client, &statement, "", &[1], &[value], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let messages = sync::<Error>(&client)
+        .await
+        .unwrap()
+        .map_ok(ServerMessage::from)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 7);
+    assert!(matches!(messages[0], ServerMessage::ParseComplete));
+    assert_eq!(
+        messages[1].parameter_description(),
+        Some(&[Type::INT4.oid()][..])
+    );
+    let columns = messages[2].row_description().unwrap();
+    assert_eq!(columns[0].type_(), &Some(Type::INT4));
+    assert!(matches!(messages[3], ServerMessage::BindComplete));
+    assert_eq!(
+        messages[4].data_row().unwrap().get(0),
+        Some(&7_i32.to_be_bytes()[..])
+    );
+    let tag = messages[5].command_complete().unwrap();
+    assert_eq!(tag.as_str(), "SELECT 1");
+    assert_eq!(tag.command(), "SELECT");
+    assert_eq!(tag.rows(), Some(1));
+    assert_eq!(messages[6].ready_for_query(), Some(b'I'));
+    assert!(messages[6].data_row().is_none());
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_statistics() {
+    let client = connect("user=postgres").await;
+    let before = (
+        client.statistics().queries_executed(),
+        client.statistics().rows_fetched(),
+        client.statistics().parse_count(),
+        client.statistics().bind_count(),
+        client.statistics().sync_count(),
+        client.statistics().bytes_sent(),
+        client.statistics().bytes_received(),
+    );
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 3)", "statistics", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+
+    let statistics = client.statistics();
+    assert_eq!(statistics.queries_executed(), before.0 + 1);
+    assert_eq!(statistics.rows_fetched(), before.1 + 3);
+    assert_eq!(statistics.parse_count(), before.2 + 1);
+    assert_eq!(statistics.bind_count(), before.3 + 1);
+    assert_eq!(statistics.sync_count(), before.4 + 1);
+    assert!(statistics.bytes_sent() > before.5);
+    assert!(statistics.bytes_received() > before.6);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pg_error() {
+    let client = connect("user=postgres").await;
+
+    let query = "SELECT $1::TEXT, 1 / $2::INT";
+    let error = simple_query::<PgError>(&client, "SELECT 1 / 0")
+        .unwrap()
+        .try_collect::<Vec<Message>>()
+        .await
+        .err()
+        .unwrap();
+    assert!(error.query().is_none());
+    assert_eq!(error.inner().code(), Some(&SqlState::DIVISION_BY_ZERO));
+    assert_eq!(error.annotated_query(), None);
+
+    let params = vec!["x".repeat(40), "0".to_string()];
+    let error =
+        PgError::with_context(error.into_inner(), query, &params).with_statement_name("division");
+    assert_eq!(error.query(), Some(query));
+    assert_eq!(error.parameters(), &params[..]);
+    assert_eq!(error.statement_name(), Some("division"));
+    assert!(std::error::Error::source(&error).is_some());
+
+    let message = error.to_string();
+    assert!(message.contains("division by zero"));
+    assert!(message.contains(", statement `division`"));
+    assert!(message.contains(", query `SELECT $1::TEXT, 1 / $2::INT`"));
+    assert!(message.contains(&format!(
+        ", parameters [$1 = \"{}\"..., $2 = \"0\"]",
+        "x".repeat(32)
+    )));
+}
+
+#[cfg(feature = "raw")]
+#[test]
+fn t_scram_client() {
+    let (client, client_first) = ScramClient::start("scram_user", "password");
+    assert!(client_first.starts_with("n,,n=,r="));
+    let nonce = &client_first["n,,n=,r=".len()..];
+
+    client.finish("v=AAAA").unwrap_err();
+
+    // The nonce of another exchange is rejected.
+    let (client, _) = ScramClient::start("scram_user", "password");
+    let server_first = format!("r={}server,s=c2FsdA==,i=4096", nonce);
+    client.continue_(&server_first).unwrap_err();
+
+    let (client, client_first) = ScramClient::start("scram_user", "password");
+    let nonce = &client_first["n,,n=,r=".len()..];
+    let server_first = format!("r={}server,s=c2FsdA==,i=4096", nonce);
+    let (last, client_final) = client.continue_(&server_first).unwrap();
+    assert!(client_final.starts_with(&format!("c=biws,r={}server,p=", nonce)));
+    client.continue_(&server_first).unwrap_err();
+
+    last.finish("v=AAAA").unwrap_err();
+    last.finish("v=AAAA").unwrap_err();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_copy_in_rows() {
+    let client = connect("user=postgres").await;
+
+    client
+        .batch_execute("CREATE TEMPORARY TABLE foo (id INT, name TEXT)")
+        .await
+        .unwrap();
+
+    let rows = vec![
+        vec![Some("1".to_string()), Some("tab\there".to_string())],
+        vec![Some("2".to_string()), Some("line\nbreak\r\\N".to_string())],
+        vec![Some("3".to_string()), None],
+    ];
+    let copied = copy_in_rows::<Error>(&client, "foo", &["id", "name"], rows)
+        .await
+        .unwrap();
+    assert_eq!(copied, 3);
+
+    let rows = client
+        .query("SELECT name FROM foo ORDER BY id", &[])
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[0].get::<_, Option<&str>>(0), Some("tab\there"));
+    assert_eq!(rows[1].get::<_, Option<&str>>(0), Some("line\nbreak\r\\N"));
+    assert_eq!(rows[2].get::<_, Option<&str>>(0), None);
+
+    copy_in_rows::<Error>(&client, "foo", &["name;"], vec![])
+        .await
+        .unwrap_err();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_portal_is_suspended() {
+    let client = connect("user=postgres").await;
+    client.batch_execute("BEGIN").await.unwrap();
+
+    let statement =
+        prepare::<Error>(&client, "SELECT generate_series(1, 3)", "suspended", &[]).unwrap();
+    let portal = bind::<&[Option<BytesMut>; 0], Error>(
+        &client,
+        &statement,
+        "suspended_portal",
+        &[],
+        &[],
+        &[1],
+    )
+    .unwrap();
+    assert!(!portal.is_suspended());
+
+    execute::<Error>(&client, &portal, 2).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(portal.is_suspended());
+
+    execute::<Error>(&client, &portal, 2).unwrap();
+    sync::<Error>(&client)
+        .await
+        .unwrap()
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert!(!portal.is_suspended());
+    assert_eq!(portal.fetched_count(), 3);
+
+    client.batch_execute("COMMIT").await.unwrap();
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_simple_query_row_hashmap() {
+    let client = connect("user=postgres").await;
+
+    let messages: Vec<Message> = simple_query::<Error>(&client, "SELECT 'a' AS name, NULL AS note")
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+
+    let mut columns = None;
+    let mut row = None;
+    for message in messages {
+        match message {
+            Message::RowDescription(body) => {
+                columns = Some(SimpleColumn::from_row_description_body(body).unwrap())
+            }
+            Message::DataRow(body) => row = Some(SimpleQueryRow::new(body).unwrap()),
+            _ => {}
+        }
+    }
+    let row = row.unwrap();
+    row.as_hashmap().unwrap_err();
+
+    let row = row.with_columns(columns.unwrap());
+    let map = row.as_hashmap().unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["name"], Some("a"));
+    assert_eq!(map["note"], None);
+
+    let map = row.into_hashmap().unwrap();
+    assert_eq!(map["name"], Some("a".to_string()));
+    assert_eq!(map["note"], None);
+}
+
+#[cfg(feature = "raw")]
+#[tokio::test]
+async fn t_pending_request_count_ready_for_query() {
+    let client = connect("user=postgres").await;
+
+    let statement = prepare::<Error>(&client, "SELECT 1", "pending", &[]).unwrap();
+    let portal =
+        bind::<&[Option<BytesMut>; 0], Error>(&client, &statement, "", &[], &[], &[1]).unwrap();
+    execute::<Error>(&client, &portal, 0).unwrap();
+
+    let mut stream = Box::pin(sync::<Error>(&client).await.unwrap());
+    assert_eq!(client.pending_request_count(), 1);
+    loop {
+        if let Message::ReadyForQuery(_) = stream.next().await.unwrap().unwrap() {
+            break;
+        }
+    }
+    assert_eq!(client.pending_request_count(), 0);
+
+    drop(stream);
+    assert_eq!(client.pending_request_count(), 0);
+}